@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/mvg.rs"]
+mod mvg;
+
+// The first two bytes pick the HTTP status, the rest is the response body
+fuzz_target!(|data: &[u8]| {
+    let Some((status, body)) = data.split_first_chunk::<2>() else {
+        return;
+    };
+    let Ok(status) = http::StatusCode::from_u16(u16::from_be_bytes(*status)) else {
+        return;
+    };
+    let Ok(response) = http::Response::builder().status(status).body(body.to_vec()) else {
+        return;
+    };
+    let _ = futures_executor::block_on(mvg::departures_from_response(response.into()));
+});
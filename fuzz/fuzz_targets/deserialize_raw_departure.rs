@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The app is a single binary, so the targets compile its MVG module directly
+#[allow(dead_code)]
+#[path = "../../src/mvg.rs"]
+mod mvg;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = serde_json::from_slice::<Vec<mvg::RawDeparture>>(data);
+});
@@ -0,0 +1,264 @@
+// Runs get_response against canned MVG API responses from tests/fixtures
+use chrono::Duration;
+use mockito::{Matcher, Server, ServerGuard};
+use reqwest::StatusCode;
+
+use crate::test_helpers::{MockMvgServer, DEPARTURES, EMPTY, MALFORMED, UNKNOWN_TRANSPORT_TYPE};
+use crate::{get_response_from, Departure, MvgError, StationConfig, TransportType};
+
+async fn serve(status: usize, body: &str) -> ServerGuard {
+    let mut server = Server::new_async().await;
+    server
+        .mock("GET", "/departure")
+        .match_query(Matcher::UrlEncoded(
+            "globalId".to_string(),
+            StationConfig::default().global_id,
+        ))
+        .with_status(status)
+        .with_header("content-type", "application/json")
+        .with_body(body)
+        .create_async()
+        .await;
+    server
+}
+
+async fn fetch(server: &ServerGuard) -> Result<Vec<Departure>, MvgError> {
+    get_response_from(&server.url(), &StationConfig::default()).await
+}
+
+#[tokio::test]
+async fn happy_path_returns_sorted_departures() {
+    let server = serve(200, DEPARTURES).await;
+    let departures = fetch(&server).await.unwrap();
+    let labels = departures
+        .iter()
+        .map(|departure| departure.vehicle_label.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(labels, ["292", "S1", "S1"]);
+    assert!(departures
+        .windows(2)
+        .all(|pair| pair[0].displayed_time() <= pair[1].displayed_time()));
+}
+
+#[tokio::test]
+async fn empty_array_returns_no_departures() {
+    let server = serve(200, EMPTY).await;
+    assert!(fetch(&server).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_parse_error() {
+    let server = serve(200, MALFORMED).await;
+    assert!(matches!(fetch(&server).await, Err(MvgError::Parse(_))));
+}
+
+#[tokio::test]
+async fn unknown_transport_type_keeps_the_other_departures() {
+    let server = serve(200, UNKNOWN_TRANSPORT_TYPE).await;
+    let departures = fetch(&server).await.unwrap();
+    let transport_types = departures
+        .iter()
+        .map(|departure| (departure.vehicle_label.as_str(), &departure.transport_type))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        transport_types,
+        [
+            ("SB1", &TransportType::Unknown("SEILBAHN".to_string())),
+            ("S1", &TransportType::Sbahn)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn server_error_is_retryable() {
+    let server = serve(500, "").await;
+    let Err(error) = fetch(&server).await else {
+        panic!("HTTP 500 was not an error");
+    };
+    assert!(matches!(error, MvgError::Network(_)));
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn not_found_is_not_retryable() {
+    let server = serve(404, "").await;
+    let Err(error) = fetch(&server).await else {
+        panic!("HTTP 404 was not an error");
+    };
+    assert!(matches!(error, MvgError::Network(_)));
+    assert!(!error.is_retryable());
+}
+
+#[tokio::test]
+async fn too_many_requests_is_rate_limited() {
+    let server = serve(429, "").await;
+    assert!(matches!(fetch(&server).await, Err(MvgError::RateLimited)));
+}
+
+#[tokio::test]
+async fn cancelled_flag_is_kept() {
+    let server = serve(200, DEPARTURES).await;
+    let departures = fetch(&server).await.unwrap();
+    let cancelled = departures
+        .iter()
+        .filter(|departure| departure.cancelled)
+        .map(|departure| departure.destination.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(cancelled, ["Ostbahnhof"]);
+}
+
+#[tokio::test]
+async fn delay_is_propagated() {
+    let server = serve(200, DEPARTURES).await;
+    let departures = fetch(&server).await.unwrap();
+    let delayed = departures
+        .iter()
+        .find(|departure| departure.destination == "Freising")
+        .unwrap();
+    assert_eq!(delayed.delay(), Some(Duration::minutes(3)));
+}
+
+async fn fetch_from(server: &MockMvgServer) -> Result<Vec<Departure>, MvgError> {
+    get_response_from(&server.base_url(), &StationConfig::default()).await
+}
+
+#[tokio::test]
+async fn mock_server_serves_sorted_departures() {
+    let server = MockMvgServer::start().await;
+    let departures = fetch_from(&server).await.unwrap();
+    let destinations = departures
+        .iter()
+        .map(|departure| departure.destination.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(destinations, ["Unterschleißheim", "Freising", "Ostbahnhof"]);
+}
+
+#[tokio::test]
+async fn mock_server_records_the_departure_request() {
+    let server = MockMvgServer::start().await;
+    fetch_from(&server).await.unwrap();
+    let requests = server.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "GET");
+    assert_eq!(requests[0].path, "/departure");
+    assert_eq!(requests[0].query_value("globalId"), Some("de:09184:2000"));
+    assert_eq!(
+        requests[0].query_value("transportTypes"),
+        Some("SBAHN,UBAHN,BUS,TRAM,BAHN,REGIONAL_SCHNELL_BAHN,SEV")
+    );
+}
+
+#[tokio::test]
+async fn mock_server_receives_limit_and_offset() {
+    let server = MockMvgServer::start().await;
+    let config = StationConfig {
+        limit: 5,
+        offset_minutes: 10,
+        ..StationConfig::default()
+    };
+    get_response_from(&server.base_url(), &config)
+        .await
+        .unwrap();
+    let request = &server.requests()[0];
+    assert_eq!(request.query_value("limit"), Some("5"));
+    assert_eq!(request.query_value("offsetInMinutes"), Some("10"));
+}
+
+#[tokio::test]
+async fn mock_server_records_every_request_in_order() {
+    let server = MockMvgServer::start().await;
+    for global_id in ["de:09162:6", "de:09162:2"] {
+        let config = StationConfig {
+            global_id: global_id.to_string(),
+            ..StationConfig::default()
+        };
+        get_response_from(&server.base_url(), &config)
+            .await
+            .unwrap();
+    }
+    let global_ids = server
+        .requests()
+        .iter()
+        .map(|request| {
+            request
+                .query_value("globalId")
+                .unwrap_or_default()
+                .to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(global_ids, ["de:09162:6", "de:09162:2"]);
+}
+
+#[tokio::test]
+async fn mock_server_empty_fixture_returns_no_departures() {
+    let server = MockMvgServer::start().await.with_body(EMPTY);
+    assert!(fetch_from(&server).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn mock_server_malformed_fixture_is_a_parse_error() {
+    let server = MockMvgServer::start().await.with_body(MALFORMED);
+    let result = fetch_from(&server).await;
+    assert!(matches!(result, Err(MvgError::Parse(_))));
+}
+
+#[tokio::test]
+async fn mock_server_unavailable_is_retryable() {
+    let server = MockMvgServer::start()
+        .await
+        .with_status(StatusCode::SERVICE_UNAVAILABLE);
+    let Err(error) = fetch_from(&server).await else {
+        panic!("HTTP 503 was not an error");
+    };
+    assert!(matches!(error, MvgError::Network(_)));
+    assert!(error.is_retryable());
+}
+
+#[tokio::test]
+async fn mock_server_too_many_requests_is_rate_limited() {
+    let server = MockMvgServer::start()
+        .await
+        .with_status(StatusCode::TOO_MANY_REQUESTS);
+    let result = fetch_from(&server).await;
+    assert!(matches!(result, Err(MvgError::RateLimited)));
+}
+
+#[tokio::test]
+async fn mock_server_latency_delays_the_response() {
+    let latency = std::time::Duration::from_millis(200);
+    let server = MockMvgServer::start().await.with_latency(latency);
+    let started = std::time::Instant::now();
+    fetch_from(&server).await.unwrap();
+    assert!(started.elapsed() >= latency);
+}
+
+#[tokio::test]
+async fn mock_server_keeps_cancellation_and_delay() {
+    let server = MockMvgServer::start().await;
+    let departures = fetch_from(&server).await.unwrap();
+    let cancelled = departures
+        .iter()
+        .find(|departure| departure.destination == "Ostbahnhof")
+        .unwrap();
+    assert!(cancelled.cancelled);
+    assert_eq!(
+        cancelled.cancellation_reason.as_deref(),
+        Some("Fahrt fällt aus")
+    );
+    assert_eq!(cancelled.delay(), None);
+    let delayed = departures
+        .iter()
+        .find(|departure| departure.destination == "Freising")
+        .unwrap();
+    assert_eq!(delayed.delay(), Some(Duration::minutes(3)));
+}
+
+#[tokio::test]
+async fn mock_server_stops_when_dropped() {
+    let server = MockMvgServer::start().await;
+    let base_url = server.base_url();
+    drop(server);
+    tokio::task::yield_now().await;
+    let result = get_response_from(&base_url, &StationConfig::default()).await;
+    assert!(matches!(result, Err(MvgError::Network(_))));
+}
@@ -0,0 +1,98 @@
+use dioxus::prelude::*;
+
+use crate::{find_connections, Departure, StationConfig};
+
+const DEFAULT_TRAVEL_MINUTES: u32 = 10;
+
+fn departure_label(departure: &Departure) -> String {
+    format!(
+        "{} {} → {}",
+        departure.displayed_time().format("%H:%M"),
+        departure.vehicle_label,
+        departure.destination
+    )
+}
+
+#[inline_props]
+pub fn ConnectionGraph<'a>(
+    cx: Scope,
+    tabs: &'a [StationConfig],
+    active: usize,
+    board_departures: &'a UseRef<std::collections::HashMap<String, Vec<Departure>>>,
+) -> Element<'a> {
+    let travel_minutes = use_state(cx, || DEFAULT_TRAVEL_MINUTES);
+    let target = use_state(cx, || None::<usize>);
+    let from = &tabs[*active];
+    let targets = tabs
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| index != active)
+        .collect::<Vec<_>>();
+    let Some((target_index, to)) = target
+        .and_then(|index| targets.iter().find(|(candidate, _)| *candidate == index))
+        .or_else(|| targets.first())
+        .copied()
+    else {
+        return cx.render(rsx!(
+            div { class: "connection_graph", "Open a second station tab to see transfers" }
+        ));
+    };
+    let departures = board_departures.read();
+    let empty = Vec::new();
+    let from_departures = departures.get(&from.global_id).unwrap_or(&empty);
+    let to_departures = departures.get(&to.global_id).unwrap_or(&empty);
+    let pairs = find_connections(from_departures, to_departures, **travel_minutes);
+    cx.render(rsx!(
+        div {
+            class: "connection_graph",
+            div {
+                class: "connection_graph_settings",
+                "{from.label} → "
+                select {
+                    onchange: move |evt| target.set(evt.value.parse().ok()),
+                    targets.iter().map(|(index, tab)| rsx!(
+                        option {
+                            key: "{index}",
+                            value: "{index}",
+                            selected: *index == target_index,
+                            "{tab.label}"
+                        }
+                    ))
+                }
+                " ride "
+                input {
+                    onkeydown: |evt| evt.stop_propagation(),
+                    r#type: "number",
+                    min: "0",
+                    value: "{travel_minutes}",
+                    oninput: move |evt| {
+                        if let Ok(minutes) = evt.value.parse() {
+                            travel_minutes.set(minutes);
+                        }
+                    }
+                }
+                " min"
+            }
+            if pairs.is_empty() {
+                rsx!(div { "No transfers found" })
+            }
+            pairs.iter().map(|pair| {
+                let class = if pair.slack_minutes < 2 { "connection_pair tight" } else { "connection_pair" };
+                rsx!(
+                    div {
+                        key: "{pair.from_departure.planned_time}{pair.from_departure.vehicle_label}",
+                        class: class,
+                        div { class: "connection_tile", departure_label(pair.from_departure) }
+                        svg {
+                            class: "connection_arc",
+                            view_box: "0 0 60 24",
+                            path { d: "M 2 20 Q 30 -8 58 20" }
+                        }
+                        span { class: "connection_slack", "{pair.slack_minutes} min" }
+                        div { class: "connection_tile", departure_label(pair.to_departure) }
+                    }
+                )
+            })
+        }
+    ))
+}
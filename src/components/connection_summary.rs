@@ -0,0 +1,71 @@
+use chrono::prelude::*;
+use dioxus::prelude::*;
+
+use crate::{check_connection, ConnectionRule, ConnectionStatus, CONNECTION_CHECK_INTERVAL};
+
+fn clock(time: &DateTime<Local>) -> String {
+    time.format("%H:%M").to_string()
+}
+
+#[inline_props]
+pub fn ConnectionSummary(cx: Scope, rule: ConnectionRule) -> Element<'a> {
+    let latest_rule = use_ref(cx, || rule.clone());
+    if *latest_rule.read() != *rule {
+        *latest_rule.write_silent() = rule.clone();
+    }
+    let status = use_state(cx, || None::<ConnectionStatus>);
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let latest_rule = latest_rule.to_owned();
+        let status = status.to_owned();
+        async move {
+            loop {
+                let rule = latest_rule.read().clone();
+                status.set(Some(check_connection(&rule).await));
+                tokio::time::sleep(CONNECTION_CHECK_INTERVAL).await;
+            }
+        }
+    });
+    let route = format!(
+        "{} → {} ({} {}) → {}",
+        rule.from_station.label,
+        rule.via_station.label,
+        rule.target_line,
+        rule.target_direction,
+        rule.to_station.label
+    );
+    cx.render(rsx!(match status.get() {
+        Some(ConnectionStatus::Secure {
+            departure,
+            arrival,
+            connection,
+        }) => rsx!(
+            div {
+                class: "connection_summary secure",
+                b { "Connection secure " }
+                "{route}: leave {clock(departure)}, arrive {clock(arrival)}, "
+                "{rule.target_line} at {clock(connection)}"
+            }
+        ),
+        Some(ConnectionStatus::AtRisk {
+            departure,
+            arrival,
+            connection,
+        }) => {
+            let connection = connection.map_or_else(
+                || "no connection after arrival".to_string(),
+                |connection| format!("{} at {}", rule.target_line, clock(&connection)),
+            );
+            rsx!(
+                div {
+                    class: "connection_summary at_risk",
+                    b { "Connection at risk " }
+                    "{route}: leave {clock(departure)}, arrive {clock(arrival)}, {connection}"
+                }
+            )
+        }
+        Some(ConnectionStatus::Unknown(reason)) => rsx!(
+            div { class: "connection_summary", "{route}: checking the connection failed: {reason}" }
+        ),
+        None => rsx!(""),
+    }))
+}
@@ -0,0 +1,88 @@
+use chrono::prelude::*;
+use chrono::Duration;
+use dioxus::prelude::*;
+
+use crate::{get_day_schedule, platform_label, Departure};
+
+fn schedule_row<'a, 'b>(departure: &Departure, is_past: bool) -> LazyNodes<'a, 'b> {
+    let time = departure.planned_time.format("%H:%M");
+    let delay = match departure.delay() {
+        Some(delay) if delay > Duration::zero() => format!(" +{}", delay.num_minutes()),
+        _ => String::new(),
+    };
+    let platform = platform_label(departure.platform);
+    let class = match (is_past, departure.cancelled) {
+        (_, true) => "schedule_row cancelled",
+        (true, false) => "schedule_row past",
+        (false, false) => "schedule_row",
+    };
+    let key = departure.planned_time.timestamp();
+    rsx!(
+        div {
+            key: "{key}",
+            class: class,
+            "{time}{delay} · Pl. {platform}"
+        }
+    )
+}
+
+#[inline_props]
+pub fn DaySchedule<'a>(
+    cx: Scope,
+    station_id: String,
+    label: String,
+    destination: String,
+    on_close: EventHandler<'a, ()>,
+) -> Element<'a> {
+    let schedule = use_future(
+        cx,
+        (station_id, label, destination),
+        |(station_id, label, destination)| async move {
+            get_day_schedule(&station_id, &label, &destination).await
+        },
+    );
+    let eval = use_eval(cx);
+    let is_loaded = matches!(schedule.value(), Some(Ok(_)));
+    use_effect(cx, (&is_loaded,), |(is_loaded,)| {
+        let eval = eval.clone();
+        async move {
+            if !is_loaded {
+                return;
+            }
+            if let Err(e) = eval(
+                "document.getElementById('schedule_now')?.scrollIntoView({ block: 'center' });",
+            ) {
+                tracing::error!("Scrolling to the current time failed: {e:?}");
+            }
+        }
+    });
+    let body = match schedule.value() {
+        None => rsx!(div { class: "loader" }),
+        Some(Err(e)) => rsx!("Loading the schedule failed: {e}"),
+        Some(Ok(departures)) if departures.is_empty() => rsx!("No departures today"),
+        Some(Ok(departures)) => {
+            let now = Local::now();
+            let split = departures.partition_point(|departure| *departure.displayed_time() < now);
+            let (past, upcoming) = departures.split_at(split);
+            rsx!(
+                div {
+                    class: "schedule_timeline",
+                    past.iter().map(|departure| schedule_row(departure, true))
+                    hr { id: "schedule_now", class: "now_marker" }
+                    upcoming.iter().map(|departure| schedule_row(departure, false))
+                }
+            )
+        }
+    };
+    cx.render(rsx!(
+        div {
+            class: "day_schedule",
+            div {
+                class: "day_schedule_header",
+                b { "{label} → {destination}" }
+                button { title: "Close", onclick: move |_| on_close.call(()), "✕" }
+            }
+            body
+        }
+    ))
+}
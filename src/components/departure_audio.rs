@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+use dioxus::prelude::*;
+
+use crate::Departure;
+
+const WATCHED_DEPARTURES: usize = 3;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Chime {
+    Arrival,
+    Departure,
+    Alert,
+}
+
+impl Chime {
+    fn file(self) -> &'static str {
+        match self {
+            Chime::Arrival => "public/chime_arrival.ogg",
+            Chime::Departure => "public/chime_departure.ogg",
+            Chime::Alert => "public/chime_alert.ogg",
+        }
+    }
+}
+
+fn departure_key(departure: &Departure) -> (&str, &str, i64) {
+    (
+        departure.vehicle_label.as_str(),
+        departure.destination.as_str(),
+        departure.planned_time.timestamp(),
+    )
+}
+
+// Compares the first three departures: a new cancellation beats one leaving
+// the list, which beats a new one entering it
+pub fn chime_for_change(previous: &[Departure], current: &[Departure]) -> Option<Chime> {
+    let previous_top = previous.iter().take(WATCHED_DEPARTURES);
+    let current_top = current.iter().take(WATCHED_DEPARTURES);
+    let previously_cancelled = previous
+        .iter()
+        .filter(|departure| departure.cancelled)
+        .map(departure_key)
+        .collect::<HashSet<_>>();
+    if current_top.clone().any(|departure| {
+        departure.cancelled && !previously_cancelled.contains(&departure_key(departure))
+    }) {
+        return Some(Chime::Alert);
+    }
+    let current_keys = current.iter().map(departure_key).collect::<HashSet<_>>();
+    if previous_top
+        .clone()
+        .any(|departure| !current_keys.contains(&departure_key(departure)))
+    {
+        return Some(Chime::Departure);
+    }
+    let previous_top_keys = previous_top.map(departure_key).collect::<HashSet<_>>();
+    current_top
+        .map(departure_key)
+        .any(|key| !previous_top_keys.contains(&key))
+        .then_some(Chime::Arrival)
+}
+
+#[inline_props]
+pub fn DepartureAudio(cx: Scope, departures: Vec<Departure>, volume: u8) -> Element<'a> {
+    let eval = use_eval(cx);
+    let previous = use_ref(cx, || None::<Vec<Departure>>);
+    if previous.read().as_ref() != Some(departures) {
+        let chime = previous
+            .read()
+            .as_deref()
+            .and_then(|previous| chime_for_change(previous, departures));
+        *previous.write_silent() = Some(departures.clone());
+        if let Some(chime) = chime {
+            let script = format!(
+                "{{ const audio = new Audio({:?}); audio.volume = {}; audio.play(); }}",
+                chime.file(),
+                f64::from((*volume).min(100)) / 100.0
+            );
+            if let Err(e) = eval(&script) {
+                tracing::error!("Playing the {chime:?} chime failed: {e:?}");
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{at, departure};
+
+    fn board() -> Vec<Departure> {
+        vec![
+            departure("S1", "Freising", at(8, 0)),
+            departure("S8", "Flughafen", at(8, 5)),
+            departure("S2", "Erding", at(8, 10)),
+            departure("S4", "Geltendorf", at(8, 15)),
+        ]
+    }
+
+    #[test]
+    fn unchanged_board_is_silent() {
+        assert_eq!(chime_for_change(&board(), &board()), None);
+    }
+
+    #[test]
+    fn departed_train_chimes_departure() {
+        assert_eq!(
+            chime_for_change(&board(), &board()[1..]),
+            Some(Chime::Departure)
+        );
+    }
+
+    #[test]
+    fn line_entering_the_top_three_chimes_arrival() {
+        let mut current = board();
+        current.insert(1, departure("U6", "Klinikum", at(8, 2)));
+        assert_eq!(chime_for_change(&board(), &current), Some(Chime::Arrival));
+    }
+
+    #[test]
+    fn changes_below_the_top_three_are_silent() {
+        let mut current = board();
+        current.push(departure("S3", "Holzkirchen", at(8, 20)));
+        assert_eq!(chime_for_change(&board(), &current), None);
+    }
+
+    #[test]
+    fn new_cancellation_chimes_alert_over_a_departure() {
+        let mut current = board()[1..].to_vec();
+        current[0].cancelled = true;
+        assert_eq!(chime_for_change(&board(), &current), Some(Chime::Alert));
+    }
+
+    #[test]
+    fn known_cancellation_doesnt_alert_again() {
+        let mut previous = board();
+        previous[1].cancelled = true;
+        assert_eq!(chime_for_change(&previous, &previous), None);
+    }
+
+    #[test]
+    fn chimes_are_bundled_as_ogg() {
+        for chime in [Chime::Arrival, Chime::Departure, Chime::Alert] {
+            let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(chime.file());
+            let header = std::fs::read(&path).unwrap_or_else(|e| panic!("{path:?}: {e}"));
+            assert!(header.starts_with(b"OggS"), "{path:?} isn't an Ogg file");
+        }
+    }
+}
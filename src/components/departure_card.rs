@@ -0,0 +1,142 @@
+use dioxus::prelude::*;
+
+use crate::components::departure_tile::{
+    classify_service, line_badge_color, TileAction, TileProps,
+};
+use crate::components::search_panel::render_highlighted;
+use crate::{add_to_calendar, platform_label, Occupancy};
+
+fn occupancy_percent(occupancy: &Occupancy) -> u8 {
+    match occupancy {
+        Occupancy::Low => 33,
+        Occupancy::Medium => 66,
+        Occupancy::High => 100,
+        Occupancy::Unknown => 0,
+    }
+}
+
+pub fn DepartureCard(cx: Scope<TileProps>) -> Element {
+    let TileProps {
+        departure,
+        countdown,
+        shows_relative,
+        arrival,
+        walk_hint,
+        delay_trend,
+        walk_minutes,
+        query,
+        is_watched,
+        is_pinned,
+        actions,
+        ..
+    } = cx.props;
+    let state_classes = cx.props.state_classes();
+    let (badge_background, badge_text) =
+        line_badge_color(&departure.transport_type, &departure.vehicle_label);
+    let service_class = classify_service(
+        departure.train_type.as_deref().unwrap_or_default(),
+        &departure.transport_type,
+    );
+    let planned_time = departure.displayed_time().format("%H:%M");
+    let delay = match departure.delay() {
+        Some(delay) if delay.num_minutes() > 0 => format!("+{}", delay.num_minutes()),
+        _ => String::new(),
+    };
+    let (realtime_icon, realtime_title) = if departure.is_real_time {
+        ("📡", "Real-time data")
+    } else {
+        ("🕒", "Scheduled time only")
+    };
+    let occupancy = occupancy_percent(&departure.occupancy);
+    let destination = render_highlighted(&departure.destination, query);
+    cx.render(rsx!(
+        div {
+            class: "departure_card {state_classes}",
+            onclick: move |_| actions.send(TileAction::ToggleWatched(departure.clone())),
+            div {
+                class: "card_header",
+                span {
+                    class: "card_line_badge {service_class.css_class()}",
+                    style: "background-color: {badge_background}; color: {badge_text}",
+                    title: "Show only this line",
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        actions.send(TileAction::Focus(departure.vehicle_label.clone(), departure.destination.clone()));
+                    },
+                    "{departure.vehicle_label}"
+                }
+                span { class: "card_destination", destination }
+                if *is_watched {
+                    rsx!(span { class: "watch_star", "★" })
+                }
+                span { class: "realtime_indicator", title: "{realtime_title}", "{realtime_icon}" }
+            }
+            div {
+                class: "card_body",
+                span { class: "card_time", "{countdown}" }
+                if *shows_relative {
+                    rsx!(span { class: "card_planned_time", "{planned_time}" })
+                }
+                if !delay.is_empty() {
+                    rsx!(
+                        span { class: "card_delay", "{delay}" }
+                        span { class: "delay_trend", title: delay_trend.title(), "{delay_trend.symbol()}" }
+                    )
+                }
+                span { class: "card_platform", "Pl. {platform_label(departure.platform)}" }
+            }
+            if let Some(reason) = &departure.cancellation_reason {
+                rsx!(div { class: "cancellation_reason", "{reason}" })
+            }
+            div {
+                class: "card_occupancy",
+                title: "Occupancy",
+                div { class: departure.occupancy.css_class(), style: "width: {occupancy}%" }
+            }
+            div {
+                class: "card_footer",
+                if !walk_hint.is_empty() {
+                    rsx!(span { class: "walk_hint", "{walk_hint} " })
+                }
+                if !arrival.is_empty() {
+                    rsx!(span { class: "arrival_time", "Arrive ~{arrival} " })
+                }
+                button {
+                    title: "Day schedule",
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        actions.send(TileAction::ShowSchedule(departure.vehicle_label.clone(), departure.destination.clone()));
+                    },
+                    "🗓"
+                }
+                button {
+                    title: "Add to calendar",
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        if let Err(e) = add_to_calendar(departure, *walk_minutes) {
+                            tracing::error!("Adding departure to calendar failed: {e}");
+                        }
+                    },
+                    "+📅"
+                }
+                button {
+                    class: if *is_pinned { "pin_button active" } else { "pin_button" },
+                    title: if *is_pinned { "Unpin" } else { "Pin to top" },
+                    onclick: move |evt| {
+                        evt.stop_propagation();
+                        actions.send(TileAction::TogglePinned(departure.clone()));
+                    },
+                    "📌"
+                }
+            }
+            if !departure.messages.is_empty() {
+                rsx!(
+                    ul {
+                        class: "messages",
+                        departure.messages.iter().map(|message| rsx!(li { "{message}" }))
+                    }
+                )
+            }
+        }
+    ))
+}
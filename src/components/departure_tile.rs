@@ -0,0 +1,664 @@
+use std::time::{Duration, Instant};
+
+use dioxus::prelude::*;
+
+use crate::components::search_panel::render_highlighted;
+
+use crate::{
+    add_to_calendar, is_mvv_network, is_night_service, platform_label, update_settings, Config,
+    DelayTrend, Departure, TransportType, WatchConfig,
+};
+
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ServiceClass {
+    Regional,
+    Express,
+    Local,
+    Unknown,
+}
+
+impl ServiceClass {
+    pub fn css_class(self) -> &'static str {
+        match self {
+            ServiceClass::Regional => "service_regional",
+            ServiceClass::Express => "service_express",
+            ServiceClass::Local => "service_local",
+            ServiceClass::Unknown => "service_unknown",
+        }
+    }
+}
+
+pub fn classify_service(train_type: &str, transport_type: &TransportType) -> ServiceClass {
+    match train_type.trim().to_ascii_uppercase().as_str() {
+        "ICE" | "IC" | "EC" | "RJ" | "RJX" | "IRE" | "RE" | "FLX" => ServiceClass::Express,
+        "RB" | "BRB" | "ALX" | "M" => ServiceClass::Regional,
+        "S" | "U" => ServiceClass::Local,
+        _ => match transport_type {
+            TransportType::RegionalSchnellBahn => ServiceClass::Express,
+            TransportType::RegionalBahn => ServiceClass::Regional,
+            TransportType::Sbahn
+            | TransportType::Ubahn
+            | TransportType::Bus
+            | TransportType::Tram
+            | TransportType::Sev => ServiceClass::Local,
+            TransportType::Unknown(_) => ServiceClass::Unknown,
+        },
+    }
+}
+
+const DEFAULT_BADGE_COLORS: (&str, &str) = ("#8a8a8a", "#ffffff");
+const SBAHN_BADGE_COLORS: (&str, &str) = ("#408335", "#ffffff");
+const TRAM_BADGE_COLORS: (&str, &str) = ("#e30613", "#ffffff");
+const BUS_BADGE_COLORS: (&str, &str) = ("#00586a", "#ffffff");
+
+static UBAHN_BADGE_COLORS: phf::Map<&'static str, (&'static str, &'static str)> = phf::phf_map! {
+    "U1" => ("#52822f", "#ffffff"),
+    "U2" => ("#c20831", "#ffffff"),
+    "U3" => ("#ec6726", "#ffffff"),
+    "U4" => ("#00a984", "#ffffff"),
+    "U5" => ("#bc7a00", "#ffffff"),
+    "U6" => ("#0065af", "#ffffff"),
+    "U7" => ("#52822f", "#ffffff"),
+    "U8" => ("#c20831", "#ffffff"),
+};
+
+// (background, text) of the line badge in MVG's brand colours
+pub fn line_badge_color(
+    transport_type: &TransportType,
+    label: &str,
+) -> (&'static str, &'static str) {
+    match transport_type {
+        TransportType::Sbahn => SBAHN_BADGE_COLORS,
+        TransportType::Ubahn => UBAHN_BADGE_COLORS
+            .get(label.trim())
+            .copied()
+            .unwrap_or(DEFAULT_BADGE_COLORS),
+        TransportType::Tram => TRAM_BADGE_COLORS,
+        TransportType::Bus => BUS_BADGE_COLORS,
+        _ => DEFAULT_BADGE_COLORS,
+    }
+}
+
+pub fn toggle_watched(watch_config: &UseState<WatchConfig>, departure: &Departure) {
+    let key = (
+        departure.vehicle_label.clone(),
+        departure.destination.clone(),
+    );
+    watch_config.with_mut(|config| {
+        if !config.lines.remove(&key) {
+            config.lines.insert(key);
+        }
+        if let Err(e) = config.save() {
+            tracing::error!("Saving watch config failed: {e}");
+        }
+    });
+}
+
+pub fn toggle_pinned(settings: &UseState<Config>, departure: &Departure) {
+    let key = departure.pin_key();
+    update_settings(settings, |settings| {
+        if let Some(index) = settings.pinned.iter().position(|pinned| *pinned == key) {
+            settings.pinned.remove(index);
+        } else {
+            settings.pinned.push(key);
+        }
+    });
+}
+
+// What a tile asks its board to change. Tiles send these instead of holding the
+// board's state, so their props only change when what they show does.
+#[derive(Clone, PartialEq, Debug)]
+pub enum TileAction {
+    ToggleWatched(Departure),
+    TogglePinned(Departure),
+    Focus(String, String),
+    ShowSchedule(String, String),
+}
+
+// Shared by the compact tile and the card, so switching the display style keeps the props
+#[derive(Props, PartialEq)]
+pub struct TileProps {
+    pub departure: Departure,
+    pub countdown: String,
+    pub urgency: &'static str,
+    pub shows_relative: bool,
+    pub imminent: bool,
+    pub arrival: String,
+    pub walk_hint: String,
+    pub delay_trend: DelayTrend,
+    pub walk_minutes: u8,
+    pub query: String,
+    pub is_watched: bool,
+    pub is_pinned: bool,
+    pub time_changed: bool,
+    pub actions: Coroutine<TileAction>,
+}
+
+impl TileProps {
+    // The urgency and state classes both display styles are styled by
+    pub fn state_classes(&self) -> String {
+        let mut classes = self.urgency.to_string();
+        for (applies, class) in [
+            (self.departure.cancelled, " cancelled"),
+            (self.departure.sev, " sev"),
+            (self.imminent, " imminent"),
+            (self.time_changed, " time_changed"),
+        ] {
+            if applies {
+                classes.push_str(class);
+            }
+        }
+        classes
+    }
+}
+
+pub fn ResponseTile(cx: Scope<TileProps>) -> Element {
+    let TileProps {
+        departure,
+        countdown,
+        shows_relative,
+        arrival,
+        walk_hint,
+        delay_trend,
+        walk_minutes,
+        query,
+        is_watched,
+        is_pinned,
+        actions,
+        ..
+    } = cx.props;
+    let show_messages = use_state(cx, || false);
+    let show_context_menu = use_state(cx, || false);
+    let pressed_at = use_ref(cx, || None::<Instant>);
+    let long_pressed = use_ref(cx, || false);
+    let state_classes = cx.props.state_classes();
+    let displayed_time = departure.displayed_time().format("%H:%M");
+    let service_class = classify_service(
+        departure.train_type.as_deref().unwrap_or_default(),
+        &departure.transport_type,
+    );
+    // Regional and express trains keep their service class colours
+    let badge_style = match service_class {
+        ServiceClass::Regional | ServiceClass::Express => String::new(),
+        ServiceClass::Local | ServiceClass::Unknown => {
+            let (background, text) =
+                line_badge_color(&departure.transport_type, &departure.vehicle_label);
+            format!("background-color: {background}; color: {text}")
+        }
+    };
+    let delay_info = match departure
+        .effective_delay_minutes()
+        .map(|minutes| minutes.round() as i64)
+    {
+        Some(minutes) if minutes < 0 => {
+            rsx!(span { class: "early", title: "Leaves early", "(−{-minutes})" })
+        }
+        Some(minutes) => rsx!("(+ {minutes})"),
+        None => rsx!(""),
+    };
+    let time_info = if !*shows_relative {
+        delay_info
+    } else if departure.delay_seconds.is_some() {
+        rsx!("{displayed_time} ", delay_info)
+    } else if !departure.cancelled {
+        rsx!(i {"{displayed_time}"})
+    } else {
+        rsx!("{displayed_time}")
+    };
+    let destination = render_highlighted(&departure.destination, query);
+    let inner = rsx!(
+        span { class: departure.occupancy.css_class() },
+        if departure.is_real_time {
+            rsx!(span { class: "realtime_indicator live", title: "Real-time data", "📡 " })
+        } else {
+            rsx!(span { class: "realtime_indicator", title: "Scheduled time only", "🕒 " })
+        },
+        "{countdown} ",
+        span { class: "absolute_time", time_info },
+        if departure.delay_seconds.is_some() {
+            rsx!(span { class: "delay_trend", title: delay_trend.title(), " {delay_trend.symbol()}" })
+        },
+        " [",
+        b {
+            class: "line_label {service_class.css_class()}",
+            style: "{badge_style}",
+            title: "Show only this line",
+            onclick: move |evt| {
+                evt.stop_propagation();
+                actions.send(TileAction::Focus(departure.vehicle_label.clone(), departure.destination.clone()));
+            },
+            "{departure.vehicle_label}"
+        },
+        a {
+            class: "line_link",
+            href: "{departure.mvg_web_url()}",
+            title: "Timetable",
+            onclick: move |evt| evt.stop_propagation(),
+            " ⓘ"
+        },
+        if departure.sev {
+            rsx!(span { class: "sev_badge", " SEV" })
+        },
+        if is_night_service(&departure.vehicle_label) {
+            rsx!(span { class: "night_service_badge", title: "Night service", " 🌙" })
+        },
+        span { class: "platform", " Pl. {platform_label(departure.platform)}" },
+        if !is_mvv_network(&departure.network) {
+            rsx!(span { class: "network_badge", title: "{departure.network}", " ↗" })
+        },
+        " ",
+        destination,
+        "] "
+    );
+    cx.render(rsx!(
+        div {
+            class: "response_tile {state_classes}",
+            prevent_default: "oncontextmenu",
+            oncontextmenu: move |_| show_context_menu.set(!show_context_menu.get()),
+            onmousedown: move |_| *pressed_at.write_silent() = Some(Instant::now()),
+            onmouseup: move |_| {
+                let pressed_for = pressed_at.write_silent().take().map(|pressed_at| pressed_at.elapsed());
+                if pressed_for.is_some_and(|pressed_for| pressed_for >= LONG_PRESS) {
+                    *long_pressed.write_silent() = true;
+                    actions.send(TileAction::ShowSchedule(departure.vehicle_label.clone(), departure.destination.clone()));
+                }
+            },
+            onclick: move |_| {
+                if std::mem::take(&mut *long_pressed.write_silent()) {
+                    return;
+                }
+                actions.send(TileAction::ToggleWatched(departure.clone()));
+            },
+            if *is_watched {
+                rsx!(span { class: "watch_star", "★ " })
+            }
+            if departure.cancelled {
+                rsx!(s { inner})
+            } else {
+                rsx!(inner)
+            }
+            if let Some(reason) = &departure.cancellation_reason {
+                rsx!(div { class: "cancellation_reason", "{reason}" })
+            }
+            if !walk_hint.is_empty() {
+                rsx!(span { class: "walk_hint", " {walk_hint}" })
+            }
+            if !arrival.is_empty() {
+                rsx!(div { class: "arrival_time", "Arrive ~{arrival}" })
+            }
+            if !departure.messages.is_empty() {
+                rsx!(
+                    span {
+                        class: "messages_toggle",
+                        onclick: move |evt| {
+                            evt.stop_propagation();
+                            show_messages.set(!show_messages.get());
+                        },
+                        "⚠"
+                    }
+                )
+            }
+            if *show_context_menu.get() {
+                rsx!(
+                    div {
+                        class: "tile_context_menu",
+                        button {
+                            onclick: move |evt| {
+                                evt.stop_propagation();
+                                show_context_menu.set(false);
+                                if let Err(e) = add_to_calendar(departure, *walk_minutes) {
+                                    tracing::error!("Adding departure to calendar failed: {e}");
+                                }
+                            },
+                            "Add to calendar"
+                        }
+                        button {
+                            onclick: move |evt| {
+                                evt.stop_propagation();
+                                show_context_menu.set(false);
+                                actions.send(TileAction::TogglePinned(departure.clone()));
+                            },
+                            if *is_pinned { "Unpin" } else { "Pin to top" }
+                        }
+                    }
+                )
+            }
+            if *show_messages.get() {
+                rsx!(
+                    ul {
+                        class: "messages",
+                        departure.messages.iter().map(|message| rsx!(li { "{message}" }))
+                    }
+                )
+            }
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use chrono::Duration;
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::fixtures;
+    use crate::test_helpers::{at, departure};
+
+    thread_local! {
+        static TILE_RENDERS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    fn tile_renders() -> usize {
+        TILE_RENDERS.with(Cell::get)
+    }
+
+    // Counts and renders a tile, so the count follows how the VirtualDom memoizes TileProps
+    fn CountingTile(cx: Scope<TileProps>) -> Element {
+        TILE_RENDERS.with(|renders| renders.set(renders.get() + 1));
+        ResponseTile(cx)
+    }
+
+    // A tile as the board shows it at 08:00, so tests only set what they're about
+    fn tile_props(departure: Departure, actions: &Coroutine<TileAction>) -> TileProps {
+        TileProps {
+            countdown: format!("in {} min", departure.minutes_until(at(8, 0))),
+            departure,
+            urgency: "normal",
+            shows_relative: false,
+            imminent: false,
+            arrival: String::new(),
+            walk_hint: String::new(),
+            delay_trend: DelayTrend::default(),
+            walk_minutes: 0,
+            query: String::new(),
+            is_watched: false,
+            is_pinned: false,
+            time_changed: false,
+            actions: actions.clone(),
+        }
+    }
+
+    fn Board(cx: Scope<Rc<RefCell<Vec<Departure>>>>) -> Element {
+        let actions = use_coroutine(cx, |_rx: UnboundedReceiver<TileAction>| async {});
+        let departures = cx.props.borrow().clone();
+        cx.render(rsx!(departures.into_iter().map(|departure| {
+            let key = departure.planned_time.to_string();
+            let props = tile_props(departure, actions);
+            rsx!(CountingTile {
+                key: "{key}",
+                ..props
+            })
+        })))
+    }
+
+    fn board() -> Rc<RefCell<Vec<Departure>>> {
+        Rc::new(RefCell::new(
+            (0..14)
+                .map(|minute| departure("S1", "Freising", at(8, minute)))
+                .collect_vec(),
+        ))
+    }
+
+    #[test]
+    fn only_the_changed_tile_renders_again() {
+        let board = board();
+        let mut dom = VirtualDom::new_with_props(Board, board.clone());
+        let _ = dom.rebuild();
+        assert_eq!(tile_renders(), 14);
+        {
+            let departures = &mut board.borrow_mut();
+            departures[3].actual_time += Duration::minutes(2);
+            departures[3].delay_seconds = Some(120);
+        }
+        dom.mark_dirty(ScopeId(0));
+        let _ = dom.render_immediate();
+        assert_eq!(tile_renders(), 15);
+    }
+
+    #[test]
+    fn unchanged_tiles_skip_board_renders() {
+        let mut dom = VirtualDom::new_with_props(Board, board());
+        let _ = dom.rebuild();
+        dom.mark_dirty(ScopeId(0));
+        let _ = dom.render_immediate();
+        assert_eq!(tile_renders(), 14);
+    }
+
+    #[test]
+    fn state_classes_list_every_state() {
+        let mut departure = departure("S1", "Freising", at(8, 0));
+        departure.cancelled = true;
+        departure.sev = true;
+        let mut dom = VirtualDom::new_with_props(ClassesOf, departure);
+        let _ = dom.rebuild();
+        assert_eq!(
+            STATE_CLASSES.with(|classes| classes.take()),
+            "urgent cancelled sev imminent time_changed"
+        );
+    }
+
+    thread_local! {
+        static STATE_CLASSES: RefCell<String> = const { RefCell::new(String::new()) };
+    }
+
+    // TileProps hold a coroutine, so they can only be built inside a VirtualDom
+    fn ClassesOf(cx: Scope<Departure>) -> Element {
+        let actions = use_coroutine(cx, |_rx: UnboundedReceiver<TileAction>| async {});
+        let props = TileProps {
+            urgency: "urgent",
+            imminent: true,
+            time_changed: true,
+            ..tile_props(cx.props.clone(), actions)
+        };
+        STATE_CLASSES.with(|classes| *classes.borrow_mut() = props.state_classes());
+        None
+    }
+
+    #[test]
+    fn long_distance_and_regional_express_trains_are_express() {
+        for train_type in ["ICE", "IC", "EC", "RJ", "RJX", "IRE", "RE", "FLX"] {
+            assert_eq!(
+                classify_service(train_type, &TransportType::RegionalBahn),
+                ServiceClass::Express,
+                "{train_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn regional_trains_are_regional() {
+        for train_type in ["RB", "BRB", "ALX", "M"] {
+            assert_eq!(
+                classify_service(train_type, &TransportType::RegionalBahn),
+                ServiceClass::Regional,
+                "{train_type}"
+            );
+        }
+    }
+
+    #[test]
+    fn s_and_u_bahn_are_local() {
+        assert_eq!(
+            classify_service("S", &TransportType::Sbahn),
+            ServiceClass::Local
+        );
+        assert_eq!(
+            classify_service("U", &TransportType::Ubahn),
+            ServiceClass::Local
+        );
+    }
+
+    #[test]
+    fn train_type_ignores_case_and_whitespace() {
+        assert_eq!(
+            classify_service(" re ", &TransportType::RegionalBahn),
+            ServiceClass::Express
+        );
+        assert_eq!(
+            classify_service("rb", &TransportType::RegionalBahn),
+            ServiceClass::Regional
+        );
+    }
+
+    #[test]
+    fn missing_train_type_falls_back_to_the_transport_type() {
+        assert_eq!(
+            classify_service("", &TransportType::RegionalSchnellBahn),
+            ServiceClass::Express
+        );
+        assert_eq!(
+            classify_service("", &TransportType::RegionalBahn),
+            ServiceClass::Regional
+        );
+        assert_eq!(
+            classify_service("", &TransportType::Bus),
+            ServiceClass::Local
+        );
+        assert_eq!(
+            classify_service("", &TransportType::Tram),
+            ServiceClass::Local
+        );
+        assert_eq!(
+            classify_service("", &TransportType::Sev),
+            ServiceClass::Local
+        );
+    }
+
+    #[test]
+    fn unknown_train_and_transport_types_are_unknown() {
+        assert_eq!(
+            classify_service("XYZ", &TransportType::Unknown("SEILBAHN".to_string())),
+            ServiceClass::Unknown
+        );
+    }
+
+    #[test]
+    fn unknown_train_type_uses_the_transport_type() {
+        assert_eq!(
+            classify_service("XYZ", &TransportType::Sbahn),
+            ServiceClass::Local
+        );
+    }
+
+    #[test]
+    fn service_classes_have_distinct_css_classes() {
+        let classes = [
+            ServiceClass::Regional,
+            ServiceClass::Express,
+            ServiceClass::Local,
+            ServiceClass::Unknown,
+        ]
+        .map(ServiceClass::css_class);
+        assert_eq!(classes.iter().unique().count(), classes.len());
+    }
+
+    fn SnapshotTile(cx: Scope<Departure>) -> Element {
+        let actions = use_coroutine(cx, |_rx: UnboundedReceiver<TileAction>| async {});
+        let props = tile_props(cx.props.clone(), actions);
+        cx.render(rsx!(ResponseTile { ..props }))
+    }
+
+    fn render(departure: Departure) -> String {
+        let mut dom = VirtualDom::new_with_props(SnapshotTile, departure);
+        let _ = dom.rebuild();
+        dioxus_ssr::render(&dom)
+    }
+
+    #[test]
+    fn on_time_tile() {
+        insta::assert_snapshot!(render(fixtures::on_time()));
+    }
+
+    #[test]
+    fn delayed_tile() {
+        insta::assert_snapshot!(render(fixtures::delayed()));
+    }
+
+    #[test]
+    fn early_tile() {
+        insta::assert_snapshot!(render(fixtures::early()));
+    }
+
+    #[test]
+    fn cancelled_tile() {
+        insta::assert_snapshot!(render(fixtures::cancelled()));
+    }
+
+    #[test]
+    fn sev_tile() {
+        insta::assert_snapshot!(render(fixtures::sev()));
+    }
+
+    #[test]
+    fn scheduled_only_tile() {
+        insta::assert_snapshot!(render(fixtures::scheduled_only()));
+    }
+
+    #[test]
+    fn regional_express_tile() {
+        insta::assert_snapshot!(render(fixtures::regional_express()));
+    }
+
+    #[test]
+    fn every_u_bahn_line_has_its_own_colour() {
+        for label in ["U1", "U2", "U3", "U4", "U5", "U6", "U7", "U8"] {
+            assert_ne!(
+                line_badge_color(&TransportType::Ubahn, label),
+                DEFAULT_BADGE_COLORS,
+                "{label}"
+            );
+        }
+        let first_six = ["U1", "U2", "U3", "U4", "U5", "U6"]
+            .map(|label| line_badge_color(&TransportType::Ubahn, label));
+        assert_eq!(first_six.iter().unique().count(), first_six.len());
+    }
+
+    #[test]
+    fn u_bahn_label_ignores_surrounding_whitespace() {
+        assert_eq!(
+            line_badge_color(&TransportType::Ubahn, " U6 "),
+            line_badge_color(&TransportType::Ubahn, "U6")
+        );
+    }
+
+    #[test]
+    fn unknown_u_bahn_line_falls_back_to_grey() {
+        assert_eq!(
+            line_badge_color(&TransportType::Ubahn, "U9"),
+            DEFAULT_BADGE_COLORS
+        );
+        assert_eq!(DEFAULT_BADGE_COLORS.0, "#8a8a8a");
+    }
+
+    #[test]
+    fn other_transport_types_share_one_colour_per_type() {
+        assert_eq!(
+            line_badge_color(&TransportType::Sbahn, "S8"),
+            line_badge_color(&TransportType::Sbahn, "S1")
+        );
+        assert_eq!(
+            line_badge_color(&TransportType::Tram, "17"),
+            TRAM_BADGE_COLORS
+        );
+        assert_eq!(
+            line_badge_color(&TransportType::Bus, "54"),
+            BUS_BADGE_COLORS
+        );
+        for transport_type in [
+            TransportType::RegionalBahn,
+            TransportType::Sev,
+            TransportType::Unknown("SEILBAHN".to_string()),
+        ] {
+            assert_eq!(
+                line_badge_color(&transport_type, "X"),
+                DEFAULT_BADGE_COLORS,
+                "{transport_type:?}"
+            );
+        }
+    }
+}
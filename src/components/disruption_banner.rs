@@ -0,0 +1,52 @@
+use dioxus::prelude::*;
+
+use crate::Disruption;
+
+#[inline_props]
+pub fn DisruptionBanner<'a>(
+    cx: Scope,
+    disruptions: Vec<Disruption>,
+    on_dismiss: EventHandler<'a, ()>,
+) -> Element<'a> {
+    let expanded = use_state(cx, || false);
+    let most_severe = disruptions.first()?;
+    let more = match disruptions.len() {
+        1 => String::new(),
+        count => format!(" (+{} more)", count - 1),
+    };
+    cx.render(rsx!(
+        div {
+            class: "disruption_banner {most_severe.severity.css_class()}",
+            div {
+                class: "disruption_header",
+                span { class: "disruption_title", "⚠ {most_severe.title}{more}" }
+                button {
+                    onclick: move |_| expanded.set(!expanded),
+                    if **expanded { "Less info" } else { "More info" }
+                }
+                button { title: "Dismiss", onclick: move |_| on_dismiss.call(()), "✕" }
+            }
+            if **expanded {
+                rsx!(
+                    disruptions.iter().map(|disruption| {
+                        let lines = disruption.lines.join(", ");
+                        let valid_from = disruption.valid_from.format("%d.%m. %H:%M");
+                        let valid_to = disruption.valid_to.map_or_else(
+                            || "further notice".to_string(),
+                            |valid_to| valid_to.format("%d.%m. %H:%M").to_string(),
+                        );
+                        rsx!(
+                            div {
+                                key: "{disruption.title}{disruption.valid_from}",
+                                class: "disruption_details",
+                                b { "{disruption.title}" }
+                                div { class: "disruption_meta", "{lines} · {valid_from} until {valid_to}" }
+                                div { "{disruption.text}" }
+                            }
+                        )
+                    })
+                )
+            }
+        }
+    ))
+}
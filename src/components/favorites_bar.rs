@@ -0,0 +1,88 @@
+use std::time::{Duration, Instant};
+
+use dioxus::prelude::*;
+
+use crate::StationConfig;
+
+pub const MAX_VISIBLE_FAVORITES: usize = 5;
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+#[inline_props]
+pub fn FavoritesBar<'a>(
+    cx: Scope,
+    favorites: &'a [StationConfig],
+    active_global_id: &'a str,
+    on_select: EventHandler<'a, StationConfig>,
+    on_remove: EventHandler<'a, usize>,
+    on_move: EventHandler<'a, (usize, usize)>,
+) -> Element<'a> {
+    let dragged = use_ref(cx, || None::<usize>);
+    let pressed_at = use_ref(cx, || None::<Instant>);
+    let long_pressed = use_ref(cx, || false);
+    if favorites.is_empty() {
+        return None;
+    }
+    let overflow = favorites.get(MAX_VISIBLE_FAVORITES..).unwrap_or_default();
+    cx.render(rsx!(
+        div {
+            class: "favorites_bar",
+            favorites.iter().take(MAX_VISIBLE_FAVORITES).enumerate().map(|(index, favorite)| {
+                let class = if favorite.global_id == *active_global_id {
+                    "favorite active"
+                } else {
+                    "favorite"
+                };
+                rsx!(
+                    button {
+                        key: "{favorite.global_id}",
+                        class: class,
+                        title: "Alt+{index + 1}, right-click or hold to remove, drag to reorder",
+                        draggable: "true",
+                        prevent_default: "oncontextmenu ondragover",
+                        ondragstart: move |_| *dragged.write_silent() = Some(index),
+                        ondragover: |_| {},
+                        ondrop: move |_| {
+                            if let Some(from) = dragged.write_silent().take() {
+                                if from != index {
+                                    on_move.call((from, index));
+                                }
+                            }
+                        },
+                        oncontextmenu: move |_| on_remove.call(index),
+                        onmousedown: move |_| *pressed_at.write_silent() = Some(Instant::now()),
+                        onmouseup: move |_| {
+                            let pressed_for = pressed_at.write_silent().take().map(|pressed_at| pressed_at.elapsed());
+                            if pressed_for.is_some_and(|pressed_for| pressed_for >= LONG_PRESS) {
+                                *long_pressed.write_silent() = true;
+                                on_remove.call(index);
+                            }
+                        },
+                        onclick: move |_| {
+                            if std::mem::take(&mut *long_pressed.write_silent()) {
+                                return;
+                            }
+                            on_select.call(favorite.clone());
+                        },
+                        "{favorite.label}"
+                    }
+                )
+            })
+            if !overflow.is_empty() {
+                rsx!(
+                    select {
+                        class: "favorites_overflow",
+                        onchange: move |evt| {
+                            if let Some(favorite) = evt.value.parse::<usize>().ok().and_then(|index| overflow.get(index)) {
+                                on_select.call(favorite.clone());
+                            }
+                        },
+                        option { value: "", selected: true, "More…" }
+                        overflow.iter().enumerate().map(|(index, favorite)| rsx!(
+                            option { key: "{favorite.global_id}", value: "{index}", "{favorite.label}" }
+                        ))
+                    }
+                )
+            }
+        }
+    ))
+}
@@ -0,0 +1,120 @@
+use dioxus::prelude::*;
+
+use crate::{platform_label, DepartureFilter, TransportType};
+
+#[inline_props]
+pub fn FilterPanel<'a>(
+    cx: Scope,
+    filter: &'a DepartureFilter,
+    platforms: Vec<u16>,
+    networks: Vec<String>,
+    on_change: EventHandler<'a, DepartureFilter>,
+) -> Element<'a> {
+    let transport_buttons = TransportType::ALL.into_iter().map(|transport_type| {
+        let class = if filter.transport_types.contains(&transport_type) {
+            "filter_button active"
+        } else {
+            "filter_button"
+        };
+        rsx!(button {
+            class: class,
+            onclick: move |_| {
+                on_change.call(filter.to_builder().toggle_transport_type(&transport_type).build())
+            },
+            transport_type.label()
+        })
+    });
+    let selected_platform = filter
+        .platforms
+        .as_ref()
+        .and_then(|platforms| platforms.first().copied());
+    cx.render(rsx!(
+        button {
+            class: if filter.inverted { "filter_button active" } else { "filter_button" },
+            title: "Hide the selected transport types and lines instead of showing only them",
+            onclick: move |_| {
+                // Flip the type selection too so the same transport types stay visible
+                let transport_types = TransportType::ALL
+                    .into_iter()
+                    .filter(|transport_type| !filter.transport_types.contains(transport_type));
+                on_change.call(filter.to_builder().transport_types(transport_types).build().invert())
+            },
+            "Hide mode"
+        }
+        transport_buttons,
+        label { " Platform " }
+        select {
+            onchange: move |evt| {
+                let platforms = evt.value.parse().ok().map(|platform| vec![platform]);
+                on_change.call(filter.to_builder().platforms(platforms).build());
+            },
+            option { value: "", selected: selected_platform.is_none(), "All" }
+            platforms.iter().map(|platform| rsx!(
+                option {
+                    value: "{platform}",
+                    selected: selected_platform == Some(*platform),
+                    "{platform_label(*platform)}"
+                }
+            ))
+        }
+        label { " Network " }
+        select {
+            onchange: move |evt| {
+                on_change.call(filter.to_builder().network(Some(evt.value.clone())).build());
+            },
+            option { value: "", selected: filter.network.is_none(), "All" }
+            networks.iter().map(|network| rsx!(
+                option {
+                    value: "{network}",
+                    selected: filter.network.as_deref() == Some(network.as_str()),
+                    "{network}"
+                }
+            ))
+        }
+        label { " To " }
+        input {
+            onkeydown: |evt| evt.stop_propagation(),
+            placeholder: "Destination",
+            oninput: move |evt| {
+                on_change.call(filter.to_builder().destination(Some(evt.value.clone())).build());
+            }
+        }
+        label { " Lines " }
+        input {
+            onkeydown: |evt| evt.stop_propagation(),
+            placeholder: "S1, S8",
+            oninput: move |evt| {
+                let labels = evt
+                    .value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|label| !label.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                on_change.call(filter.to_builder().vehicle_labels(Some(labels)).build());
+            }
+        }
+        label {
+            input {
+                r#type: "checkbox",
+                checked: filter.hide_cancelled,
+                onchange: move |evt| {
+                    let hide_cancelled = evt.value == "true";
+                    on_change.call(filter.to_builder().hide_cancelled(hide_cancelled).build());
+                }
+            }
+            " Hide cancelled"
+        }
+        label {
+            input {
+                r#type: "checkbox",
+                checked: filter.real_time_only,
+                onchange: move |evt| {
+                    let real_time_only = evt.value == "true";
+                    on_change.call(filter.to_builder().real_time_only(real_time_only).build());
+                }
+            }
+            " Only real-time"
+        }
+    ))
+}
@@ -0,0 +1,96 @@
+use chrono::prelude::*;
+use dioxus::prelude::*;
+
+use crate::{
+    get_connection, parse_datetime_input, Journey, RawLocation, StationSearch,
+    DATETIME_INPUT_FORMAT,
+};
+
+const JOURNEY_COUNT: usize = 3;
+
+#[inline_props]
+pub fn JourneyPanel(cx: Scope) -> Element<'a> {
+    let from = use_state(cx, || None::<RawLocation>);
+    let to = use_state(cx, || None::<RawLocation>);
+    let time = use_state(cx, || {
+        Local::now().format(DATETIME_INPUT_FORMAT).to_string()
+    });
+    let journeys = use_state(cx, || None::<Result<Vec<Journey>, String>>);
+    let is_searching = use_state(cx, || false);
+    let departure_time = parse_datetime_input(time);
+    let can_search = from.is_some() && to.is_some() && departure_time.is_some();
+    let plan = move |_| {
+        let (Some(from), Some(to), Some(departure_time)) = (from.get(), to.get(), departure_time)
+        else {
+            return;
+        };
+        let from = from.global_id.clone();
+        let to = to.global_id.clone();
+        let journeys = journeys.to_owned();
+        let is_searching = is_searching.to_owned();
+        is_searching.set(true);
+        cx.spawn(async move {
+            let result = get_connection(&from, &to, departure_time)
+                .await
+                .map(|journeys| journeys.into_iter().take(JOURNEY_COUNT).collect())
+                .map_err(|e| e.to_string());
+            journeys.set(Some(result));
+            is_searching.set(false);
+        });
+    };
+    let station_name = |location: &Option<RawLocation>| {
+        location
+            .as_ref()
+            .map_or_else(|| "–".to_string(), |location| location.name.clone())
+    };
+    cx.render(rsx!(
+        div {
+            class: "journey_panel",
+            div {
+                class: "journey_stop",
+                "From: {station_name(from)} "
+                StationSearch { on_select: move |location| from.set(Some(location)) }
+            }
+            div {
+                class: "journey_stop",
+                "To: {station_name(to)} "
+                StationSearch { on_select: move |location| to.set(Some(location)) }
+            }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                r#type: "datetime-local",
+                value: "{time}",
+                oninput: move |evt| time.set(evt.value.clone())
+            }
+            button { disabled: !can_search || **is_searching, onclick: plan, "Plan journey" }
+            match journeys.get() {
+                Some(Ok(journeys)) if journeys.is_empty() => rsx!(div { "No connections found" }),
+                Some(Ok(journeys)) => rsx!(
+                    journeys.iter().enumerate().map(|(index, journey)| rsx!(
+                        div {
+                            key: "{index}",
+                            class: "journey",
+                            div {
+                                class: "journey_summary",
+                                "{journey.duration_minutes} min, {journey.changes} changes"
+                            }
+                            journey.legs.iter().map(|leg| {
+                                let departure = leg.departure.format("%H:%M");
+                                let arrival = leg.arrival.format("%H:%M");
+                                rsx!(
+                                    div {
+                                        class: "journey_leg",
+                                        "{departure} {leg.from_stop} → {arrival} {leg.to_stop} "
+                                        b { "{leg.line}" }
+                                    }
+                                )
+                            })
+                        }
+                    ))
+                ),
+                Some(Err(e)) => rsx!(div { "Planning the journey failed: {e}" }),
+                None => rsx!(""),
+            }
+        }
+    ))
+}
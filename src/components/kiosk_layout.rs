@@ -0,0 +1,46 @@
+use chrono::prelude::*;
+use dioxus::prelude::*;
+
+use crate::{platform_label, urgency_class, Departure};
+
+pub const KIOSK_DEPARTURES: usize = 8;
+
+fn minutes_label(minutes: i64) -> String {
+    if minutes <= 0 {
+        "now".to_string()
+    } else {
+        format!("{minutes} min")
+    }
+}
+
+#[inline_props]
+pub fn KioskLayout(cx: Scope, departures: Vec<Departure>, now: DateTime<Local>) -> Element<'a> {
+    let upcoming = departures
+        .iter()
+        .filter(|departure| departure.cancelled || departure.minutes_until(*now) >= 0)
+        .take(KIOSK_DEPARTURES);
+    let clock = now.format("%H:%M");
+    cx.render(rsx!(
+        div {
+            class: "kiosk_layout",
+            div { class: "kiosk_clock", "{clock}" }
+            table {
+                upcoming.map(|departure| {
+                    let minutes = departure.minutes_until(*now);
+                    let urgency = if departure.cancelled { "cancelled" } else { urgency_class(minutes) };
+                    let countdown = if departure.cancelled { "Cancelled".to_string() } else { minutes_label(minutes) };
+                    rsx!(
+                        tr {
+                            key: "{departure.vehicle_label}-{departure.planned_time}",
+                            class: "kiosk_row {urgency}",
+                            td { class: "kiosk_line", "{departure.vehicle_label}" }
+                            td { class: "kiosk_destination", "{departure.destination}" }
+                            td { class: "kiosk_platform", "{platform_label(departure.platform)}" }
+                            td { class: "kiosk_countdown", "{countdown}" }
+                        }
+                    )
+                })
+            }
+        }
+    ))
+}
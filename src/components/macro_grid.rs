@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use dioxus::prelude::*;
+
+use crate::{format_departure_time, urgency_class, use_window_size, Departure, TimeDisplayMode};
+
+fn column_class(window_width: u32) -> &'static str {
+    match window_width {
+        0..=399 => "macro_grid columns_2",
+        400..=639 => "macro_grid columns_3",
+        _ => "macro_grid columns_4",
+    }
+}
+
+fn cell_time(mode: TimeDisplayMode, departure: &Departure, now: DateTime<Local>) -> String {
+    let time = format_departure_time(mode, departure, now);
+    match departure.delay() {
+        Some(delay) if delay > Duration::zero() && !mode.shows_relative(departure, now) => {
+            format!("{time} (+{})", delay.num_minutes())
+        }
+        _ => time,
+    }
+}
+
+#[inline_props]
+pub fn MacroGrid(
+    cx: Scope,
+    departures: Vec<Departure>,
+    now: DateTime<Local>,
+    time_display_mode: TimeDisplayMode,
+) -> Element<'a> {
+    let (window_width, _) = use_window_size(cx);
+    let mut next_by_line = BTreeMap::new();
+    for departure in departures.iter().filter(|departure| !departure.cancelled) {
+        next_by_line
+            .entry(departure.vehicle_label.as_str())
+            .and_modify(|next: &mut &Departure| {
+                if departure.displayed_time() < next.displayed_time() {
+                    *next = departure;
+                }
+            })
+            .or_insert(departure);
+    }
+    cx.render(rsx!(
+        div {
+            class: column_class(window_width),
+            next_by_line.into_iter().map(|(label, departure)| {
+                let urgency = urgency_class(departure.minutes_until(*now));
+                let time = cell_time(*time_display_mode, departure, *now);
+                rsx!(
+                    div {
+                        key: "{label}",
+                        class: "macro_cell {urgency}",
+                        div { class: "macro_cell_label", "{label}" }
+                        div { class: "macro_cell_time", "{time}" }
+                    }
+                )
+            })
+        }
+    ))
+}
@@ -0,0 +1,114 @@
+use dioxus::prelude::*;
+
+const MAP_ZOOM: u8 = 16;
+
+pub fn build_osm_embed_url(lat: f64, lon: f64, zoom: u8) -> String {
+    let lat = lat.clamp(-90.0, 90.0);
+    let lon = lon.clamp(-180.0, 180.0);
+    // A slippy map tile spans 360 / 2^zoom degrees of longitude
+    let half_width = 180.0 / 2f64.powi(zoom.min(19) as i32);
+    let half_height = half_width / 2.0;
+    let bbox = format!(
+        "{},{},{},{}",
+        (lon - half_width).max(-180.0),
+        (lat - half_height).max(-90.0),
+        (lon + half_width).min(180.0),
+        (lat + half_height).min(90.0)
+    );
+    reqwest::Url::parse_with_params(
+        "https://www.openstreetmap.org/export/embed.html",
+        [
+            ("bbox", bbox),
+            ("layer", "mapnik".to_string()),
+            ("marker", format!("{lat},{lon}")),
+        ],
+    )
+    .expect("The OpenStreetMap embed URL is valid")
+    .to_string()
+}
+
+#[inline_props]
+pub fn MapView(cx: Scope, latitude: f64, longitude: f64) -> Element<'a> {
+    cx.render(rsx!(
+        div {
+            class: "map_view",
+            iframe {
+                src: "{build_osm_embed_url(*latitude, *longitude, MAP_ZOOM)}",
+                title: "Station location"
+            }
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(url: &str) -> Vec<(String, String)> {
+        reqwest::Url::parse(url)
+            .unwrap()
+            .query_pairs()
+            .into_owned()
+            .collect()
+    }
+
+    fn bbox(url: &str) -> Vec<f64> {
+        query(url)
+            .into_iter()
+            .find(|(key, _)| key == "bbox")
+            .unwrap()
+            .1
+            .split(',')
+            .map(|value| value.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn embed_url_percent_encodes_the_commas() {
+        let url = build_osm_embed_url(48.14, 11.56, 1);
+        assert!(url.starts_with("https://www.openstreetmap.org/export/embed.html?bbox="));
+        assert!(url.contains("&marker=48.14%2C11.56"), "{url}");
+        assert!(!url.contains(','), "{url}");
+    }
+
+    #[test]
+    fn embed_url_centres_the_bbox_on_the_marker() {
+        let url = build_osm_embed_url(48.0, 11.5, 2);
+        assert_eq!(bbox(&url), [-33.5, 25.5, 56.5, 70.5]);
+        assert_eq!(
+            query(&url)[1..],
+            [
+                ("layer".to_string(), "mapnik".to_string()),
+                ("marker".to_string(), "48,11.5".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn embed_url_clamps_coordinates_out_of_range() {
+        let url = build_osm_embed_url(91.0, -181.0, MAP_ZOOM);
+        assert!(url.ends_with("marker=90%2C-180"), "{url}");
+        let bbox = bbox(&url);
+        assert_eq!((bbox[0], bbox[3]), (-180.0, 90.0));
+    }
+
+    #[test]
+    fn embed_url_bbox_stays_on_the_map_at_the_edges() {
+        for (lat, lon) in [(-90.0, 180.0), (90.0, -180.0), (0.0, 0.0)] {
+            let bbox = bbox(&build_osm_embed_url(lat, lon, 0));
+            assert!(bbox[0] >= -180.0 && bbox[2] <= 180.0, "{bbox:?}");
+            assert!(bbox[1] >= -90.0 && bbox[3] <= 90.0, "{bbox:?}");
+            assert!(bbox[0] < bbox[2] && bbox[1] < bbox[3], "{bbox:?}");
+        }
+    }
+
+    #[test]
+    fn embed_url_bbox_shrinks_with_the_zoom() {
+        let width = |zoom| {
+            let bbox = bbox(&build_osm_embed_url(48.14, 11.56, zoom));
+            bbox[2] - bbox[0]
+        };
+        assert!(width(MAP_ZOOM) < width(10));
+        assert_eq!(width(19), width(25));
+    }
+}
@@ -0,0 +1,73 @@
+use chrono::prelude::*;
+use dioxus::prelude::*;
+
+use crate::{
+    batch_fetch_departures, merge_departure_lists, urgency_class, Departure, StationConfig,
+};
+
+#[inline_props]
+pub fn MergedView<'a>(
+    cx: Scope,
+    tabs: &'a [StationConfig],
+    refresh_interval_secs: u64,
+) -> Element<'a> {
+    let latest_tabs = use_ref(cx, || tabs.to_vec());
+    if *latest_tabs.read() != *tabs {
+        *latest_tabs.write_silent() = tabs.to_vec();
+    }
+    let lists = use_state(cx, Vec::<(String, Vec<Departure>)>::new);
+    let errors = use_state(cx, Vec::<String>::new);
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let latest_tabs = latest_tabs.to_owned();
+        let lists = lists.to_owned();
+        let errors = errors.to_owned();
+        let refresh_interval_secs = *refresh_interval_secs;
+        async move {
+            loop {
+                let stations = latest_tabs.read().clone();
+                let mut fetched = Vec::new();
+                let mut failed = Vec::new();
+                for (station, response) in batch_fetch_departures(&stations).await {
+                    match response {
+                        Ok(departures) => fetched.push((station.label, departures)),
+                        Err(e) => failed.push(format!("{}: {e}", station.label)),
+                    }
+                }
+                lists.set(fetched);
+                errors.set(failed);
+                tokio::time::sleep(std::time::Duration::from_secs(refresh_interval_secs)).await;
+            }
+        }
+    });
+    let now = Local::now();
+    let merged = merge_departure_lists(lists.get());
+    cx.render(rsx!(
+        div {
+            class: "merged_view",
+            errors.iter().map(|error| rsx!(div { class: "merged_error", "Fetching failed for {error}" }))
+            table {
+                merged
+                    .into_iter()
+                    .filter(|(_, departure)| departure.cancelled || departure.minutes_until(now) >= 0)
+                    .map(|(station, departure)| {
+                        let time = departure.displayed_time().format("%H:%M");
+                        let class = if departure.cancelled {
+                            "cancelled"
+                        } else {
+                            urgency_class(departure.minutes_until(now))
+                        };
+                        rsx!(
+                            tr {
+                                key: "{station}{departure.vehicle_label}{departure.planned_time}",
+                                class: "{class}",
+                                td { "{time}" }
+                                td { b { "{departure.vehicle_label}" } }
+                                td { "{departure.destination}" }
+                                td { class: "merged_station", "{station}" }
+                            }
+                        )
+                    })
+            }
+        }
+    ))
+}
@@ -0,0 +1,21 @@
+pub mod connection_graph;
+pub mod connection_summary;
+pub mod day_schedule;
+pub mod departure_audio;
+pub mod departure_card;
+pub mod departure_tile;
+pub mod disruption_banner;
+pub mod favorites_bar;
+pub mod filter_panel;
+pub mod journey_panel;
+pub mod kiosk_layout;
+pub mod macro_grid;
+pub mod map_view;
+pub mod merged_view;
+pub mod qr_code_panel;
+pub mod search_panel;
+pub mod settings_panel;
+pub mod stats_panel;
+pub mod stop_point_info;
+#[cfg(feature = "weather")]
+pub mod weather_widget;
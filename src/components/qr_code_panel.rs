@@ -0,0 +1,60 @@
+use dioxus::prelude::*;
+use qrcode::render::svg;
+use qrcode::QrCode;
+
+const QR_CODE_SIZE: u32 = 200;
+
+fn departure_board_url(station_id: &str) -> String {
+    format!("https://www.mvg.de/departure/{station_id}")
+}
+
+#[inline_props]
+pub fn QrCodePanel<'a>(cx: Scope, station_id: &'a str) -> Element<'a> {
+    let url = departure_board_url(station_id);
+    let svg = match QrCode::new(&url) {
+        Ok(code) => code
+            .render::<svg::Color>()
+            .min_dimensions(QR_CODE_SIZE, QR_CODE_SIZE)
+            .dark_color(svg::Color("#000000"))
+            .light_color(svg::Color("#ffffff"))
+            .build(),
+        Err(e) => {
+            tracing::error!("Generating the QR code for {url} failed: {e}");
+            return None;
+        }
+    };
+    cx.render(rsx!(
+        div {
+            class: "qr_code_panel",
+            div { class: "qr_code", dangerous_inner_html: "{svg}" }
+            a { href: "{url}", "{url}" }
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn departure_board_url_points_to_the_station() {
+        assert_eq!(
+            departure_board_url("de:09162:6"),
+            "https://www.mvg.de/departure/de:09162:6"
+        );
+    }
+
+    #[test]
+    fn qr_code_renders_as_svg() {
+        fn Panel(cx: Scope) -> Element {
+            cx.render(rsx!(QrCodePanel {
+                station_id: "de:09162:6"
+            }))
+        }
+        let mut dom = VirtualDom::new(Panel);
+        let _ = dom.rebuild();
+        let html = dioxus_ssr::render(&dom);
+        assert!(html.contains("<svg"), "{html}");
+        assert!(html.contains(r#"href="https://www.mvg.de/departure/de:09162:6""#));
+    }
+}
@@ -0,0 +1,188 @@
+use dioxus::prelude::*;
+use futures_util::StreamExt;
+
+const SEARCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(300);
+
+// Returns the byte length of the case-insensitive match of query at the start of text
+fn match_len(text: &str, query: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for query_char in query.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(query_char.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(text.len(), |(index, _)| index))
+}
+
+// Splits text into spans, marking non-overlapping case-insensitive matches
+pub fn highlight_match(text: &str, query: &str) -> Vec<(String, bool)> {
+    if query.is_empty() {
+        return vec![(text.to_string(), false)];
+    }
+    let mut spans = Vec::new();
+    let mut unmatched_start = 0;
+    let mut position = 0;
+    while position < text.len() {
+        match match_len(&text[position..], query) {
+            Some(len) => {
+                if unmatched_start < position {
+                    spans.push((text[unmatched_start..position].to_string(), false));
+                }
+                spans.push((text[position..position + len].to_string(), true));
+                position += len;
+                unmatched_start = position;
+            }
+            None => {
+                position += text[position..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+    if unmatched_start < text.len() {
+        spans.push((text[unmatched_start..].to_string(), false));
+    }
+    spans
+}
+
+// The text with the matches of the query wrapped in <mark>
+pub fn render_highlighted<'a>(text: &str, query: &str) -> impl Iterator<Item = LazyNodes<'a, 'a>> {
+    highlight_match(text, query)
+        .into_iter()
+        .map(|(span, matched)| {
+            if matched {
+                rsx!(mark { "{span}" })
+            } else {
+                rsx!("{span}")
+            }
+        })
+}
+
+pub fn matches_query(destination: &str, query: &str) -> bool {
+    query.is_empty() || destination.to_lowercase().contains(&query.to_lowercase())
+}
+
+#[inline_props]
+pub fn SearchPanel(cx: Scope, query: UseState<String>) -> Element<'a> {
+    let input = use_state(cx, || query.get().clone());
+    let debounce = use_coroutine(cx, |mut rx: UnboundedReceiver<String>| {
+        let query = query.to_owned();
+        async move {
+            while let Some(mut next_query) = rx.next().await {
+                loop {
+                    tokio::select! {
+                        next = rx.next() => match next {
+                            Some(next) => next_query = next,
+                            None => return,
+                        },
+                        _ = tokio::time::sleep(SEARCH_DEBOUNCE) => break,
+                    }
+                }
+                query.set(next_query.trim().to_string());
+            }
+        }
+    });
+    cx.render(rsx!(
+        div {
+            class: "search_panel",
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                placeholder: "Search destination",
+                value: "{input}",
+                oninput: move |evt| {
+                    input.set(evt.value.clone());
+                    debounce.send(evt.value.clone());
+                }
+            }
+            if !input.is_empty() {
+                rsx!(
+                    button {
+                        title: "Clear search",
+                        onclick: move |_| {
+                            input.set(String::new());
+                            query.set(String::new());
+                        },
+                        "✕"
+                    }
+                )
+            }
+        }
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(spans: &[(&str, bool)]) -> Vec<(String, bool)> {
+        spans
+            .iter()
+            .map(|(span, matched)| (span.to_string(), *matched))
+            .collect()
+    }
+
+    #[test]
+    fn empty_query_highlights_nothing() {
+        assert_eq!(
+            highlight_match("Freising", ""),
+            spans(&[("Freising", false)])
+        );
+    }
+
+    #[test]
+    fn query_without_a_match_highlights_nothing() {
+        assert_eq!(
+            highlight_match("Freising", "Erding"),
+            spans(&[("Freising", false)])
+        );
+    }
+
+    #[test]
+    fn highlight_ignores_case_and_keeps_the_original_text() {
+        assert_eq!(
+            highlight_match("Flughafen München", "MÜNCHEN"),
+            spans(&[("Flughafen ", false), ("München", true)])
+        );
+    }
+
+    #[test]
+    fn highlight_marks_every_match() {
+        assert_eq!(
+            highlight_match("Ostbahnhof Ost", "ost"),
+            spans(&[("Ost", true), ("bahnhof ", false), ("Ost", true)])
+        );
+    }
+
+    #[test]
+    fn overlapping_matches_are_highlighted_once() {
+        assert_eq!(
+            highlight_match("Aaa", "aa"),
+            spans(&[("Aa", true), ("a", false)])
+        );
+        assert_eq!(
+            highlight_match("aaaa", "aa"),
+            spans(&[("aa", true), ("aa", true)])
+        );
+    }
+
+    #[test]
+    fn highlight_of_the_whole_text_is_one_span() {
+        assert_eq!(
+            highlight_match("Pasing", "pasing"),
+            spans(&[("Pasing", true)])
+        );
+        assert_eq!(highlight_match("", "pasing"), spans(&[]));
+    }
+
+    #[test]
+    fn matches_query_ignores_case() {
+        assert!(matches_query("Flughafen München", "münchen"));
+        assert!(matches_query("Flughafen München", "FLUG"));
+        assert!(!matches_query("Flughafen München", "Freising"));
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert!(matches_query("Freising", ""));
+        assert!(matches_query("", ""));
+    }
+}
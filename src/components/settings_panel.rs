@@ -0,0 +1,374 @@
+use dioxus::prelude::*;
+
+use crate::{
+    format_travel_times, parse_datetime_input, parse_travel_times, speech, update_settings, Config,
+    ConnectionAlert, FontSize, HomeStop, LayoutMode, RawLocation, ReturnStationConfig,
+    StationConfig, StationSearch, WatchConfig, DATETIME_INPUT_FORMAT, LIMIT_RANGE,
+    OFFSET_RANGE_MINUTES, REFRESH_INTERVAL_RANGE_SECS,
+};
+
+#[inline_props]
+pub fn SettingsPanel<'a>(
+    cx: Scope,
+    station_config: &'a StationConfig,
+    on_station_change: EventHandler<'a, StationConfig>,
+    watch_config: &'a UseState<WatchConfig>,
+    settings: &'a UseState<Config>,
+) -> Element<'a> {
+    let global_id = use_state(cx, || station_config.global_id.clone());
+    let label = use_state(cx, || station_config.label.clone());
+    let home_stop_name = settings
+        .home_stop
+        .as_ref()
+        .map_or("not set", |home_stop| home_stop.name.as_str());
+    let travel_times = format_travel_times(&settings.travel_times);
+    let return_station_name = settings
+        .return_station
+        .as_ref()
+        .map_or("not set", |return_station| return_station.label.as_str());
+    let alert_label = use_state(cx, || {
+        settings
+            .connection_alert
+            .as_ref()
+            .map(|alert| alert.label.clone())
+            .unwrap_or_default()
+    });
+    let alert_prefix = use_state(cx, || {
+        settings
+            .connection_alert
+            .as_ref()
+            .map(|alert| alert.destination_prefix.clone())
+            .unwrap_or_default()
+    });
+    let alert_deadline = use_state(cx, || {
+        settings
+            .connection_alert
+            .as_ref()
+            .map(|alert| alert.deadline.format(DATETIME_INPUT_FORMAT).to_string())
+            .unwrap_or_default()
+    });
+    let new_alert = parse_datetime_input(alert_deadline)
+        .filter(|_| !alert_label.trim().is_empty())
+        .map(|deadline| ConnectionAlert {
+            label: alert_label.trim().to_string(),
+            destination_prefix: alert_prefix.trim().to_string(),
+            deadline,
+        });
+    let save_alert = move |alert: Option<ConnectionAlert>| {
+        update_settings(settings, |settings| {
+            settings.connection_alert = alert;
+        });
+    };
+    cx.render(rsx!(
+        div {
+            class: "settings_panel",
+            StationSearch {
+                on_select: move |location: RawLocation| {
+                    global_id.set(location.global_id.clone());
+                    label.set(location.name.clone());
+                    on_station_change.call(StationConfig {
+                        global_id: location.global_id,
+                        label: location.name,
+                        coordinates: Some((location.latitude, location.longitude)),
+                        ..(*station_config).clone()
+                    });
+                }
+            }
+            label { "Station ID " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                value: "{global_id}",
+                oninput: move |evt| global_id.set(evt.value.clone())
+            }
+            label { " Name " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                value: "{label}",
+                oninput: move |evt| label.set(evt.value.clone())
+            }
+            button {
+                onclick: move |_| {
+                    on_station_change.call(StationConfig {
+                        global_id: global_id.trim().to_string(),
+                        label: label.trim().to_string(),
+                        coordinates: None,
+                        ..(*station_config).clone()
+                    });
+                },
+                "Save"
+            }
+            label { " Notify within (min) " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                r#type: "number",
+                min: "0",
+                value: "{watch_config.threshold_minutes}",
+                oninput: move |evt| {
+                    if let Ok(minutes) = evt.value.parse::<i64>() {
+                        watch_config.with_mut(|config| {
+                            config.threshold_minutes = minutes;
+                            if let Err(e) = config.save() {
+                                tracing::error!("Saving watch config failed: {e}");
+                            }
+                        });
+                    }
+                }
+            }
+            label { " Walk offset " }
+            button {
+                disabled: station_config.offset_minutes <= *OFFSET_RANGE_MINUTES.start(),
+                onclick: move |_| {
+                    on_station_change.call(StationConfig {
+                        offset_minutes: station_config.offset_minutes.saturating_sub(1),
+                        ..(*station_config).clone()
+                    });
+                },
+                "−"
+            }
+            span { " {station_config.offset_minutes} min " }
+            button {
+                disabled: station_config.offset_minutes >= *OFFSET_RANGE_MINUTES.end(),
+                onclick: move |_| {
+                    on_station_change.call(StationConfig {
+                        offset_minutes: (station_config.offset_minutes + 1)
+                            .min(*OFFSET_RANGE_MINUTES.end()),
+                        ..(*station_config).clone()
+                    });
+                },
+                "+"
+            }
+            label { " Show {station_config.limit} departures " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                r#type: "range",
+                min: "{LIMIT_RANGE.start()}",
+                max: "{LIMIT_RANGE.end()}",
+                value: "{station_config.limit}",
+                onchange: move |evt| {
+                    if let Ok(limit) = evt.value.parse::<u8>() {
+                        on_station_change.call(StationConfig {
+                            limit: limit.clamp(*LIMIT_RANGE.start(), *LIMIT_RANGE.end()),
+                            ..(*station_config).clone()
+                        });
+                    }
+                }
+            }
+            label { " Font size " }
+            select {
+                onchange: move |evt| {
+                    let font_size = evt.value.parse::<usize>().ok().and_then(|index| FontSize::ALL.get(index));
+                    if let Some(font_size) = font_size {
+                        update_settings(settings, |settings| {
+                            settings.font_size = *font_size;
+                        });
+                    }
+                },
+                FontSize::ALL.iter().enumerate().map(|(index, font_size)| rsx!(
+                    option {
+                        value: "{index}",
+                        selected: settings.font_size == *font_size,
+                        font_size.label()
+                    }
+                ))
+            }
+            label { " Layout " }
+            select {
+                onchange: move |evt| {
+                    let layout_mode = evt.value.parse::<usize>().ok().and_then(|index| LayoutMode::ALL.get(index));
+                    if let Some(layout_mode) = layout_mode {
+                        update_settings(settings, |settings| {
+                            settings.layout_mode = *layout_mode;
+                        });
+                    }
+                },
+                LayoutMode::ALL.iter().enumerate().map(|(index, layout_mode)| rsx!(
+                    option {
+                        value: "{index}",
+                        selected: settings.layout_mode == *layout_mode,
+                        layout_mode.label()
+                    }
+                ))
+            }
+            label { " Flash within (s) " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                r#type: "number",
+                min: "0",
+                value: "{settings.imminent_threshold_secs}",
+                oninput: move |evt| {
+                    if let Ok(secs) = evt.value.parse::<i64>() {
+                        update_settings(settings, |settings| {
+                            settings.imminent_threshold_secs = secs.max(0);
+                        });
+                    }
+                }
+            }
+            label { " Home stop: {home_stop_name} " }
+            StationSearch {
+                on_select: move |location: RawLocation| {
+                    update_settings(settings, |settings| {
+                        settings.home_stop = Some(HomeStop {
+                            global_id: location.global_id,
+                            name: location.name,
+                        });
+                    });
+                }
+            }
+            button {
+                disabled: settings.home_stop.is_none(),
+                onclick: move |_| {
+                    update_settings(settings, |settings| {
+                        settings.home_stop = None;
+                    });
+                },
+                "Clear"
+            }
+            label { " Return station: {return_station_name} " }
+            StationSearch {
+                on_select: move |location: RawLocation| {
+                    update_settings(settings, |settings| {
+                        settings.return_station = Some(ReturnStationConfig {
+                            global_id: location.global_id,
+                            label: location.name,
+                        });
+                    });
+                }
+            }
+            button {
+                disabled: settings.return_station.is_none(),
+                onclick: move |_| {
+                    update_settings(settings, |settings| {
+                        settings.return_station = None;
+                    });
+                },
+                "Clear"
+            }
+            label { " Travel times (line=min) " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                placeholder: "S1=25, U4=12",
+                value: "{travel_times}",
+                onchange: move |evt| {
+                    update_settings(settings, |settings| {
+                        settings.travel_times = parse_travel_times(&evt.value);
+                    });
+                }
+            }
+            label { " Connection alert: line " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                placeholder: "S1",
+                value: "{alert_label}",
+                oninput: move |evt| alert_label.set(evt.value.clone())
+            }
+            label { " to " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                placeholder: "München",
+                value: "{alert_prefix}",
+                oninput: move |evt| alert_prefix.set(evt.value.clone())
+            }
+            label { " before " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                r#type: "datetime-local",
+                value: "{alert_deadline}",
+                oninput: move |evt| alert_deadline.set(evt.value.clone())
+            }
+            button {
+                disabled: new_alert.is_none(),
+                onclick: move |_| save_alert(new_alert.clone()),
+                "Set alert"
+            }
+            button {
+                disabled: settings.connection_alert.is_none(),
+                onclick: move |_| save_alert(None),
+                "Clear"
+            }
+            label {
+                " Read out next departure "
+                input {
+                    r#type: "checkbox",
+                    checked: settings.tts_enabled,
+                    onchange: move |evt| {
+                        update_settings(settings, |settings| {
+                            settings.tts_enabled = evt.value == "true";
+                        });
+                    }
+                }
+            }
+            button {
+                onclick: move |_| speech::speak("Next departure: S1 to Freising in 5 minutes".to_string()),
+                "Test voice"
+            }
+            label {
+                " Chime on changes "
+                input {
+                    r#type: "checkbox",
+                    checked: settings.sound_enabled,
+                    onchange: move |evt| {
+                        update_settings(settings, |settings| {
+                            settings.sound_enabled = evt.value == "true";
+                        });
+                    }
+                }
+            }
+            label { " Volume " }
+            input {
+                r#type: "range",
+                min: "0",
+                max: "100",
+                value: "{settings.sound_volume}",
+                onchange: move |evt| {
+                    if let Ok(volume) = evt.value.parse::<u8>() {
+                        update_settings(settings, |settings| {
+                            settings.sound_volume = volume.min(100);
+                        });
+                    }
+                }
+            }
+            label {
+                " Hide night lines in daytime "
+                input {
+                    r#type: "checkbox",
+                    checked: settings.hide_night_service_daytime,
+                    onchange: move |evt| {
+                        update_settings(settings, |settings| {
+                            settings.hide_night_service_daytime = evt.value == "true";
+                        });
+                    }
+                }
+            }
+            label {
+                " Log departures to CSV "
+                input {
+                    r#type: "checkbox",
+                    checked: settings.csv_logging,
+                    onchange: move |evt| {
+                        update_settings(settings, |settings| {
+                            settings.csv_logging = evt.value == "true";
+                        });
+                    }
+                }
+            }
+            label { " Refresh every {settings.refresh_interval_secs} s " }
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                r#type: "range",
+                min: "{REFRESH_INTERVAL_RANGE_SECS.start()}",
+                max: "{REFRESH_INTERVAL_RANGE_SECS.end()}",
+                value: "{settings.refresh_interval_secs}",
+                oninput: move |evt| {
+                    if let Ok(secs) = evt.value.parse::<u64>() {
+                        update_settings(settings, |settings| {
+                            settings.refresh_interval_secs = secs.clamp(
+                                *REFRESH_INTERVAL_RANGE_SECS.start(),
+                                *REFRESH_INTERVAL_RANGE_SECS.end(),
+                            );
+                        });
+                    }
+                }
+            }
+        }
+    ))
+}
@@ -0,0 +1,39 @@
+use dioxus::prelude::*;
+
+use crate::DelayStats;
+
+#[inline_props]
+pub fn StatsPanel(cx: Scope, stats: Vec<(String, DelayStats)>, window_days: u32) -> Element<'a> {
+    cx.render(rsx!(
+        div {
+            class: "stats_panel",
+            div { class: "stats_header", "Delays over the last {window_days} days (real-time departures only)" }
+            if stats.is_empty() {
+                rsx!("No departures recorded yet")
+            } else {
+                rsx!(
+                    table {
+                        tr {
+                            th { "Line" }
+                            th { "Average" }
+                            th { "Max" }
+                            th { "Samples" }
+                        }
+                        stats.iter().map(|(label, line_stats)| {
+                            let mean = format!("{:.1} min", line_stats.mean_minutes());
+                            rsx!(
+                                tr {
+                                    key: "{label}",
+                                    td { "{label}" }
+                                    td { "{mean}" }
+                                    td { "{line_stats.max_minutes} min" }
+                                    td { "{line_stats.count}" }
+                                }
+                            )
+                        })
+                    }
+                )
+            }
+        }
+    ))
+}
@@ -0,0 +1,68 @@
+use dioxus::prelude::*;
+
+use crate::{get_stop_point, GlobalId, StopPointInfo};
+
+fn facility_icon<'a, 'b>(
+    available: bool,
+    icon: &'static str,
+    label: &'static str,
+) -> LazyNodes<'a, 'b> {
+    let class = if available {
+        "facility available"
+    } else {
+        "facility"
+    };
+    let title = if available {
+        label.to_string()
+    } else {
+        format!("No {}", label.to_lowercase())
+    };
+    rsx!(span {
+        class: class,
+        title: "{title}",
+        icon
+    })
+}
+
+// Only refetched when the station changes, the stop point is static data.
+#[inline_props]
+pub fn StopPointPanel(cx: Scope, station_id: String, stop_point_id: GlobalId) -> Element<'a> {
+    let info = use_state(cx, || None::<StopPointInfo>);
+    let expanded = use_state(cx, || false);
+    use_effect(cx, (station_id,), |_| {
+        let info = info.to_owned();
+        let stop_point_id = stop_point_id.clone();
+        async move {
+            match get_stop_point(&stop_point_id).await {
+                Ok(stop_point) => info.set(Some(stop_point)),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Fetching stop info for {stop_point_id} failed");
+                    info.set(None);
+                }
+            }
+        }
+    });
+    let stop_point = info.get().as_ref()?;
+    cx.render(rsx!(
+        div {
+            class: "stop_point_info",
+            button {
+                class: "filter_button",
+                onclick: move |_| expanded.set(!expanded),
+                if **expanded { "Stop info ▾" } else { "Stop info ▸" }
+            }
+            if **expanded {
+                rsx!(
+                    div { b { "{stop_point.name}" } " {stop_point.address}" }
+                    div {
+                        class: "facilities",
+                        facility_icon(stop_point.wheelchair_accessible, "♿", "Wheelchair accessible")
+                        facility_icon(stop_point.tactile_paving, "⠿", "Tactile paving")
+                        facility_icon(stop_point.shelter, "☂", "Shelter")
+                        facility_icon(stop_point.live_departure_display, "🕒", "Live departure display")
+                    }
+                )
+            }
+        }
+    ))
+}
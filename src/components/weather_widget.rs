@@ -0,0 +1,34 @@
+use dioxus::prelude::*;
+
+use crate::weather::{get_weather, WeatherData};
+
+const WEATHER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+const MUNICH_COORDINATES: (f64, f64) = (48.1374, 11.5755);
+
+#[inline_props]
+pub fn WeatherWidget(cx: Scope) -> Element<'a> {
+    let weather = use_state(cx, || None::<WeatherData>);
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let weather = weather.to_owned();
+        async move {
+            loop {
+                let (lat, lon) = MUNICH_COORDINATES;
+                match get_weather(lat, lon).await {
+                    Ok(data) => weather.set(Some(data)),
+                    Err(e) => tracing::warn!(error = %e, "Fetching the weather failed"),
+                }
+                tokio::time::sleep(WEATHER_POLL_INTERVAL).await;
+            }
+        }
+    });
+    let weather = weather.get().as_ref()?;
+    let temperature = format!("{:.0}", weather.temperature_c);
+    cx.render(rsx!(
+        span {
+            class: "weather_widget",
+            title: "{weather.condition}",
+            img { class: "weather_icon", src: "{weather.icon_url}", alt: "{weather.condition}" }
+            "{temperature}°C"
+        }
+    ))
+}
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::mvg::parse_departures;
+use crate::{Departure, MvgError};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Pushes every departure list received over the WebSocket through the same
+// parsing as the polled responses. Reconnects with exponential backoff and
+// reports the connection state so the fetcher knows when to poll instead.
+pub async fn run(
+    url: &str,
+    mut on_connected: impl FnMut(bool),
+    mut on_update: impl FnMut(Result<Vec<Departure>, MvgError>),
+) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        match tokio_tungstenite::connect_async(url).await {
+            Ok((mut stream, _)) => {
+                tracing::info!(url, "Live updates connected");
+                backoff = INITIAL_BACKOFF;
+                on_connected(true);
+                while let Some(message) = stream.next().await {
+                    match message {
+                        Ok(Message::Text(text)) => on_update(parse_departures(text.as_bytes())),
+                        Ok(Message::Binary(bytes)) => on_update(parse_departures(&bytes)),
+                        Ok(Message::Close(_)) => break,
+                        Ok(_) => {}
+                        Err(e) => {
+                            tracing::warn!(url, error = %e, "Live update stream failed");
+                            break;
+                        }
+                    }
+                }
+                on_connected(false);
+                tracing::warn!(url, "Live updates disconnected, polling until reconnected");
+            }
+            Err(e) => {
+                tracing::warn!(url, error = %e, ?backoff, "Connecting for live updates failed");
+            }
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::test_helpers::DEPARTURES;
+
+    #[derive(Debug)]
+    enum Event {
+        Connected(bool),
+        Update(Result<usize, String>),
+    }
+
+    // Serves one connection that sends the given messages and closes
+    async fn serve(messages: Vec<Message>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for message in messages {
+                socket.send(message).await.unwrap();
+            }
+            let _ = socket.close(None).await;
+        });
+        format!("ws://{address}")
+    }
+
+    // Runs the client until it disconnects for the first time
+    async fn events(url: &str) -> Vec<Event> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let update_tx = tx.clone();
+        let client = run(
+            url,
+            move |connected| {
+                let _ = tx.send(Event::Connected(connected));
+            },
+            move |response| {
+                let update = response
+                    .map(|departures| departures.len())
+                    .map_err(|e| e.to_string());
+                let _ = update_tx.send(Event::Update(update));
+            },
+        );
+        let collect = async {
+            let mut events = Vec::new();
+            while let Some(event) = rx.recv().await {
+                let disconnected = matches!(event, Event::Connected(false));
+                events.push(event);
+                if disconnected {
+                    break;
+                }
+            }
+            events
+        };
+        tokio::time::timeout(Duration::from_secs(5), async {
+            tokio::select! {
+                _ = client => unreachable!("the client never stops on its own"),
+                events = collect => events,
+            }
+        })
+        .await
+        .expect("The live client did not disconnect")
+    }
+
+    #[tokio::test]
+    async fn pushed_departures_go_through_the_parser() {
+        let url = serve(vec![Message::Text(DEPARTURES.to_string())]).await;
+        let events = events(&url).await;
+        assert!(matches!(
+            events.as_slice(),
+            [
+                Event::Connected(true),
+                Event::Update(Ok(3)),
+                Event::Connected(false)
+            ]
+        ));
+    }
+
+    #[tokio::test]
+    async fn binary_and_malformed_messages_are_parsed_too() {
+        let url = serve(vec![
+            Message::Binary(b"[]".to_vec()),
+            Message::Text("{".to_string()),
+        ])
+        .await;
+        let events = events(&url).await;
+        assert!(matches!(
+            events.as_slice(),
+            [
+                Event::Connected(true),
+                Event::Update(Ok(0)),
+                Event::Update(Err(_)),
+                Event::Connected(false)
+            ]
+        ));
+    }
+}
@@ -1,176 +1,7265 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+#![allow(non_snake_case)]
 
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::error::Error;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
 
 use chrono::{DateTime, Duration};
 
 use chrono::prelude::*;
+use clap::Parser;
+use components::connection_graph::ConnectionGraph;
+use components::connection_summary::ConnectionSummary;
+use components::day_schedule::DaySchedule;
+use components::departure_audio::DepartureAudio;
+use components::departure_card::DepartureCard;
+use components::departure_tile::{toggle_pinned, toggle_watched, ResponseTile, TileAction};
+use components::disruption_banner::DisruptionBanner;
+use components::favorites_bar::{FavoritesBar, MAX_VISIBLE_FAVORITES};
+use components::filter_panel::FilterPanel;
+use components::journey_panel::JourneyPanel;
+use components::kiosk_layout::KioskLayout;
+use components::macro_grid::MacroGrid;
+use components::map_view::MapView;
+use components::merged_view::MergedView;
+use components::qr_code_panel::QrCodePanel;
+use components::search_panel::{matches_query, SearchPanel};
+use components::settings_panel::SettingsPanel;
+use components::stats_panel::StatsPanel;
+use components::stop_point_info::StopPointPanel;
+use dioxus::html::input_data::keyboard_types::{Code, Modifiers};
 use dioxus::prelude::*;
+use directories::ProjectDirs;
+use futures_util::StreamExt;
 use itertools::Itertools;
-use serde::Deserialize;
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
 
-const OBERSCHLEISSHEIM_URL: &str = "https://www.mvg.de/api/fib/v2/departure?globalId=de:09184:2000&limit=14&offsetInMinutes=0&transportTypes=SBAHN,BUS,UBAHN,TRAM";
+use mvg::{
+    departures_from_response, is_mvv_network, local_time_from_millis, Departure, GlobalId,
+    MvgError, Occupancy, TransportType,
+};
 
-enum TransportType {
-    Sbahn,
+#[cfg(test)]
+mod api_integration;
+mod components;
+#[cfg(test)]
+#[path = "tests/fixtures.rs"]
+mod fixtures;
+#[cfg(feature = "ws")]
+mod live;
+mod mvg;
+mod notifications;
+mod speech;
+#[cfg(feature = "statusbar")]
+mod statusbar;
+#[cfg(test)]
+mod test_helpers;
+#[cfg(feature = "tray")]
+mod tray;
+#[cfg(feature = "weather")]
+mod weather;
+
+const MVG_API_URL: &str = "https://www.mvg.de/api/fib/v2";
+const CACHE_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(60);
+const RETRY_DELAYS_SECS: [u64; 4] = [1, 2, 4, 8];
+const REFRESH_INTERVAL_RANGE_SECS: std::ops::RangeInclusive<u64> = 5..=60;
+const OFFSET_RANGE_MINUTES: std::ops::RangeInclusive<u8> = 0..=30;
+const LIMIT_RANGE: std::ops::RangeInclusive<u8> = 5..=50;
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const LOAD_PROGRESS_TICK: std::time::Duration = std::time::Duration::from_millis(200);
+const LOAD_PROGRESS_MAX: u8 = 90;
+const PAGE_SIZE: usize = 10;
+const MAX_TABS: usize = 6;
+const HISTORY_LENGTH: usize = 50;
+const WINDOW_TITLE: &str = "MVG Departures";
+const DISRUPTION_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+const TICKER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2 * 60);
+const TRAVEL_TIME_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+const TRAVEL_TIME_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+const CONNECTION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+const SCHEDULE_PAGE_LIMIT: u8 = 100;
+const SCHEDULE_MAX_PAGES: usize = 30;
+const SVG_WIDTH: u32 = 640;
+const SVG_ROW_HEIGHT: u32 = 22;
+const SVG_PADDING: u32 = 16;
+const HURRY_SLACK_SECS: i64 = 120;
+const WALK_HINT_MAX_SLACK_SECS: i64 = 10 * 60;
+const LANDSCAPE_ASPECT_RATIO: f64 = 1.4;
+const CSV_LOG_RETENTION_DAYS: i64 = 30;
+const RETURN_DEPARTURE_COUNT: usize = 5;
+const BANNER_HASH_CAPACITY: usize = 1000;
+const BANNER_HASH_EVICTION: usize = 500;
+const PLATFORM_CHANGE_TTL_MINUTES: i64 = 5;
+const DELAY_SAMPLE_COUNT: usize = 10;
+const DELAY_TREND_WINDOW: usize = 3;
+const DELAY_TREND_THRESHOLD_MINUTES: f64 = 0.5;
+const NIGHT_SERVICE_END_HOUR: u32 = 5;
+const WATCH_MODE_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+static HTTP_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::ClientBuilder::new()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .expect("Building the HTTP client failed")
+});
+
+enum FetchCommand {
+    Refresh,
+    // Departures pushed over the WebSocket, tagged with the fetch generation
+    // they arrived in
+    #[cfg(feature = "ws")]
+    Live(u64, Result<Vec<Departure>, MvgError>),
 }
 
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct RawDeparture {
-    #[serde(rename = "plannedDepartureTime")]
-    planned_departure_time_ms: u64,
-    #[serde(rename = "realtime")]
-    is_real_time: bool,
-    #[serde(rename = "delayInMinutes", default)]
-    delay_minutes: u16,
-    #[serde(rename = "realtimeDepartureTime")]
-    real_departure_time_ms: u64,
-    transport_type: String,
-    #[serde(rename = "label")]
-    vehicle_label: String,
-    diva_id: String,
-    network: String,
-    train_type: String,
-    destination: String,
-    cancelled: bool,
-    sev: bool,
-    platform: u16,
-    messages: Vec<String>,
-    banner_hash: String,
-    occupancy: String,
-    stop_point_global_id: String,
+impl FetchCommand {
+    // A refresh fetches again, a live update is used as it is
+    fn into_live_update(self) -> Option<(u64, Result<Vec<Departure>, MvgError>)> {
+        match self {
+            FetchCommand::Refresh => None,
+            #[cfg(feature = "ws")]
+            FetchCommand::Live(generation, response) => Some((generation, response)),
+        }
+    }
 }
 
-#[derive(PartialEq)]
-struct Departure {
-    actual_time: DateTime<Local>,
-    planned_time: DateTime<Local>,
-    delay: Option<Duration>,
-    destination: String,
-    cancelled: bool,
-    vehicle_label: String,
+// A fetch that started before the settings changed is dropped, along with every
+// refresh queued since: the next fetch already uses the new settings.
+fn is_stale_fetch(
+    generation: u64,
+    current_generation: u64,
+    rx: &mut UnboundedReceiver<FetchCommand>,
+) -> bool {
+    if generation == current_generation {
+        return false;
+    }
+    while let Ok(Some(_)) = rx.try_next() {}
+    true
 }
 
-impl Departure {
-    fn displayed_time(&self) -> &DateTime<Local> {
-        if self.cancelled {
-            &self.planned_time
-        } else {
-            &self.actual_time
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("de", "Fundevoge", "mvg_dioxus")
+}
+
+fn config_path(file_name: &str) -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().join(file_name))
+}
+
+fn load_json<T: DeserializeOwned>(path: Option<PathBuf>) -> Option<T> {
+    serde_json::from_str(&std::fs::read_to_string(path?).ok()?).ok()
+}
+
+fn save_json<T: Serialize>(path: Option<PathBuf>, value: &T) -> Result<(), Box<dyn Error>> {
+    let path = path.ok_or("No project directory available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct StationConfig {
+    global_id: String,
+    label: String,
+    limit: u8,
+    offset_minutes: u8,
+    #[serde(default)]
+    coordinates: Option<(f64, f64)>,
+}
+
+impl Default for StationConfig {
+    fn default() -> Self {
+        StationConfig {
+            global_id: "de:09184:2000".to_string(),
+            label: "Oberschleißheim".to_string(),
+            limit: 14,
+            offset_minutes: 0,
+            coordinates: None,
         }
     }
 }
 
-impl From<RawDeparture> for Departure {
-    fn from(value: RawDeparture) -> Self {
-        let actual_time = Local
-            .timestamp_millis_opt(value.real_departure_time_ms as i64)
-            .unwrap();
-        let planned_time = Local
-            .timestamp_millis_opt(value.planned_departure_time_ms as i64)
-            .unwrap();
-        let delay = value
-            .is_real_time
-            .then(|| Duration::minutes(value.delay_minutes as i64));
-        Departure {
-            actual_time,
-            planned_time,
-            delay,
-            destination: value.destination,
-            cancelled: value.cancelled,
-            vehicle_label: value.vehicle_label,
+impl StationConfig {
+    fn load(config: &Config) -> Self {
+        load_json(config_path("station.json")).unwrap_or_else(|| config.station())
+    }
+}
+
+fn load_station_tabs(config: &Config) -> Vec<StationConfig> {
+    load_json::<Vec<StationConfig>>(config_path("tabs.json"))
+        .filter(|tabs| !tabs.is_empty())
+        .unwrap_or_else(|| vec![StationConfig::load(config)])
+}
+
+fn update_settings(settings: &UseState<Config>, apply: impl FnOnce(&mut Config)) {
+    settings.with_mut(|settings| {
+        apply(settings);
+        if let Err(e) = settings.save() {
+            tracing::error!("Saving settings failed: {e}");
+        }
+    });
+}
+
+fn update_station_tabs(
+    tabs: &UseState<Vec<StationConfig>>,
+    apply: impl FnOnce(&mut Vec<StationConfig>),
+) {
+    tabs.with_mut(|tabs| {
+        apply(tabs);
+        if let Err(e) = save_json(config_path("tabs.json"), tabs) {
+            tracing::error!("Saving station tabs failed: {e}");
         }
+    });
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+struct WindowGeometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+}
+
+const MAX_WINDOW_COORDINATE: u32 = 16384;
+
+fn is_valid_geometry(x: i32, y: i32, w: u32, h: u32) -> bool {
+    let on_screen = |position: i32| (0..MAX_WINDOW_COORDINATE as i32).contains(&position);
+    let usable_size = |size: u32| (1..=MAX_WINDOW_COORDINATE).contains(&size);
+    on_screen(x) && on_screen(y) && usable_size(w) && usable_size(h)
+}
+
+impl WindowGeometry {
+    fn of_window(window: &dioxus_desktop::DesktopContext) -> Option<WindowGeometry> {
+        let position = window.outer_position().ok()?;
+        let size = window.inner_size();
+        Some(WindowGeometry {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+        })
+    }
+
+    fn is_valid(&self) -> bool {
+        is_valid_geometry(self.x, self.y, self.width, self.height)
     }
 }
 
-#[inline_props]
-fn ResponseTile<'a>(cx: Scope, departure: &'a Departure) -> Element {
-    let displayed_time = departure.displayed_time().format("%H:%M");
-    let time_info = if let Some(delay) = &departure.delay {
-        rsx!("{displayed_time} (+ {delay.num_minutes()})")
-    } else if !departure.cancelled {
-        rsx!(i {"{displayed_time}"})
-    } else {
-        rsx!("{displayed_time}")
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+enum Theme {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+impl Theme {
+    fn label(self) -> &'static str {
+        match self {
+            Theme::Light => "☀ Light",
+            Theme::Dark => "☾ Dark",
+            Theme::System => "◐ System",
+        }
+    }
+
+    fn next(self) -> Theme {
+        match self {
+            Theme::System => Theme::Light,
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::System,
+        }
+    }
+
+    fn resolve(self, system_theme: dioxus_desktop::tao::window::Theme) -> &'static str {
+        match (self, system_theme) {
+            (Theme::Light, _) => "light",
+            (Theme::Dark, _) => "dark",
+            (Theme::System, dioxus_desktop::tao::window::Theme::Dark) => "dark",
+            (Theme::System, _) => "light",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+enum FontSize {
+    Small,
+    #[default]
+    Normal,
+    Large,
+    ExtraLarge,
+}
+
+impl FontSize {
+    const ALL: [FontSize; 4] = [
+        FontSize::Small,
+        FontSize::Normal,
+        FontSize::Large,
+        FontSize::ExtraLarge,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            FontSize::Small => "Small",
+            FontSize::Normal => "Normal",
+            FontSize::Large => "Large",
+            FontSize::ExtraLarge => "XL",
+        }
+    }
+
+    fn pixels(self) -> u8 {
+        match self {
+            FontSize::Small => 12,
+            FontSize::Normal => 16,
+            FontSize::Large => 20,
+            FontSize::ExtraLarge => 24,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+enum LayoutMode {
+    #[default]
+    Auto,
+    Landscape,
+    Portrait,
+}
+
+impl LayoutMode {
+    const ALL: [LayoutMode; 3] = [
+        LayoutMode::Auto,
+        LayoutMode::Landscape,
+        LayoutMode::Portrait,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            LayoutMode::Auto => "Auto",
+            LayoutMode::Landscape => "Side by side",
+            LayoutMode::Portrait => "Stacked",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RenderedLayout {
+    Landscape,
+    Portrait,
+}
+
+impl RenderedLayout {
+    fn css_class(self) -> &'static str {
+        match self {
+            RenderedLayout::Landscape => "board_layout landscape",
+            RenderedLayout::Portrait => "board_layout portrait",
+        }
+    }
+}
+
+fn select_layout(width: u32, height: u32, mode: LayoutMode) -> RenderedLayout {
+    match mode {
+        LayoutMode::Landscape => RenderedLayout::Landscape,
+        LayoutMode::Portrait => RenderedLayout::Portrait,
+        LayoutMode::Auto if width as f64 > LANDSCAPE_ASPECT_RATIO * height as f64 => {
+            RenderedLayout::Landscape
+        }
+        LayoutMode::Auto => RenderedLayout::Portrait,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+enum DisplayMode {
+    #[default]
+    Chronological,
+    GroupedByLine,
+    Grid,
+}
+
+impl DisplayMode {
+    fn next(self) -> DisplayMode {
+        match self {
+            DisplayMode::Chronological => DisplayMode::GroupedByLine,
+            DisplayMode::GroupedByLine => DisplayMode::Grid,
+            DisplayMode::Grid => DisplayMode::Chronological,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DisplayMode::Chronological => "By time",
+            DisplayMode::GroupedByLine => "By line",
+            DisplayMode::Grid => "Grid",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+enum DisplayStyle {
+    #[default]
+    Compact,
+    Card,
+}
+
+impl DisplayStyle {
+    fn toggled(self) -> DisplayStyle {
+        match self {
+            DisplayStyle::Compact => DisplayStyle::Card,
+            DisplayStyle::Card => DisplayStyle::Compact,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            DisplayStyle::Compact => "Compact",
+            DisplayStyle::Card => "Cards",
+        }
+    }
+}
+
+// Example config.toml, every key is optional:
+//
+// station_global_id = "de:09162:6"
+// station_label = "Hauptbahnhof"
+// refresh_interval_secs = 15
+// walk_offset_minutes = 5
+// departure_limit = 20
+// transport_type_filter = ["SBAHN", "UBAHN"]
+// always_on_top = false
+// theme = "System"
+// font_size = "Normal"
+// display_mode = "Chronological"
+// display_style = "Compact"
+// sort = { primary = "DisplayedTime", secondary = "Line", cancelled_to_end = false }
+// layout_mode = "Auto"
+// imminent_threshold_secs = 120
+// watch_mode = false
+// kiosk_mode = false
+// kiosk_exit_key = "Escape"
+// kiosk_exit_presses = 3
+// delay_stats_days = 7
+// csv_logging = false
+// hide_night_service_daytime = false
+// tts_enabled = false
+// sound_enabled = false
+// sound_volume = 50
+// pinned = [["U6", "Klinikum Großhadern"]]
+// fetch_mode = "Poll"
+// # or, with the ws feature, push updates ({global_id} is the station):
+// # fetch_mode = { WebSocket = "wss://example.org/departures/{global_id}" }
+//
+// [home_stop]
+// global_id = "de:09162:1"
+// name = "Karlsplatz (Stachus)"
+//
+// [travel_times]
+// S1 = 25
+// U4 = 12
+//
+// [return_station]
+// global_id = "de:09162:1"
+// label = "Karlsplatz (Stachus)"
+//
+// [connection_alert]
+// label = "S1"
+// destination_prefix = "Freising"
+// deadline = "2024-01-15T15:30:00+01:00"
+//
+// [[favorites]]
+// global_id = "de:09162:6"
+// label = "Hauptbahnhof"
+// limit = 20
+// offset_minutes = 5
+//
+// [[connection_rules]]
+// transfer_minutes_needed = 4
+// target_line = "S1"
+// target_direction = "Flughafen"
+// from_station = { global_id = "de:09184:2000", label = "Oberschleißheim", limit = 14, offset_minutes = 5 }
+// via_station = { global_id = "de:09162:6", label = "Hauptbahnhof", limit = 30, offset_minutes = 0 }
+// to_station = { global_id = "de:09162:1200", label = "Flughafen München", limit = 14, offset_minutes = 0 }
+//
+// [window_geometry]
+// x = 100
+// y = 100
+// width = 480
+// height = 800
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+struct Config {
+    station_global_id: String,
+    station_label: String,
+    refresh_interval_secs: u64,
+    walk_offset_minutes: u8,
+    departure_limit: u8,
+    #[serde(with = "transport_type_filter")]
+    transport_type_filter: Vec<TransportType>,
+    always_on_top: bool,
+    window_geometry: Option<WindowGeometry>,
+    theme: Theme,
+    font_size: FontSize,
+    display_mode: DisplayMode,
+    display_style: DisplayStyle,
+    sort: SortConfig,
+    layout_mode: LayoutMode,
+    imminent_threshold_secs: i64,
+    watch_mode: bool,
+    kiosk_mode: bool,
+    kiosk_exit_key: String,
+    kiosk_exit_presses: u8,
+    home_stop: Option<HomeStop>,
+    travel_times: HashMap<String, u32>,
+    connection_alert: Option<ConnectionAlert>,
+    connection_rules: Vec<ConnectionRule>,
+    favorites: Vec<StationConfig>,
+    // (vehicle_label, destination) in the order the pinned section shows them
+    pinned: Vec<(String, String)>,
+    return_station: Option<ReturnStationConfig>,
+    delay_stats_days: u32,
+    csv_logging: bool,
+    hide_night_service_daytime: bool,
+    tts_enabled: bool,
+    sound_enabled: bool,
+    // 0–100
+    sound_volume: u8,
+    fetch_mode: FetchMode,
+}
+
+// Every transport type the app doesn't know is one filter entry, saved with the
+// same marker filter links use. Older configs saved it as an empty string.
+mod transport_type_filter {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{TransportType, OTHER_TRANSPORT_TYPES};
+
+    pub fn serialize<S: Serializer>(
+        filter: &[TransportType],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        filter
+            .iter()
+            .map(|transport_type| match transport_type {
+                TransportType::Unknown(_) => OTHER_TRANSPORT_TYPES,
+                transport_type => transport_type.api_name(),
+            })
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<TransportType>, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+        Ok(names
+            .into_iter()
+            .map(|name| match name.as_str() {
+                OTHER_TRANSPORT_TYPES | "" => TransportType::Unknown(String::new()),
+                _ => TransportType::try_from(name.as_str()).unwrap_or_else(|e| {
+                    tracing::error!("{e}");
+                    TransportType::Unknown(name)
+                }),
+            })
+            .collect())
+    }
+}
+
+// Polling uses refresh_interval_secs. The WebSocket mode needs the ws feature
+// and falls back to polling while the connection is down.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+enum FetchMode {
+    #[default]
+    Poll,
+    WebSocket(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct HomeStop {
+    global_id: String,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct ReturnStationConfig {
+    global_id: String,
+    label: String,
+}
+
+impl ReturnStationConfig {
+    fn station(&self, outbound: &StationConfig) -> StationConfig {
+        StationConfig {
+            global_id: self.global_id.clone(),
+            label: self.label.clone(),
+            limit: outbound.limit,
+            offset_minutes: 0,
+            coordinates: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct ConnectionRule {
+    from_station: StationConfig,
+    via_station: StationConfig,
+    to_station: StationConfig,
+    transfer_minutes_needed: u8,
+    target_line: String,
+    target_direction: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct ConnectionPair<'a> {
+    from_departure: &'a Departure,
+    to_departure: &'a Departure,
+    slack_minutes: i64,
+}
+
+// Pairs every departure from one station with the first departure at the other
+// station that leaves after riding travel_minutes
+fn find_connections<'a>(
+    from: &'a [Departure],
+    to: &'a [Departure],
+    travel_minutes: u32,
+) -> Vec<ConnectionPair<'a>> {
+    let travel_time = Duration::minutes(travel_minutes as i64);
+    from.iter()
+        .filter(|departure| !departure.cancelled)
+        .filter_map(|from_departure| {
+            let arrival = *from_departure.displayed_time() + travel_time;
+            let to_departure = to
+                .iter()
+                .filter(|departure| !departure.cancelled && *departure.displayed_time() >= arrival)
+                .min_by_key(|departure| *departure.displayed_time())?;
+            Some(ConnectionPair {
+                from_departure,
+                to_departure,
+                slack_minutes: (*to_departure.displayed_time() - arrival).num_minutes(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum ConnectionStatus {
+    Secure {
+        departure: DateTime<Local>,
+        arrival: DateTime<Local>,
+        connection: DateTime<Local>,
+    },
+    AtRisk {
+        departure: DateTime<Local>,
+        arrival: DateTime<Local>,
+        connection: Option<DateTime<Local>>,
+    },
+    Unknown(String),
+}
+
+// Catching the next departure at from_station that can be reached after the walk
+// offset, is there time to change to the next target departure at via_station?
+async fn check_connection(rule: &ConnectionRule) -> ConnectionStatus {
+    let now = Local::now();
+    let (departures, via_departures, journeys) = tokio::join!(
+        get_response(&rule.from_station),
+        get_response(&rule.via_station),
+        get_connection(
+            &rule.from_station.global_id,
+            &rule.via_station.global_id,
+            now
+        ),
+    );
+    let (departures, via_departures, journeys) = match (departures, via_departures, journeys) {
+        (Ok(departures), Ok(via_departures), Ok(journeys)) => {
+            (departures, via_departures, journeys)
+        }
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+            return ConnectionStatus::Unknown(e.to_string())
+        }
     };
-    let inner =
-        rsx!(time_info, " [", b {"{departure.vehicle_label}"}, " {departure.destination}] ");
-    cx.render(rsx!(
-        div {
-            if departure.cancelled {
-                rsx!(s { inner})
-            } else {
-                rsx!(inner)
+    let walk = Duration::minutes(rule.from_station.offset_minutes as i64);
+    let travel_times = TravelTimeCache::from_journeys(&journeys, now);
+    let Some((departure, travel_time)) = departures
+        .iter()
+        .filter(|departure| !departure.cancelled && *departure.displayed_time() >= now + walk)
+        .find_map(|departure| {
+            let travel_time = travel_times.times.get(&departure.vehicle_label)?;
+            Some((*departure.displayed_time(), *travel_time))
+        })
+    else {
+        return ConnectionStatus::Unknown(format!(
+            "No direct departure to {} found",
+            rule.via_station.label
+        ));
+    };
+    let arrival = departure + travel_time;
+    let connection = via_departures
+        .iter()
+        .filter(|departure| {
+            !departure.cancelled
+                && departure.vehicle_label == rule.target_line
+                && departure.destination.starts_with(&rule.target_direction)
+        })
+        .map(|departure| *departure.displayed_time())
+        .find(|time| *time >= arrival);
+    match connection {
+        Some(connection)
+            if connection - arrival >= Duration::minutes(rule.transfer_minutes_needed as i64) =>
+        {
+            ConnectionStatus::Secure {
+                departure,
+                arrival,
+                connection,
             }
         }
-    ))
+        connection => ConnectionStatus::AtRisk {
+            departure,
+            arrival,
+            connection,
+        },
+    }
 }
 
-async fn get_response() -> Result<Vec<Departure>, Box<dyn Error>> {
-    Ok(reqwest::get(OBERSCHLEISSHEIM_URL)
-        .await?
-        .json::<Vec<RawDeparture>>()
-        .await?
-        .into_iter()
-        .map(Departure::from)
-        .sorted_by(|dep1, dep2| dep1.displayed_time().cmp(dep2.displayed_time()))
-        .collect::<Vec<_>>())
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct ConnectionAlert {
+    label: String,
+    destination_prefix: String,
+    deadline: DateTime<Local>,
 }
 
-fn app(cx: Scope) -> Element {
-    let current_response = use_state(cx, || None);
-    let is_fetching = use_state(cx, || false);
-    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
-        let is_fetching = is_fetching.to_owned();
-        let current_response = current_response.to_owned();
-        async move {
-            loop {
-                is_fetching.set(true);
-                current_response.set(Some(get_response().await));
-                is_fetching.set(false);
-                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-            }
+impl ConnectionAlert {
+    // None once the deadline has passed, so the alert resets by itself.
+    fn remaining(&self, departures: &[Departure], now: DateTime<Local>) -> Option<usize> {
+        if now >= self.deadline {
+            return None;
         }
-    });
-    let time = use_state(cx, Local::now);
-    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
-        let time = time.to_owned();
-        async move {
-            loop {
-                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                time.set(Local::now());
-            }
+        let upcoming = &departures
+            [departures.partition_point(|departure| *departure.displayed_time() < now)..];
+        Some(departures_before(
+            upcoming,
+            self.deadline,
+            &self.label,
+            &self.destination_prefix,
+        ))
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let station = StationConfig::default();
+        Config {
+            station_global_id: station.global_id,
+            station_label: station.label,
+            refresh_interval_secs: 10,
+            walk_offset_minutes: station.offset_minutes,
+            departure_limit: station.limit,
+            transport_type_filter: TransportType::ALL.to_vec(),
+            always_on_top: false,
+            window_geometry: None,
+            theme: Theme::default(),
+            font_size: FontSize::default(),
+            display_mode: DisplayMode::default(),
+            display_style: DisplayStyle::default(),
+            sort: SortConfig::default(),
+            layout_mode: LayoutMode::default(),
+            imminent_threshold_secs: 120,
+            watch_mode: false,
+            kiosk_mode: false,
+            kiosk_exit_key: "Escape".to_string(),
+            kiosk_exit_presses: 3,
+            home_stop: None,
+            travel_times: HashMap::new(),
+            connection_alert: None,
+            connection_rules: Vec::new(),
+            favorites: Vec::new(),
+            pinned: Vec::new(),
+            return_station: None,
+            delay_stats_days: 7,
+            csv_logging: false,
+            hide_night_service_daytime: false,
+            tts_enabled: false,
+            sound_enabled: false,
+            sound_volume: 50,
+            fetch_mode: FetchMode::default(),
         }
-    });
+    }
+}
 
-    let time = Local::now().format("%H:%M:%S");
-    let tile_body = match current_response.get() {
-        Some(Ok(responses)) => {
-            rsx! {
-                responses.iter().map(|response| {
-                    rsx!(ResponseTile {
-                        departure: response
-                    })
-                })
-            }
+impl Config {
+    fn load() -> Self {
+        config_path("config.toml").map_or_else(Config::default, |path| Config::load_from(&path))
+    }
+
+    fn load_from(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                tracing::error!("Parsing {} failed: {e}", path.display());
+                Config::default()
+            }),
+            Err(_) => Config::default(),
         }
-        Some(Err(e)) => rsx! { "Fetching data failed: {e}"  },
-        None => rsx! { ""  },
-    };
-    cx.render(rsx!(div {class: "parent", div {class: "child", "{time}"}, if *is_fetching.get() { rsx!(div {class: "child", div {class: "loader"}}) }  }, div {tile_body }))
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        self.save_to(&config_path("config.toml").ok_or("No project directory available")?)
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn station(&self) -> StationConfig {
+        StationConfig {
+            global_id: self.station_global_id.clone(),
+            label: self.station_label.clone(),
+            limit: self.departure_limit,
+            offset_minutes: self.walk_offset_minutes,
+            coordinates: None,
+        }
+    }
 }
 
-fn main() {
-    #[cfg(debug_assertions)]
-    env_logger::builder()
-        .filter_level(log::LevelFilter::Info)
-        .try_init()
-        .unwrap();
-    hot_reload_init!();
-    dioxus_desktop::launch_cfg(
-        app,
-        dioxus_desktop::Config::new()
-            .with_custom_head(r#"<link rel="stylesheet" href="public/tailwind.css">"#.to_string()),
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct WatchConfig {
+    lines: HashSet<(String, String)>,
+    threshold_minutes: i64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            lines: HashSet::new(),
+            threshold_minutes: 5,
+        }
+    }
+}
+
+impl WatchConfig {
+    fn load() -> Self {
+        load_json(config_path("watch.json")).unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        save_json(config_path("watch.json"), self)
+    }
+
+    fn is_watched(&self, departure: &Departure) -> bool {
+        self.lines.contains(&(
+            departure.vehicle_label.clone(),
+            departure.destination.clone(),
+        ))
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct RawLocation {
+    #[serde(default)]
+    global_id: String,
+    name: String,
+    #[serde(default)]
+    place: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+// Every known transport type is requested, because the transport type filter
+// is applied to the departures after they arrive
+fn requested_transport_types() -> String {
+    TransportType::ALL
+        .iter()
+        .filter(|transport_type| !matches!(transport_type, TransportType::Unknown(_)))
+        .map(TransportType::api_name)
+        .join(",")
+}
+
+fn build_departure_url(base_url: &str, config: &StationConfig) -> String {
+    debug_assert!(config.limit >= 1, "departure limit must be at least 1");
+    reqwest::Url::parse_with_params(
+        &format!("{base_url}/departure"),
+        [
+            ("globalId", config.global_id.clone()),
+            ("limit", config.limit.max(1).to_string()),
+            ("offsetInMinutes", config.offset_minutes.to_string()),
+            ("transportTypes", requested_transport_types()),
+        ],
     )
+    .map(|url| url.to_string())
+    .unwrap_or_default()
+}
+
+#[cfg(feature = "weather")]
+fn weather_widget<'a, 'b>() -> LazyNodes<'a, 'b> {
+    rsx!(components::weather_widget::WeatherWidget {})
+}
+
+#[cfg(not(feature = "weather"))]
+fn weather_widget<'a, 'b>() -> LazyNodes<'a, 'b> {
+    rsx!("")
+}
+
+// Nachtlinien are labelled N followed by the line number, e.g. N17 or N40
+fn is_night_service(vehicle_label: &str) -> bool {
+    vehicle_label
+        .trim()
+        .strip_prefix('N')
+        .is_some_and(|number| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_night_time(now: DateTime<Local>) -> bool {
+    now.hour() < NIGHT_SERVICE_END_HOUR
+}
+
+impl Display for Departure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let delay_info = if self.cancelled {
+            "cancelled".to_string()
+        } else {
+            match self.delay() {
+                Some(delay) if delay > Duration::zero() => format!("+{} min", delay.num_minutes()),
+                Some(_) => "on time".to_string(),
+                None => "scheduled".to_string(),
+            }
+        };
+        let sev = if self.sev { ", SEV" } else { "" };
+        write!(
+            f,
+            "[{}] {} @ {} ({delay_info}{sev})",
+            self.vehicle_label,
+            self.destination,
+            self.displayed_time().format("%H:%M")
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+enum SortField {
+    DisplayedTime,
+    PlannedTime,
+    Line,
+    Destination,
+    Platform,
+    Delay,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum SortValue<'a> {
+    Time(DateTime<Local>),
+    Text(&'a str),
+    Number(i64),
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+struct SortConfig {
+    primary: SortField,
+    secondary: SortField,
+    cancelled_to_end: bool,
+}
+
+impl Default for SortConfig {
+    fn default() -> Self {
+        SortConfig {
+            primary: SortField::DisplayedTime,
+            secondary: SortField::Line,
+            cancelled_to_end: false,
+        }
+    }
+}
+
+impl Departure {
+    fn sort_value(&self, field: SortField) -> SortValue<'_> {
+        match field {
+            SortField::DisplayedTime => SortValue::Time(*self.displayed_time()),
+            SortField::PlannedTime => SortValue::Time(self.planned_time),
+            SortField::Line => SortValue::Text(&self.vehicle_label),
+            SortField::Destination => SortValue::Text(&self.destination),
+            SortField::Platform => SortValue::Number(self.platform as i64),
+            SortField::Delay => SortValue::Number(self.delay_seconds.unwrap_or(0)),
+        }
+    }
+
+    // Rounded to whole minutes like MVG's own delayInMinutes
+    fn delay(&self) -> Option<Duration> {
+        self.effective_delay_minutes()
+            .map(|minutes| Duration::minutes(minutes.round() as i64))
+    }
+
+    fn effective_delay_minutes(&self) -> Option<f64> {
+        self.delay_seconds.map(|seconds| seconds as f64 / 60.0)
+    }
+
+    fn pin_key(&self) -> (String, String) {
+        (self.vehicle_label.clone(), self.destination.clone())
+    }
+
+    fn sort_key(&self, config: &SortConfig) -> (bool, SortValue<'_>, SortValue<'_>) {
+        (
+            config.cancelled_to_end && self.cancelled,
+            self.sort_value(config.primary),
+            self.sort_value(config.secondary),
+        )
+    }
+}
+
+impl From<&Departure> for String {
+    fn from(departure: &Departure) -> Self {
+        departure.to_string()
+    }
+}
+
+fn platform_label(platform: u16) -> String {
+    if platform == 0 {
+        "–".to_string()
+    } else {
+        platform.to_string()
+    }
+}
+
+impl Departure {
+    fn minutes_until(&self, now: DateTime<Local>) -> i64 {
+        (*self.displayed_time() - now).num_minutes()
+    }
+
+    fn is_imminent(&self, now: DateTime<Local>, threshold_secs: i64) -> bool {
+        let seconds_until = (*self.displayed_time() - now).num_seconds();
+        !self.cancelled && (0..=threshold_secs).contains(&seconds_until)
+    }
+
+    fn walk_and_catch(&self, now: DateTime<Local>, walk_minutes: u8) -> WalkStatus {
+        if self.cancelled {
+            return WalkStatus::Cancelled;
+        }
+        let seconds_until = (*self.displayed_time() - now).num_seconds();
+        if seconds_until < 0 {
+            return WalkStatus::AlreadyDeparted;
+        }
+        let slack = seconds_until - walk_minutes as i64 * 60;
+        if slack < 0 {
+            WalkStatus::MissedBySeconds(-slack)
+        } else if slack < HURRY_SLACK_SECS {
+            WalkStatus::HurryNeeded
+        } else {
+            WalkStatus::ComfortableWalk
+        }
+    }
+
+    fn mvg_web_url(&self) -> String {
+        format!(
+            "https://www.mvg.de/verbindungen/fahrplan.html?line={}&stop={}",
+            self.diva_id, self.stop_point_global_id
+        )
+    }
+
+    // RFC 5545 VEVENT from leaving the door until the departure, CRLF terminated
+    fn to_ical_event(&self, walk_minutes: u8) -> String {
+        let departure = self.displayed_time().with_timezone(&Utc);
+        let leave = departure - Duration::minutes(walk_minutes as i64);
+        let uid = self
+            .trip_id
+            .clone()
+            .unwrap_or_else(|| format!("{}-{}", self.diva_id, self.planned_time.timestamp()));
+        let delay = match self.delay() {
+            Some(delay) if delay > Duration::zero() => {
+                format!("Delayed by {} min", delay.num_minutes())
+            }
+            _ => "On time".to_string(),
+        };
+        let description = format!(
+            "Platform {}\n{delay}\n{}",
+            platform_label(self.platform),
+            self.mvg_web_url()
+        );
+        [
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}@mvg_dioxus", ical_text(&uid)),
+            format!("DTSTAMP:{}", Utc::now().format(ICAL_DATETIME_FORMAT)),
+            format!("DTSTART:{}", leave.format(ICAL_DATETIME_FORMAT)),
+            format!("DTEND:{}", departure.format(ICAL_DATETIME_FORMAT)),
+            format!(
+                "SUMMARY:{}",
+                ical_text(&format!(
+                    "Catch {} to {}",
+                    self.vehicle_label, self.destination
+                ))
+            ),
+            format!("DESCRIPTION:{}", ical_text(&description)),
+            "END:VEVENT".to_string(),
+        ]
+        .iter()
+        .map(|line| fold_ical_line(line))
+        .collect()
+    }
+}
+
+const ICAL_DATETIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const ICAL_LINE_OCTETS: usize = 75;
+
+fn ical_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Content lines longer than 75 octets continue on the next line after a space
+fn fold_ical_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > ICAL_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn add_to_calendar(departure: &Departure, walk_minutes: u8) -> Result<(), Box<dyn Error>> {
+    let calendar = format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//mvg_dioxus//EN\r\n{}END:VCALENDAR\r\n",
+        departure.to_ical_event(walk_minutes)
+    );
+    let path = std::env::temp_dir().join(format!(
+        "departure-{}-{}.ics",
+        departure
+            .vehicle_label
+            .replace(|c: char| !c.is_ascii_alphanumeric(), ""),
+        departure.planned_time.format("%Y%m%d-%H%M")
+    ));
+    std::fs::write(&path, calendar)?;
+    open_with_default_app(&path)
+}
+
+fn open_with_default_app(path: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    command.arg(path).spawn()?;
+    Ok(())
+}
+
+fn next_departure_summary(departures: &[Departure], now: DateTime<Local>) -> Option<String> {
+    if departures.is_empty() {
+        return None;
+    }
+    let summary = departures
+        .iter()
+        .find(|departure| !departure.cancelled && *departure.displayed_time() >= now)
+        .map_or_else(
+            || "No services running".to_string(),
+            |departure| {
+                let minutes = departure.minutes_until(now);
+                format!(
+                    "Next {} to {} in {minutes} {}",
+                    departure.vehicle_label,
+                    departure.destination,
+                    if minutes == 1 { "minute" } else { "minutes" }
+                )
+            },
+        );
+    Some(summary)
+}
+
+// Only a successful fetch that matches the previous one in every field is
+// skipped, so platform, occupancy or message changes still reach the board.
+fn is_unchanged_fetch(previous: Option<&[Departure]>, departures: Option<&[Departure]>) -> bool {
+    departures.is_some() && departures == previous
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum WalkStatus {
+    ComfortableWalk,
+    HurryNeeded,
+    MissedBySeconds(i64),
+    AlreadyDeparted,
+    Cancelled,
+}
+
+fn walk_hint(departure: &Departure, now: DateTime<Local>, walk_minutes: u8) -> String {
+    if walk_minutes == 0 {
+        return String::new();
+    }
+    match departure.walk_and_catch(now, walk_minutes) {
+        WalkStatus::ComfortableWalk => {
+            let slack =
+                (*departure.displayed_time() - now).num_seconds() - walk_minutes as i64 * 60;
+            if slack < WALK_HINT_MAX_SLACK_SECS {
+                "Walk normally".to_string()
+            } else {
+                String::new()
+            }
+        }
+        WalkStatus::HurryNeeded => "Hurry!".to_string(),
+        WalkStatus::MissedBySeconds(seconds) => format!("Missed by {seconds}s — take next"),
+        WalkStatus::AlreadyDeparted | WalkStatus::Cancelled => String::new(),
+    }
+}
+
+fn group_by_line<'a>(departures: &[&'a Departure]) -> Vec<(String, Vec<&'a Departure>)> {
+    let sorted_by_line = departures
+        .iter()
+        .copied()
+        .sorted_by(|dep1, dep2| {
+            dep1.vehicle_label
+                .cmp(&dep2.vehicle_label)
+                .then_with(|| dep1.displayed_time().cmp(dep2.displayed_time()))
+        })
+        .collect_vec();
+    sorted_by_line
+        .into_iter()
+        .group_by(|departure| departure.vehicle_label.clone())
+        .into_iter()
+        .map(|(label, group)| (label, group.collect_vec()))
+        .sorted_by_key(|(_, group)| *group[0].displayed_time())
+        .collect()
+}
+
+fn departures_before(
+    deps: &[Departure],
+    deadline: DateTime<Local>,
+    label: &str,
+    dest_prefix: &str,
+) -> usize {
+    deps.iter()
+        .filter(|departure| {
+            !departure.cancelled
+                && departure.vehicle_label == label
+                && departure.destination.starts_with(dest_prefix)
+                && *departure.displayed_time() < deadline
+        })
+        .count()
+}
+
+fn urgency_class(minutes: i64) -> &'static str {
+    match minutes {
+        0..=2 => "urgent",
+        3..=5 => "soon",
+        _ => "normal",
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct DepartureFilter {
+    transport_types: HashSet<TransportType>,
+    platforms: Option<Vec<u16>>,
+    destination: Option<String>,
+    vehicle_labels: Option<Vec<String>>,
+    network: Option<String>,
+    hide_cancelled: bool,
+    real_time_only: bool,
+    // In hide mode the departures selected by transport type and line are excluded instead
+    inverted: bool,
+}
+
+impl Default for DepartureFilter {
+    fn default() -> Self {
+        DepartureFilter {
+            transport_types: TransportType::ALL.into_iter().collect(),
+            platforms: None,
+            destination: None,
+            vehicle_labels: None,
+            network: None,
+            hide_cancelled: false,
+            real_time_only: false,
+            inverted: false,
+        }
+    }
+}
+
+impl DepartureFilter {
+    fn builder() -> DepartureFilterBuilder {
+        DepartureFilterBuilder::default()
+    }
+
+    fn to_builder(&self) -> DepartureFilterBuilder {
+        DepartureFilterBuilder {
+            filter: self.clone(),
+        }
+    }
+
+    fn invert(self) -> DepartureFilter {
+        DepartureFilter {
+            inverted: !self.inverted,
+            ..self
+        }
+    }
+
+    fn matches(&self, departure: &Departure) -> bool {
+        let destination = departure.destination.to_lowercase();
+        let type_selected = departure
+            .transport_type
+            .matches_filter(&self.transport_types);
+        let label_selected = |labels: &Vec<String>| {
+            labels
+                .iter()
+                .any(|label| label.eq_ignore_ascii_case(&departure.vehicle_label))
+        };
+        let selected = type_selected && self.vehicle_labels.as_ref().is_none_or(label_selected);
+        selected != self.inverted
+            && self
+                .platforms
+                .as_ref()
+                .is_none_or(|platforms| platforms.contains(&departure.platform))
+            && self
+                .destination
+                .as_ref()
+                .is_none_or(|query| destination.contains(&query.trim().to_lowercase()))
+            && self
+                .network
+                .as_ref()
+                .is_none_or(|network| &departure.network == network)
+            && !(self.hide_cancelled && departure.cancelled)
+            && (!self.real_time_only || departure.is_real_time)
+    }
+
+    fn apply<'a>(&self, departures: &'a [Departure]) -> Vec<&'a Departure> {
+        departures
+            .iter()
+            .filter(|departure| self.matches(departure))
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct DepartureFilterBuilder {
+    filter: DepartureFilter,
+}
+
+impl DepartureFilterBuilder {
+    fn transport_types(mut self, transport_types: impl IntoIterator<Item = TransportType>) -> Self {
+        self.filter.transport_types = transport_types.into_iter().collect();
+        self
+    }
+
+    fn toggle_transport_type(mut self, transport_type: &TransportType) -> Self {
+        if !self.filter.transport_types.remove(transport_type) {
+            self.filter.transport_types.insert(transport_type.clone());
+        }
+        self
+    }
+
+    fn platforms(mut self, platforms: Option<Vec<u16>>) -> Self {
+        self.filter.platforms = platforms.filter(|platforms| !platforms.is_empty());
+        self
+    }
+
+    fn destination(mut self, destination: Option<String>) -> Self {
+        self.filter.destination = destination.filter(|destination| !destination.trim().is_empty());
+        self
+    }
+
+    fn vehicle_labels(mut self, vehicle_labels: Option<Vec<String>>) -> Self {
+        self.filter.vehicle_labels = vehicle_labels.filter(|labels| !labels.is_empty());
+        self
+    }
+
+    fn network(mut self, network: Option<String>) -> Self {
+        self.filter.network = network.filter(|network| !network.is_empty());
+        self
+    }
+
+    fn hide_cancelled(mut self, hide_cancelled: bool) -> Self {
+        self.filter.hide_cancelled = hide_cancelled;
+        self
+    }
+
+    fn real_time_only(mut self, real_time_only: bool) -> Self {
+        self.filter.real_time_only = real_time_only;
+        self
+    }
+
+    fn inverted(mut self, inverted: bool) -> Self {
+        self.filter.inverted = inverted;
+        self
+    }
+
+    fn build(self) -> DepartureFilter {
+        self.filter
+    }
+}
+
+const DEEP_LINK_SCHEME: &str = "mvg_dioxus";
+// URL schemes can't contain underscores, so links are parsed with this one instead
+const DEEP_LINK_PARSE_SCHEME: &str = "mvg-dioxus";
+// Stands for every transport type the app doesn't know in filter links
+const OTHER_TRANSPORT_TYPES: &str = "OTHER";
+
+#[derive(Debug)]
+enum FilterParseError {
+    InvalidLink(String),
+    UnknownTransportType(String),
+    InvalidLimit(String),
+    InvalidPlatform(String),
+    InvalidFlag(String, String),
+}
+
+impl Display for FilterParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterParseError::InvalidLink(link) => write!(f, "Not a station link: \"{link}\""),
+            FilterParseError::UnknownTransportType(transport_type) => {
+                write!(f, "Unknown transport type \"{transport_type}\"")
+            }
+            FilterParseError::InvalidLimit(limit) => write!(f, "Invalid limit \"{limit}\""),
+            FilterParseError::InvalidPlatform(platform) => {
+                write!(f, "Invalid platform \"{platform}\"")
+            }
+            FilterParseError::InvalidFlag(key, value) => {
+                write!(
+                    f,
+                    "Invalid value \"{value}\" for {key}, expected true or false"
+                )
+            }
+        }
+    }
+}
+
+impl Error for FilterParseError {}
+
+fn parse_flag(key: &str, value: &str) -> Result<bool, FilterParseError> {
+    value
+        .parse()
+        .map_err(|_| FilterParseError::InvalidFlag(key.to_string(), value.to_string()))
+}
+
+impl DepartureFilter {
+    // Parses e.g. transport=SBAHN,UBAHN&destination=Hbf&platform=1,2&line=S1&hide_cancelled=true.
+    // Unknown keys are ignored so links can carry other parameters such as the limit.
+    fn from_url_query_string(query: &str) -> Result<DepartureFilter, FilterParseError> {
+        let mut builder = DepartureFilter::builder();
+        let mut transport_types = None;
+        for (key, value) in
+            reqwest::Url::parse(&format!("{DEEP_LINK_PARSE_SCHEME}://filter?{query}"))
+                .map_err(|_| FilterParseError::InvalidLink(query.to_string()))?
+                .query_pairs()
+        {
+            let list = || {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+            };
+            builder = match key.as_ref() {
+                "transport" => {
+                    let transport_types = transport_types.get_or_insert_with(Vec::new);
+                    for transport_type in list() {
+                        transport_types.push(match transport_type {
+                            OTHER_TRANSPORT_TYPES => TransportType::Unknown(String::new()),
+                            transport_type => {
+                                TransportType::try_from(transport_type).map_err(|_| {
+                                    FilterParseError::UnknownTransportType(
+                                        transport_type.to_string(),
+                                    )
+                                })?
+                            }
+                        });
+                    }
+                    builder
+                }
+                "destination" => builder.destination(Some(value.to_string())),
+                "platform" => builder.platforms(Some(
+                    list()
+                        .map(|platform| {
+                            platform.parse().map_err(|_| {
+                                FilterParseError::InvalidPlatform(platform.to_string())
+                            })
+                        })
+                        .collect::<Result<_, _>>()?,
+                )),
+                "line" => builder.vehicle_labels(Some(list().map(String::from).collect())),
+                "network" => builder.network(Some(value.to_string())),
+                "hide_cancelled" => builder.hide_cancelled(parse_flag(&key, &value)?),
+                "real_time_only" => builder.real_time_only(parse_flag(&key, &value)?),
+                "inverted" => builder.inverted(parse_flag(&key, &value)?),
+                _ => builder,
+            };
+        }
+        if let Some(transport_types) = transport_types {
+            builder = builder.transport_types(transport_types);
+        }
+        Ok(builder.build())
+    }
+
+    // The inverse of from_url_query_string; settings left at their default are omitted
+    fn to_url_query_string(&self) -> String {
+        let mut url = reqwest::Url::parse(&format!("{DEEP_LINK_PARSE_SCHEME}://filter"))
+            .expect("The filter base URL is valid");
+        {
+            let mut query = url.query_pairs_mut();
+            let all_transport_types: HashSet<_> = TransportType::ALL.into_iter().collect();
+            if self.transport_types != all_transport_types {
+                let transport_types = self
+                    .transport_types
+                    .iter()
+                    .map(|transport_type| match transport_type {
+                        TransportType::Unknown(_) => OTHER_TRANSPORT_TYPES,
+                        transport_type => transport_type.api_name(),
+                    })
+                    .sorted()
+                    .join(",");
+                query.append_pair("transport", &transport_types);
+            }
+            if let Some(destination) = &self.destination {
+                query.append_pair("destination", destination);
+            }
+            if let Some(platforms) = &self.platforms {
+                query.append_pair("platform", &platforms.iter().join(","));
+            }
+            if let Some(vehicle_labels) = &self.vehicle_labels {
+                query.append_pair("line", &vehicle_labels.join(","));
+            }
+            if let Some(network) = &self.network {
+                query.append_pair("network", network);
+            }
+            for (key, set) in [
+                ("hide_cancelled", self.hide_cancelled),
+                ("real_time_only", self.real_time_only),
+                ("inverted", self.inverted),
+            ] {
+                if set {
+                    query.append_pair(key, "true");
+                }
+            }
+        }
+        url.query().unwrap_or_default().to_string()
+    }
+}
+
+// A mvg_dioxus://station/<global id>?<filter query>&limit=<n> link
+#[derive(Clone, PartialEq, Debug)]
+struct DeepLink {
+    global_id: String,
+    limit: Option<u8>,
+    filter: DepartureFilter,
+}
+
+impl DeepLink {
+    fn parse(link: &str) -> Result<DeepLink, FilterParseError> {
+        let invalid_link = || FilterParseError::InvalidLink(link.to_string());
+        let url = link
+            .strip_prefix(DEEP_LINK_SCHEME)
+            .and_then(|link| link.strip_prefix(':'))
+            .and_then(|link| reqwest::Url::parse(&format!("{DEEP_LINK_PARSE_SCHEME}:{link}")).ok())
+            .ok_or_else(invalid_link)?;
+        if url.host_str() != Some("station") {
+            return Err(invalid_link());
+        }
+        let global_id = url.path().trim_matches('/').to_string();
+        if global_id.is_empty() {
+            return Err(invalid_link());
+        }
+        let limit = url
+            .query_pairs()
+            .find(|(key, _)| key == "limit")
+            .map(|(_, limit)| match limit.parse::<u8>() {
+                Ok(limit) if limit >= 1 => Ok(limit),
+                _ => Err(FilterParseError::InvalidLimit(limit.to_string())),
+            })
+            .transpose()?;
+        Ok(DeepLink {
+            global_id,
+            limit,
+            filter: DepartureFilter::from_url_query_string(url.query().unwrap_or_default())?,
+        })
+    }
+
+    fn to_link(&self) -> String {
+        let mut link = format!("{DEEP_LINK_SCHEME}://station/{}", self.global_id);
+        let query = self.filter.to_url_query_string();
+        let limit = self.limit.map(|limit| format!("limit={limit}"));
+        let query = [Some(query).filter(|query| !query.is_empty()), limit]
+            .into_iter()
+            .flatten()
+            .join("&");
+        if !query.is_empty() {
+            link.push('?');
+            link.push_str(&query);
+        }
+        link
+    }
+}
+
+// Moves the dragged pinned line to where it was dropped
+fn reorder_pinned(
+    pinned: &mut Vec<(String, String)>,
+    dragged: &(String, String),
+    target: &(String, String),
+) {
+    let Some(from) = pinned.iter().position(|line| line == dragged) else {
+        return;
+    };
+    let Some(to) = pinned.iter().position(|line| line == target) else {
+        return;
+    };
+    let line = pinned.remove(from);
+    pinned.insert(to, line);
+}
+
+fn move_pinned(settings: &UseState<Config>, dragged: &(String, String), target: &(String, String)) {
+    if dragged == target {
+        return;
+    }
+    update_settings(settings, |settings| {
+        reorder_pinned(&mut settings.pinned, dragged, target)
+    });
+}
+
+// Moves departures of pinned lines to the front in pinned order, keeping the
+// existing order within each line and among the rest.
+fn apply_pinned_ordering<'a>(
+    pinned: &[(String, String)],
+    departures: &[&'a Departure],
+) -> Vec<&'a Departure> {
+    departures
+        .iter()
+        .copied()
+        .sorted_by_key(|departure| {
+            pinned
+                .iter()
+                .position(|(label, destination)| {
+                    &departure.vehicle_label == label && &departure.destination == destination
+                })
+                .unwrap_or(usize::MAX)
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+enum TimeDisplayMode {
+    Absolute,
+    Relative,
+    #[default]
+    Auto,
+}
+
+impl TimeDisplayMode {
+    const AUTO_RELATIVE_MAX_MINUTES: i64 = 30;
+
+    fn label(self) -> &'static str {
+        match self {
+            TimeDisplayMode::Absolute => "Clock",
+            TimeDisplayMode::Relative => "Countdown",
+            TimeDisplayMode::Auto => "Auto",
+        }
+    }
+
+    fn next(self) -> TimeDisplayMode {
+        match self {
+            TimeDisplayMode::Auto => TimeDisplayMode::Absolute,
+            TimeDisplayMode::Absolute => TimeDisplayMode::Relative,
+            TimeDisplayMode::Relative => TimeDisplayMode::Auto,
+        }
+    }
+
+    fn shows_relative(self, departure: &Departure, now: DateTime<Local>) -> bool {
+        match self {
+            TimeDisplayMode::Absolute => false,
+            TimeDisplayMode::Relative => true,
+            TimeDisplayMode::Auto => {
+                departure.minutes_until(now) <= TimeDisplayMode::AUTO_RELATIVE_MAX_MINUTES
+            }
+        }
+    }
+}
+
+fn format_departure_time(
+    mode: TimeDisplayMode,
+    departure: &Departure,
+    now: DateTime<Local>,
+) -> String {
+    if !mode.shows_relative(departure, now) {
+        return departure.displayed_time().format("%H:%M").to_string();
+    }
+    let time_until = *departure.displayed_time() - now;
+    if time_until < -Duration::minutes(1) {
+        format!("departed {} min ago", (-time_until).num_minutes())
+    } else if time_until < Duration::zero() {
+        "departed".to_string()
+    } else if time_until < Duration::minutes(1) {
+        "now".to_string()
+    } else {
+        format!("in {} min", time_until.num_minutes())
+    }
+}
+
+fn notify_watched_departures(
+    departures: &[Departure],
+    watch_config: &WatchConfig,
+    notified: &mut HashSet<(String, String, DateTime<Local>)>,
+) {
+    let now = Local::now();
+    notified.retain(|(_, _, planned_time)| *planned_time > now - Duration::hours(1));
+    for departure in departures
+        .iter()
+        .filter(|departure| !departure.cancelled && watch_config.is_watched(departure))
+    {
+        let minutes = (*departure.displayed_time() - now).num_minutes();
+        if !(0..=watch_config.threshold_minutes).contains(&minutes) {
+            continue;
+        }
+        let key = (
+            departure.vehicle_label.clone(),
+            departure.destination.clone(),
+            departure.planned_time,
+        );
+        if !notified.insert(key.clone()) {
+            continue;
+        }
+        notifications::notify(notifications::Notification::new(
+            key,
+            format!("{} {}", departure.vehicle_label, departure.destination),
+            format!(
+                "Departs in {minutes} min at {}",
+                departure.displayed_time().format("%H:%M")
+            ),
+        ));
+    }
+}
+
+fn notify_connection_alert(
+    departures: &[Departure],
+    alert: Option<&ConnectionAlert>,
+    notified_deadline: &mut Option<DateTime<Local>>,
+) {
+    let Some(alert) = alert else {
+        *notified_deadline = None;
+        return;
+    };
+    match alert.remaining(departures, Local::now()) {
+        None => *notified_deadline = None,
+        Some(0) if *notified_deadline != Some(alert.deadline) => {
+            *notified_deadline = Some(alert.deadline);
+            notifications::notify(notifications::Notification::new(
+                (
+                    alert.label.clone(),
+                    alert.destination_prefix.clone(),
+                    alert.deadline,
+                ),
+                format!("No more {} connections", alert.label),
+                format!(
+                    "No {} to {} leaves before {}",
+                    alert.label,
+                    alert.destination_prefix,
+                    alert.deadline.format("%H:%M")
+                ),
+            ));
+        }
+        Some(_) => {}
+    }
+}
+
+const ANNOUNCEMENT_STEP_MINUTES: i64 = 5;
+
+// Announces the next departure when it changes or its countdown crosses a
+// five minute boundary, so the same departure isn't repeated every fetch
+fn announce_next_departure(
+    departures: &[Departure],
+    now: DateTime<Local>,
+    last_announced: &UseState<Option<(String, String, i64)>>,
+) {
+    let Some(next) = departures
+        .iter()
+        .find(|departure| !departure.cancelled && *departure.displayed_time() >= now)
+    else {
+        return;
+    };
+    let minutes = next.minutes_until(now);
+    let rounded = (minutes + ANNOUNCEMENT_STEP_MINUTES / 2) / ANNOUNCEMENT_STEP_MINUTES
+        * ANNOUNCEMENT_STEP_MINUTES;
+    let key = (
+        next.vehicle_label.clone(),
+        next.destination.clone(),
+        rounded,
+    );
+    if last_announced.current().as_ref().as_ref() == Some(&key) {
+        return;
+    }
+    speech::speak(format!(
+        "Next departure: {} to {} in {minutes} minutes",
+        next.vehicle_label, next.destination
+    ));
+    last_announced.set(Some(key));
+}
+
+// Remembers every banner hash the API has returned, so a hash that was never
+// seen before can be flagged as a possible schema change.
+#[derive(Serialize, Deserialize, Default)]
+struct BannerHashTracker {
+    first_seen: BTreeMap<DateTime<Local>, String>,
+    #[serde(default)]
+    hash_length: Option<usize>,
+    #[serde(skip)]
+    known: HashSet<String>,
+}
+
+impl BannerHashTracker {
+    fn load() -> Self {
+        let mut tracker: BannerHashTracker =
+            load_json(config_path("banner_hashes.json")).unwrap_or_default();
+        tracker.known = tracker.first_seen.values().cloned().collect();
+        tracker
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        save_json(config_path("banner_hashes.json"), self)
+    }
+
+    // Returns the hashes that were not known before.
+    fn observe(&mut self, departures: &[Departure], now: DateTime<Local>) -> Vec<String> {
+        let mut new_hashes = Vec::new();
+        for departure in departures {
+            let hash = &departure.banner_hash;
+            if hash.is_empty() || !self.known.insert(hash.clone()) {
+                continue;
+            }
+            let mut seen_at = now;
+            while self.first_seen.contains_key(&seen_at) {
+                seen_at += Duration::nanoseconds(1);
+            }
+            self.first_seen.insert(seen_at, hash.clone());
+            new_hashes.push(hash.clone());
+        }
+        if self.first_seen.len() > BANNER_HASH_CAPACITY {
+            for _ in 0..BANNER_HASH_EVICTION {
+                if let Some((_, hash)) = self.first_seen.pop_first() {
+                    self.known.remove(&hash);
+                }
+            }
+        }
+        new_hashes
+    }
+}
+
+fn validate_banner_hash(hash: &str, expected_length: usize) -> bool {
+    !hash.is_empty()
+        && hash.len() == expected_length
+        && hash.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn track_banner_hashes(departures: &[Departure], tracker: &mut BannerHashTracker) -> usize {
+    let had_history = !tracker.known.is_empty();
+    let new_hashes = tracker.observe(departures, Local::now());
+    if new_hashes.is_empty() {
+        return 0;
+    }
+    let expected_length = *tracker.hash_length.get_or_insert(new_hashes[0].len());
+    for hash in &new_hashes {
+        if !validate_banner_hash(hash, expected_length) {
+            tracing::warn!(
+                hash,
+                expected_length,
+                "Banner hash does not match the known format, the API may have changed"
+            );
+        }
+    }
+    if let Err(e) = tracker.save() {
+        tracing::error!("Saving banner hashes failed: {e}");
+    }
+    if !had_history {
+        return 0;
+    }
+    tracing::warn!(
+        count = new_hashes.len(),
+        hashes = ?new_hashes,
+        "Previously unseen banner hashes, the API response format may have changed"
+    );
+    new_hashes.len()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+enum DelayTrend {
+    Worsening,
+    Improving,
+    #[default]
+    Stable,
+}
+
+impl DelayTrend {
+    fn symbol(self) -> &'static str {
+        match self {
+            DelayTrend::Worsening => "↑",
+            DelayTrend::Improving => "↓",
+            DelayTrend::Stable => "–",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            DelayTrend::Worsening => "Delays are getting worse",
+            DelayTrend::Improving => "Delays are getting better",
+            DelayTrend::Stable => "Delays are stable",
+        }
+    }
+}
+
+// Compares the mean of the latest known delays with the mean of the ones before,
+// using windows of up to three samples. Samples without real-time data are skipped.
+fn compute_delay_trend(samples: &VecDeque<Option<i64>>) -> DelayTrend {
+    let delays = samples.iter().flatten().copied().collect_vec();
+    let window = DELAY_TREND_WINDOW.min(delays.len() / 2);
+    if window == 0 {
+        return DelayTrend::Stable;
+    }
+    let mean = |delays: &[i64]| delays.iter().sum::<i64>() as f64 / delays.len() as f64;
+    let recent = mean(&delays[delays.len() - window..]);
+    let earlier = mean(&delays[delays.len() - 2 * window..delays.len() - window]);
+    if recent - earlier > DELAY_TREND_THRESHOLD_MINUTES {
+        DelayTrend::Worsening
+    } else if earlier - recent > DELAY_TREND_THRESHOLD_MINUTES {
+        DelayTrend::Improving
+    } else {
+        DelayTrend::Stable
+    }
+}
+
+// One sample per line and fetch: the delay of the line's next departure
+fn record_delay_samples(
+    samples: &mut HashMap<String, VecDeque<Option<i64>>>,
+    departures: &[Departure],
+) {
+    let mut sampled = HashSet::new();
+    for departure in departures.iter().filter(|departure| !departure.cancelled) {
+        if !sampled.insert(departure.vehicle_label.as_str()) {
+            continue;
+        }
+        let line_samples = samples.entry(departure.vehicle_label.clone()).or_default();
+        line_samples.push_back(departure.delay().map(|delay| delay.num_minutes()));
+        if line_samples.len() > DELAY_SAMPLE_COUNT {
+            line_samples.pop_front();
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+struct DelayStats {
+    count: u32,
+    total_minutes: i64,
+    max_minutes: i64,
+}
+
+impl DelayStats {
+    fn record(&mut self, minutes: i64) {
+        self.count += 1;
+        self.total_minutes += minutes;
+        self.max_minutes = self.max_minutes.max(minutes);
+    }
+
+    fn merge(&mut self, other: &DelayStats) {
+        self.count += other.count;
+        self.total_minutes += other.total_minutes;
+        self.max_minutes = self.max_minutes.max(other.max_minutes);
+    }
+
+    fn mean_minutes(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.total_minutes as f64 / self.count as f64
+    }
+}
+
+// Stats are kept per day so old days can be dropped from the rolling window.
+// Each trip is only counted once, when it departs, so repeated fetches of the
+// same departure don't skew the averages.
+#[derive(Serialize, Deserialize, Default)]
+struct DelayStatistics {
+    days: BTreeMap<NaiveDate, HashMap<String, DelayStats>>,
+    #[serde(skip)]
+    counted: HashSet<(String, DateTime<Local>)>,
+}
+
+impl DelayStatistics {
+    fn load() -> Self {
+        load_json(config_path("delay_stats.json")).unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        save_json(config_path("delay_stats.json"), self)
+    }
+
+    // Takes the departures that left the board, with the last delay seen for each
+    fn accumulate(
+        &mut self,
+        departed: &[Departure],
+        now: DateTime<Local>,
+        window_days: u32,
+    ) -> bool {
+        self.counted
+            .retain(|(_, planned_time)| *planned_time > now - Duration::hours(1));
+        let mut changed = false;
+        for departure in departed.iter().filter(|departure| !departure.cancelled) {
+            let Some(delay) = departure.delay() else {
+                continue;
+            };
+            if !self
+                .counted
+                .insert((departure.vehicle_label.clone(), departure.planned_time))
+            {
+                continue;
+            }
+            self.days
+                .entry(departure.planned_time.date_naive())
+                .or_default()
+                .entry(departure.vehicle_label.clone())
+                .or_default()
+                .record(delay.num_minutes());
+            changed = true;
+        }
+        let oldest = now.date_naive() - Duration::days(window_days.saturating_sub(1) as i64);
+        let days_before = self.days.len();
+        self.days.retain(|day, _| *day >= oldest);
+        changed || self.days.len() != days_before
+    }
+
+    fn per_line(&self) -> Vec<(String, DelayStats)> {
+        let mut totals = HashMap::<String, DelayStats>::new();
+        for stats in self.days.values() {
+            for (label, line_stats) in stats {
+                totals.entry(label.clone()).or_default().merge(line_stats);
+            }
+        }
+        totals
+            .into_iter()
+            .sorted_by(|(label1, _), (label2, _)| label1.cmp(label2))
+            .collect()
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct PlatformChangeEvent {
+    vehicle_label: String,
+    destination: String,
+    old_platform: u16,
+    new_platform: u16,
+    detected_at: DateTime<Local>,
+}
+
+impl PlatformChangeEvent {
+    fn is_active(&self, now: DateTime<Local>) -> bool {
+        now - self.detected_at < Duration::minutes(PLATFORM_CHANGE_TTL_MINUTES)
+    }
+}
+
+// Departures are matched by line and planned time, unknown platforms are ignored
+fn detect_platform_changes(
+    previous: &[Departure],
+    current: &[Departure],
+    now: DateTime<Local>,
+) -> Vec<PlatformChangeEvent> {
+    let previous_platforms: HashMap<_, _> = previous
+        .iter()
+        .map(|departure| {
+            (
+                (departure.vehicle_label.as_str(), departure.planned_time),
+                departure.platform,
+            )
+        })
+        .collect();
+    current
+        .iter()
+        .filter_map(|departure| {
+            let old_platform = *previous_platforms
+                .get(&(departure.vehicle_label.as_str(), departure.planned_time))?;
+            (old_platform != 0 && departure.platform != 0 && old_platform != departure.platform)
+                .then(|| PlatformChangeEvent {
+                    vehicle_label: departure.vehicle_label.clone(),
+                    destination: departure.destination.clone(),
+                    old_platform,
+                    new_platform: departure.platform,
+                    detected_at: now,
+                })
+        })
+        .collect()
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct DepartureDiff {
+    vehicle_label: String,
+    destination: String,
+    old_time: DateTime<Local>,
+    new_time: DateTime<Local>,
+    delta_seconds: i64,
+}
+
+impl DepartureDiff {
+    fn is_for(&self, departure: &Departure) -> bool {
+        self.vehicle_label == departure.vehicle_label
+            && self.destination == departure.destination
+            && self.new_time == departure.actual_time
+    }
+}
+
+// Matched like platform changes by line and planned time, so departures that
+// appear or disappear between fetches don't produce diffs
+fn diff_departures(prev: &[Departure], next: &[Departure]) -> Vec<DepartureDiff> {
+    let previous_times: HashMap<_, _> = prev
+        .iter()
+        .map(|departure| {
+            (
+                (departure.vehicle_label.as_str(), departure.planned_time),
+                departure.actual_time,
+            )
+        })
+        .collect();
+    next.iter()
+        .filter_map(|departure| {
+            let old_time =
+                *previous_times.get(&(departure.vehicle_label.as_str(), departure.planned_time))?;
+            (old_time != departure.actual_time).then(|| DepartureDiff {
+                vehicle_label: departure.vehicle_label.clone(),
+                destination: departure.destination.clone(),
+                old_time,
+                new_time: departure.actual_time,
+                delta_seconds: (departure.actual_time - old_time).num_seconds(),
+            })
+        })
+        .collect()
+}
+
+// The previous fetch of a board, kept to compare real-time updates against. It
+// only counts for the station it was fetched for.
+#[derive(Default)]
+struct DepartureCache {
+    previous: Option<(String, Vec<Departure>)>,
+}
+
+impl DepartureCache {
+    fn previous(&self, global_id: &str) -> Option<&[Departure]> {
+        self.previous
+            .as_ref()
+            .filter(|(previous_id, _)| previous_id == global_id)
+            .map(|(_, departures)| departures.as_slice())
+    }
+
+    // Returns the time changes since the previous fetch and remembers this one
+    fn update(&mut self, global_id: &str, departures: &[Departure]) -> Vec<DepartureDiff> {
+        let diffs = self
+            .previous(global_id)
+            .map(|previous| diff_departures(previous, departures))
+            .unwrap_or_default();
+        self.previous = Some((global_id.to_string(), departures.to_vec()));
+        diffs
+    }
+}
+
+// Departures that dropped off the front of the board since the previous fetch.
+// The API stops listing a departure once it has left, or once it is closer than
+// the station's offset, so this doesn't depend on the clock or the walk time.
+fn departed_departures(previous: &[Departure], departures: &[Departure]) -> Vec<Departure> {
+    let key = |departure: &Departure| {
+        (
+            departure.vehicle_label.clone(),
+            departure.destination.clone(),
+            departure.planned_time,
+        )
+    };
+    let listed = departures.iter().map(key).collect::<HashSet<_>>();
+    let first = departures.iter().map(Departure::displayed_time).min();
+    previous
+        .iter()
+        .filter(|departure| {
+            !listed.contains(&key(departure))
+                && first.is_none_or(|first| departure.displayed_time() <= first)
+        })
+        .cloned()
+        .collect()
+}
+
+#[derive(Clone, PartialEq)]
+struct FetchRecord {
+    fetched_at: DateTime<Local>,
+    departure_count: usize,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct HistoryCsvRow<'a> {
+    fetched_at: String,
+    departure_count: usize,
+    error: &'a str,
+}
+
+fn history_to_csv<'a>(
+    records: impl IntoIterator<Item = &'a FetchRecord>,
+) -> Result<String, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        writer.serialize(HistoryCsvRow {
+            fetched_at: record.fetched_at.to_rfc3339(),
+            departure_count: record.departure_count,
+            error: record.error.as_deref().unwrap_or_default(),
+        })?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn csv_log_path(date: NaiveDate) -> Option<PathBuf> {
+    project_dirs().map(|dirs| {
+        dirs.data_dir()
+            .join(format!("departures_{}.csv", date.format("%Y-%m-%d")))
+    })
+}
+
+// One line of the departure log. The field order is the column order.
+#[derive(Serialize)]
+struct DepartureCsvRow<'a> {
+    fetched_at: String,
+    vehicle_label: &'a str,
+    destination: &'a str,
+    planned_time: String,
+    actual_time: String,
+    delay_minutes: Option<i64>,
+    cancelled: bool,
+    occupancy: &'static str,
+    platform: u16,
+}
+
+impl<'a> DepartureCsvRow<'a> {
+    fn new(departure: &'a Departure, fetched_at: DateTime<Local>) -> Self {
+        DepartureCsvRow {
+            fetched_at: fetched_at.to_rfc3339(),
+            vehicle_label: &departure.vehicle_label,
+            destination: &departure.destination,
+            planned_time: departure.planned_time.to_rfc3339(),
+            actual_time: departure.actual_time.to_rfc3339(),
+            delay_minutes: departure.delay().map(|delay| delay.num_minutes()),
+            cancelled: departure.cancelled,
+            occupancy: departure.occupancy.name(),
+            platform: departure.platform,
+        }
+    }
+}
+
+fn write_departures_csv(
+    writer: impl std::io::Write,
+    departures: &[Departure],
+    fetched_at: DateTime<Local>,
+    with_header: bool,
+) -> Result<(), csv::Error> {
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(with_header)
+        .from_writer(writer);
+    for departure in departures {
+        writer.serialize(DepartureCsvRow::new(departure, fetched_at))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// A new file is started whenever the date changes, so the log rotates daily.
+fn log_departures_csv(
+    departures: &[Departure],
+    fetched_at: DateTime<Local>,
+) -> Result<(), Box<dyn Error>> {
+    // The header comes with the first row, so an empty fetch doesn't start a file
+    if departures.is_empty() {
+        return Ok(());
+    }
+    let path = csv_log_path(fetched_at.date_naive()).ok_or("No project directory available")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let is_new = !path.exists();
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    write_departures_csv(file, departures, fetched_at, is_new)?;
+    Ok(())
+}
+
+fn prune_csv_logs(today: NaiveDate) {
+    let Some(directory) = project_dirs().map(|dirs| dirs.data_dir().to_path_buf()) else {
+        return;
+    };
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    let oldest = today - Duration::days(CSV_LOG_RETENTION_DAYS - 1);
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let date = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix("departures_"))
+            .and_then(|name| name.strip_suffix(".csv"))
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+        if date.is_some_and(|date| date < oldest) {
+            if let Err(e) = std::fs::remove_file(entry.path()) {
+                tracing::error!("Removing old CSV log {file_name:?} failed: {e}");
+            }
+        }
+    }
+}
+
+// Upper bounds of the latency buckets, the last bucket takes everything slower
+const LATENCY_BUCKET_BOUNDS_MS: [u64; 9] = [50, 100, 200, 300, 500, 750, 1000, 2000, 5000];
+const HISTOGRAM_WIDTH: u64 = 20;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct FetchMetrics {
+    latency_ms_buckets: [u64; 10],
+    total_fetches: u64,
+    total_errors: u64,
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+}
+
+impl FetchMetrics {
+    fn load() -> Self {
+        load_json(config_path("fetch_metrics.json")).unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        save_json(config_path("fetch_metrics.json"), self)
+    }
+
+    // Interpolates linearly inside the bucket holding the percentile
+    fn percentile(&self, percentile: f64) -> f64 {
+        let total = self.latency_ms_buckets.iter().sum::<u64>();
+        if total == 0 {
+            return 0.0;
+        }
+        let rank = percentile / 100.0 * total as f64;
+        let mut below = 0;
+        for (index, &count) in self.latency_ms_buckets.iter().enumerate() {
+            let lower = index
+                .checked_sub(1)
+                .map_or(0, |previous| LATENCY_BUCKET_BOUNDS_MS[previous]);
+            if count > 0 && (below + count) as f64 >= rank {
+                let Some(&upper) = LATENCY_BUCKET_BOUNDS_MS.get(index) else {
+                    return lower as f64;
+                };
+                let fraction = (rank - below as f64) / count as f64;
+                return lower as f64 + fraction * (upper - lower) as f64;
+            }
+            below += count;
+        }
+        LATENCY_BUCKET_BOUNDS_MS[LATENCY_BUCKET_BOUNDS_MS.len() - 1] as f64
+    }
+
+    fn histogram(&self) -> String {
+        let max = self
+            .latency_ms_buckets
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        self.latency_ms_buckets
+            .iter()
+            .enumerate()
+            .map(|(index, &count)| {
+                let label = match LATENCY_BUCKET_BOUNDS_MS.get(index) {
+                    Some(upper) => format!("<{upper}ms"),
+                    None => format!(">={}ms", LATENCY_BUCKET_BOUNDS_MS[index - 1]),
+                };
+                let bar = "█".repeat((count * HISTOGRAM_WIDTH).div_ceil(max) as usize);
+                format!("{label:>8} |{bar} {count}")
+            })
+            .join("\n")
+    }
+}
+
+// A latency equal to a bucket bound goes into the next bucket up.
+fn update_latency_histogram(metrics: &mut FetchMetrics, latency_ms: u64) {
+    let bucket = LATENCY_BUCKET_BOUNDS_MS.partition_point(|&upper| upper <= latency_ms);
+    metrics.latency_ms_buckets[bucket] += 1;
+    metrics.total_fetches += 1;
+    metrics.p50_ms = metrics.percentile(50.0);
+    metrics.p95_ms = metrics.percentile(95.0);
+    metrics.p99_ms = metrics.percentile(99.0);
+}
+
+#[inline_props]
+fn FetchMetricsView<'a>(cx: Scope, fetch_metrics: &'a UseRef<FetchMetrics>) -> Element<'a> {
+    let metrics = fetch_metrics.read();
+    cx.render(rsx!(
+        div {
+            class: "fetch_metrics",
+            div {
+                "{metrics.total_fetches} fetches, {metrics.total_errors} errors · "
+                "p50 {metrics.p50_ms:.0}ms · p95 {metrics.p95_ms:.0}ms · p99 {metrics.p99_ms:.0}ms "
+                button {
+                    onclick: move |_| {
+                        *fetch_metrics.write() = FetchMetrics::default();
+                        if let Err(e) = fetch_metrics.read().save() {
+                            tracing::error!("Saving fetch metrics failed: {e}");
+                        }
+                    },
+                    "Reset"
+                }
+            }
+            pre { class: "latency_histogram", "{metrics.histogram()}" }
+        }
+    ))
+}
+
+#[inline_props]
+fn HistoryPanel<'a>(
+    cx: Scope,
+    history: &'a UseState<VecDeque<FetchRecord>>,
+    fetch_metrics: &'a UseRef<FetchMetrics>,
+) -> Element<'a> {
+    let eval = use_eval(cx);
+    cx.render(rsx!(
+        div {
+            class: "history_panel",
+            button {
+                onclick: move |_| {
+                    let csv = match history_to_csv(history.iter()) {
+                        Ok(csv) => serde_json::to_string(&csv).unwrap_or_default(),
+                        Err(e) => {
+                            tracing::error!("Writing the history as CSV failed: {e}");
+                            return;
+                        }
+                    };
+                    if let Err(e) = eval(&format!("navigator.clipboard.writeText({csv})")) {
+                        tracing::error!("Copying history to clipboard failed: {e:?}");
+                    }
+                },
+                "Copy as CSV"
+            }
+            FetchMetricsView { fetch_metrics: fetch_metrics }
+            table {
+                tr {
+                    th { "Time" }
+                    th { "Departures" }
+                    th { "Error" }
+                }
+                history.iter().map(|record| {
+                    let fetched_at = record.fetched_at.format("%H:%M:%S");
+                    rsx!(tr {
+                        td { "{fetched_at}" }
+                        td { "{record.departure_count}" }
+                        td { record.error.as_deref().unwrap_or_default() }
+                    })
+                })
+            }
+        }
+    ))
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedResponse<T> {
+    global_id: String,
+    departures: T,
+}
+
+fn cache_path(global_id: &str) -> Option<PathBuf> {
+    project_dirs().map(|dirs| {
+        dirs.cache_dir()
+            .join(format!("departures_{}.json", global_id.replace(':', "_")))
+    })
+}
+
+fn load_cached_departures(config: &StationConfig) -> Option<Vec<Departure>> {
+    let path = cache_path(&config.global_id)?;
+    let age = std::fs::metadata(&path)
+        .ok()?
+        .modified()
+        .ok()?
+        .elapsed()
+        .ok()?;
+    if age > CACHE_MAX_AGE {
+        return None;
+    }
+    let cached: CachedResponse<Vec<Departure>> =
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    (cached.global_id == config.global_id).then_some(cached.departures)
+}
+
+fn save_cached_departures(
+    config: &StationConfig,
+    departures: &[Departure],
+) -> Result<(), Box<dyn Error>> {
+    save_json(
+        cache_path(&config.global_id),
+        &CachedResponse {
+            global_id: config.global_id.clone(),
+            departures,
+        },
+    )
+}
+
+async fn get_response(config: &StationConfig) -> Result<Vec<Departure>, MvgError> {
+    get_response_from(MVG_API_URL, config).await
+}
+
+async fn get_response_from(
+    base_url: &str,
+    config: &StationConfig,
+) -> Result<Vec<Departure>, MvgError> {
+    let span = tracing::info_span!(
+        "fetch",
+        station = %config.global_id,
+        duration_ms = tracing::field::Empty,
+        departure_count = tracing::field::Empty,
+        parse_errors = tracing::field::Empty,
+        error = tracing::field::Empty,
+    );
+    let started = std::time::Instant::now();
+    let result = fetch_departures(base_url, config)
+        .instrument(span.clone())
+        .await;
+    span.record("duration_ms", started.elapsed().as_millis() as u64);
+    match &result {
+        Ok(departures) => span.record("departure_count", departures.len()),
+        Err(e) => span.record("error", tracing::field::display(e)),
+    };
+    result
+}
+
+async fn get_both_directions(
+    outbound: &StationConfig,
+    return_config: &StationConfig,
+) -> (
+    Result<Vec<Departure>, MvgError>,
+    Result<Vec<Departure>, MvgError>,
+) {
+    tokio::join!(get_response(outbound), get_response(return_config))
+}
+
+async fn batch_fetch_departures(
+    stations: &[StationConfig],
+) -> Vec<(StationConfig, Result<Vec<Departure>, MvgError>)> {
+    let responses = futures_util::future::join_all(stations.iter().map(get_response)).await;
+    stations.iter().cloned().zip(responses).collect()
+}
+
+// The sort is stable, so departures at the same time keep the order of the
+// lists and their order within each list.
+fn merge_departure_lists(lists: &[(String, Vec<Departure>)]) -> Vec<(&str, &Departure)> {
+    lists
+        .iter()
+        .flat_map(|(station, departures)| {
+            departures
+                .iter()
+                .map(move |departure| (station.as_str(), departure))
+        })
+        .sorted_by_key(|(_, departure)| *departure.displayed_time())
+        .collect()
+}
+
+async fn fetch_departures(
+    base_url: &str,
+    config: &StationConfig,
+) -> Result<Vec<Departure>, MvgError> {
+    fetch_departures_from(build_departure_url(base_url, config)).await
+}
+
+const LOW_RATE_LIMIT_REMAINING: u32 = 2;
+
+// Retry-After is either a number of seconds or an HTTP date
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<std::time::Duration> {
+    let value = headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(std::time::Duration::from_secs(seconds));
+    }
+    let retry_at = DateTime::parse_from_rfc2822(value).ok()?;
+    (retry_at.with_timezone(&Utc) - Utc::now()).to_std().ok()
+}
+
+fn parse_rate_limit_remaining(headers: &reqwest::header::HeaderMap) -> Option<u32> {
+    headers
+        .get("x-ratelimit-remaining")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// The MVG API limits the client rather than a station, so all boards share one guard
+#[derive(Default)]
+struct RateLimit {
+    retry_at: Option<tokio::time::Instant>,
+    remaining: Option<u32>,
+}
+
+static RATE_LIMIT: Lazy<std::sync::Mutex<RateLimit>> = Lazy::new(Default::default);
+
+impl RateLimit {
+    fn record(&mut self, headers: &reqwest::header::HeaderMap) {
+        if let Some(retry_after) = parse_retry_after(headers) {
+            tracing::warn!(?retry_after, "MVG API asked to retry later");
+            self.retry_at = Some(tokio::time::Instant::now() + retry_after);
+        }
+        self.remaining = parse_rate_limit_remaining(headers);
+    }
+
+    // Polls half as often while few requests are left
+    fn interval_factor(&self) -> u64 {
+        if self
+            .remaining
+            .is_some_and(|remaining| remaining < LOW_RATE_LIMIT_REMAINING)
+        {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+fn rate_limit() -> std::sync::MutexGuard<'static, RateLimit> {
+    RATE_LIMIT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+async fn wait_for_rate_limit() {
+    let retry_at = rate_limit().retry_at;
+    if let Some(retry_at) = retry_at.filter(|retry_at| *retry_at > tokio::time::Instant::now()) {
+        tracing::info!("Waiting for the MVG API rate limit to reset");
+        tokio::time::sleep_until(retry_at).await;
+    }
+}
+
+async fn fetch_departures_from(url: String) -> Result<Vec<Departure>, MvgError> {
+    let response = HTTP_CLIENT.get(url).send().await?;
+    rate_limit().record(response.headers());
+    departures_from_response(response).await
+}
+
+fn build_schedule_url(global_id: &str, offset_minutes: i64) -> String {
+    reqwest::Url::parse_with_params(
+        &format!("{MVG_API_URL}/departure"),
+        [
+            ("globalId", global_id.to_string()),
+            ("limit", SCHEDULE_PAGE_LIMIT.to_string()),
+            ("offsetInMinutes", offset_minutes.to_string()),
+            ("transportTypes", requested_transport_types()),
+        ],
+    )
+    .map(|url| url.to_string())
+    .unwrap_or_default()
+}
+
+// The departure API only returns a window of departures, so the day is
+// loaded page by page, moving offsetInMinutes past the last departure seen.
+async fn get_day_schedule(
+    global_id: &str,
+    label: &str,
+    destination: &str,
+) -> Result<Vec<Departure>, MvgError> {
+    let now = Local::now();
+    let start_of_day = now
+        .date_naive()
+        .and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .unwrap_or(now);
+    let end_of_day = start_of_day + Duration::days(1);
+    let mut offset_minutes = (start_of_day - now).num_minutes();
+    let mut schedule = Vec::new();
+    for _ in 0..SCHEDULE_MAX_PAGES {
+        let page = fetch_departures_from(build_schedule_url(global_id, offset_minutes)).await?;
+        let Some(last_planned) = page.iter().map(|departure| departure.planned_time).max() else {
+            break;
+        };
+        schedule.extend(page.into_iter().filter(|departure| {
+            departure.vehicle_label == label
+                && departure.destination == destination
+                && departure.planned_time < end_of_day
+        }));
+        if last_planned >= end_of_day {
+            break;
+        }
+        offset_minutes = ((last_planned - now).num_minutes() + 1).max(offset_minutes + 1);
+    }
+    schedule.sort_by_key(|departure| departure.planned_time);
+    schedule.dedup_by_key(|departure| departure.planned_time);
+    Ok(schedule)
+}
+
+async fn search_stations(query: &str) -> Result<Vec<RawLocation>, Box<dyn Error>> {
+    let url =
+        reqwest::Url::parse_with_params(&format!("{MVG_API_URL}/location"), [("query", query)])?;
+    Ok(HTTP_CLIENT
+        .get(url)
+        .send()
+        .await?
+        .json::<Vec<RawLocation>>()
+        .await?
+        .into_iter()
+        .filter(|location| !location.global_id.is_empty())
+        .collect())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawConnection {
+    parts: Vec<RawConnectionPart>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawConnectionPart {
+    from: RawConnectionStop,
+    to: RawConnectionStop,
+    line: RawConnectionLine,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawConnectionStop {
+    name: String,
+    planned_departure: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawConnectionLine {
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    transport_type: String,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Leg {
+    line: String,
+    departure: DateTime<Local>,
+    arrival: DateTime<Local>,
+    from_stop: String,
+    to_stop: String,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Journey {
+    duration_minutes: u32,
+    legs: Vec<Leg>,
+    changes: u8,
+}
+
+impl From<RawConnection> for Journey {
+    fn from(value: RawConnection) -> Self {
+        let is_walk = |part: &RawConnectionPart| part.line.transport_type == "PEDESTRIAN";
+        let rides = value.parts.iter().filter(|part| !is_walk(part)).count();
+        let legs = value
+            .parts
+            .into_iter()
+            .map(|part| Leg {
+                line: if part.line.label.is_empty() {
+                    "Walk".to_string()
+                } else {
+                    part.line.label
+                },
+                departure: part.from.planned_departure.with_timezone(&Local),
+                arrival: part.to.planned_departure.with_timezone(&Local),
+                from_stop: part.from.name,
+                to_stop: part.to.name,
+            })
+            .collect_vec();
+        let duration_minutes = match (legs.first(), legs.last()) {
+            (Some(first), Some(last)) => {
+                (last.arrival - first.departure).num_minutes().max(0) as u32
+            }
+            _ => 0,
+        };
+        Journey {
+            duration_minutes,
+            legs,
+            changes: rides.saturating_sub(1).min(u8::MAX as usize) as u8,
+        }
+    }
+}
+
+fn build_connection_url(from: &str, to: &str, time: DateTime<Local>) -> String {
+    reqwest::Url::parse_with_params(
+        &format!("{MVG_API_URL}/connection"),
+        [
+            ("originStationGlobalId", from.to_string()),
+            ("destinationStationGlobalId", to.to_string()),
+            (
+                "routingDateTime",
+                time.with_timezone(&Utc)
+                    .to_rfc3339_opts(SecondsFormat::Millis, true),
+            ),
+            ("routingDateTimeIsArrival", "false".to_string()),
+        ],
+    )
+    .map(|url| url.to_string())
+    .unwrap_or_default()
+}
+
+async fn get_connection(
+    from: &str,
+    to: &str,
+    time: DateTime<Local>,
+) -> Result<Vec<Journey>, MvgError> {
+    let response = HTTP_CLIENT
+        .get(build_connection_url(from, to, time))
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(MvgError::RateLimited);
+    }
+    let body = response.error_for_status()?.bytes().await?;
+    Ok(serde_json::from_slice::<Vec<RawConnection>>(&body)?
+        .into_iter()
+        .map(Journey::from)
+        .collect())
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct TravelTimeCache {
+    times: HashMap<String, Duration>,
+    fetched_at: DateTime<Local>,
+}
+
+impl TravelTimeCache {
+    fn from_journeys(journeys: &[Journey], fetched_at: DateTime<Local>) -> TravelTimeCache {
+        let mut times = HashMap::new();
+        for journey in journeys.iter().filter(|journey| journey.changes == 0) {
+            let Some(leg) = journey.legs.iter().find(|leg| leg.line != "Walk") else {
+                continue;
+            };
+            let duration = Duration::minutes(journey.duration_minutes as i64);
+            times
+                .entry(leg.line.clone())
+                .and_modify(|time: &mut Duration| *time = (*time).min(duration))
+                .or_insert(duration);
+        }
+        TravelTimeCache { times, fetched_at }
+    }
+
+    fn is_fresh(&self, now: DateTime<Local>) -> bool {
+        (now - self.fetched_at)
+            .to_std()
+            .is_ok_and(|age| age < TRAVEL_TIME_CACHE_TTL)
+    }
+}
+
+fn estimated_arrival(
+    departure: &Departure,
+    configured: &HashMap<String, u32>,
+    cache: Option<&TravelTimeCache>,
+) -> Option<DateTime<Local>> {
+    let travel_time = configured
+        .get(&departure.vehicle_label)
+        .map(|minutes| Duration::minutes(*minutes as i64))
+        .or_else(|| cache?.times.get(&departure.vehicle_label).copied())?;
+    Some(*departure.displayed_time() + travel_time)
+}
+
+const DATETIME_INPUT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+fn parse_datetime_input(value: &str) -> Option<DateTime<Local>> {
+    NaiveDateTime::parse_from_str(value, DATETIME_INPUT_FORMAT)
+        .ok()?
+        .and_local_timezone(Local)
+        .single()
+}
+
+fn parse_travel_times(text: &str) -> HashMap<String, u32> {
+    text.split(',')
+        .filter_map(|entry| {
+            let (label, minutes) = entry.split_once('=')?;
+            Some((label.trim().to_string(), minutes.trim().parse().ok()?))
+        })
+        .filter(|(label, _)| !label.is_empty())
+        .collect()
+}
+
+fn format_travel_times(times: &HashMap<String, u32>) -> String {
+    times
+        .iter()
+        .sorted()
+        .map(|(label, minutes)| format!("{label}={minutes}"))
+        .join(", ")
+}
+
+#[derive(Deserialize)]
+struct RawDisruptionLine {
+    label: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawDisruption {
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    lines: Vec<RawDisruptionLine>,
+    #[serde(rename = "validFrom")]
+    valid_from_ms: u64,
+    #[serde(rename = "validTo", default)]
+    valid_to_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum DisruptionSeverity {
+    Low,
+    Medium,
+    High,
+}
+
+impl DisruptionSeverity {
+    fn from_kind(kind: &str) -> DisruptionSeverity {
+        match kind {
+            "INCIDENT" => DisruptionSeverity::High,
+            "SCHEDULE_CHANGE" => DisruptionSeverity::Medium,
+            _ => DisruptionSeverity::Low,
+        }
+    }
+
+    fn css_class(self) -> &'static str {
+        match self {
+            DisruptionSeverity::Low => "severity_low",
+            DisruptionSeverity::Medium => "severity_medium",
+            DisruptionSeverity::High => "severity_high",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Disruption {
+    title: String,
+    text: String,
+    lines: Vec<String>,
+    valid_from: DateTime<Local>,
+    valid_to: Option<DateTime<Local>>,
+    severity: DisruptionSeverity,
+}
+
+impl TryFrom<RawDisruption> for Disruption {
+    type Error = MvgError;
+
+    fn try_from(value: RawDisruption) -> Result<Self, Self::Error> {
+        Ok(Disruption {
+            title: value.title,
+            text: value.description,
+            lines: value.lines.into_iter().map(|line| line.label).collect(),
+            valid_from: local_time_from_millis(value.valid_from_ms)?,
+            valid_to: value.valid_to_ms.map(local_time_from_millis).transpose()?,
+            severity: DisruptionSeverity::from_kind(&value.kind),
+        })
+    }
+}
+
+async fn get_disruptions() -> Result<Vec<Disruption>, MvgError> {
+    let response = HTTP_CLIENT
+        .get(format!("{MVG_API_URL}/messages"))
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(MvgError::RateLimited);
+    }
+    let body = response.error_for_status()?.bytes().await?;
+    serde_json::from_slice::<Vec<RawDisruption>>(&body)?
+        .into_iter()
+        .map(Disruption::try_from)
+        .collect()
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+struct Ticker {
+    text: String,
+    #[serde(default)]
+    lines: Vec<String>,
+}
+
+async fn get_tickers() -> Result<Vec<Ticker>, MvgError> {
+    let response = HTTP_CLIENT
+        .get(format!("{MVG_API_URL}/ticker"))
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(MvgError::RateLimited);
+    }
+    let body = response.error_for_status()?.bytes().await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+fn relevant_tickers<'a>(
+    tickers: &'a [Ticker],
+    displayed_lines: &HashSet<String>,
+) -> Vec<&'a Ticker> {
+    tickers
+        .iter()
+        .filter(|ticker| {
+            ticker
+                .lines
+                .iter()
+                .any(|line| displayed_lines.contains(line))
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct RawStopPoint {
+    name: String,
+    place: String,
+    street: Option<String>,
+    wheelchair_accessible: bool,
+    tactile_paving: bool,
+    shelter: bool,
+    live_departure_display: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct StopPointInfo {
+    name: String,
+    address: String,
+    wheelchair_accessible: bool,
+    tactile_paving: bool,
+    shelter: bool,
+    live_departure_display: bool,
+}
+
+impl From<RawStopPoint> for StopPointInfo {
+    fn from(value: RawStopPoint) -> Self {
+        let address = match value.street {
+            Some(street) if !street.is_empty() => format!("{street}, {}", value.place),
+            _ => value.place,
+        };
+        StopPointInfo {
+            name: value.name,
+            address,
+            wheelchair_accessible: value.wheelchair_accessible,
+            tactile_paving: value.tactile_paving,
+            shelter: value.shelter,
+            live_departure_display: value.live_departure_display,
+        }
+    }
+}
+
+async fn get_stop_point(id: &GlobalId) -> Result<StopPointInfo, MvgError> {
+    let response = HTTP_CLIENT
+        .get(format!("{MVG_API_URL}/stoppoint/{id}"))
+        .send()
+        .await?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(MvgError::RateLimited);
+    }
+    let body = response.error_for_status()?.bytes().await?;
+    Ok(serde_json::from_slice::<RawStopPoint>(&body)?.into())
+}
+
+#[inline_props]
+fn StationSearch<'a>(cx: Scope, on_select: EventHandler<'a, RawLocation>) -> Element<'a> {
+    let query = use_state(cx, String::new);
+    let results = use_state(cx, Vec::<RawLocation>::new);
+    let search = use_coroutine(cx, |mut rx: UnboundedReceiver<String>| {
+        let results = results.to_owned();
+        async move {
+            while let Some(mut query) = rx.next().await {
+                loop {
+                    tokio::select! {
+                        next = rx.next() => match next {
+                            Some(next) => query = next,
+                            None => return,
+                        },
+                        _ = tokio::time::sleep(std::time::Duration::from_millis(300)) => break,
+                    }
+                }
+                if query.trim().is_empty() {
+                    results.set(Vec::new());
+                    continue;
+                }
+                match search_stations(query.trim()).await {
+                    Ok(locations) => results.set(locations),
+                    Err(e) => tracing::error!("Station search failed: {e}"),
+                }
+            }
+        }
+    });
+    cx.render(rsx!(
+        div {
+            class: "station_search",
+            input {
+                onkeydown: |evt| evt.stop_propagation(),
+                placeholder: "Search station",
+                value: "{query}",
+                oninput: move |evt| {
+                    query.set(evt.value.clone());
+                    search.send(evt.value.clone());
+                }
+            }
+            if !results.is_empty() {
+                rsx!(
+                    div {
+                        class: "station_search_results",
+                        results.iter().map(|location| {
+                            let location = location.clone();
+                            rsx!(
+                                div {
+                                    class: "station_search_result",
+                                    onclick: move |_| {
+                                        query.set(String::new());
+                                        results.set(Vec::new());
+                                        on_select.call(location.clone());
+                                    },
+                                    "{location.name}, {location.place}"
+                                }
+                            )
+                        })
+                    }
+                )
+            }
+        }
+    ))
+}
+
+fn advance_load_progress(progress: u8) -> u8 {
+    if progress >= LOAD_PROGRESS_MAX {
+        return progress;
+    }
+    (progress + ((LOAD_PROGRESS_MAX - progress) / 10).max(1)).min(LOAD_PROGRESS_MAX)
+}
+
+#[inline_props]
+fn ProgressBar(cx: Scope, active: bool, progress: u8) -> Element<'a> {
+    cx.render(rsx!(
+        div {
+            class: if *active { "load_progress" } else { "load_progress done" },
+            div { class: "load_progress_bar", style: "width: {progress}%" }
+        }
+    ))
+}
+
+fn page_range(len: usize, page: usize, page_size: usize) -> (std::ops::Range<usize>, usize) {
+    let page_count = len.div_ceil(page_size).max(1);
+    let page = page.min(page_count - 1);
+    let start = page * page_size;
+    (start..(start + page_size).min(len), page_count)
+}
+
+fn is_stale(last_fetch: DateTime<Local>, now: DateTime<Local>, interval_secs: u64) -> bool {
+    now - last_fetch > Duration::seconds(2 * interval_secs as i64)
+}
+
+#[inline_props]
+fn StationBoard<'a>(
+    cx: Scope,
+    station_config: StationConfig,
+    active: bool,
+    now: DateTime<Local>,
+    watch_config: &'a UseState<WatchConfig>,
+    settings: &'a UseState<Config>,
+    notified: &'a UseRef<HashSet<(String, String, DateTime<Local>)>>,
+    banner_hashes: &'a UseRef<BannerHashTracker>,
+    delay_stats: &'a UseRef<DelayStatistics>,
+    fetch_metrics: &'a UseRef<FetchMetrics>,
+    history: &'a UseState<VecDeque<FetchRecord>>,
+    show_filters: bool,
+    refresh_generation: u64,
+    on_reset_offset: EventHandler<'a, ()>,
+    time_display_mode: TimeDisplayMode,
+    focus: &'a UseState<Option<(String, String)>>,
+    schedule: &'a UseState<Option<(String, String)>>,
+    imminent_stations: &'a UseRef<HashSet<String>>,
+    layout: RenderedLayout,
+    displayed_lines: &'a UseRef<HashSet<String>>,
+    board_departures: &'a UseRef<HashMap<String, Vec<Departure>>>,
+    deep_link_filter: &'a UseRef<Option<DepartureFilter>>,
+    muted: bool,
+) -> Element<'a> {
+    let seen_refresh_generation = use_ref(cx, || *refresh_generation);
+    let latest_config = use_ref(cx, || station_config.clone());
+    let latest_active = use_ref(cx, || *active);
+    let cached_departures = cx.use_hook(|| load_cached_departures(station_config));
+    let is_cached = use_state(cx, || cached_departures.is_some());
+    let last_successful_fetch = use_state(cx, || None::<DateTime<Local>>);
+    let fetches_saved = use_state(cx, || 0u32);
+    let new_banner_hashes = use_state(cx, || 0usize);
+    let fewer_results = use_state(cx, || false);
+    let load_progress = use_state(cx, || 0u8);
+    let current_response = use_state(cx, || cached_departures.take().map(Ok));
+    let return_response = use_state(cx, || None::<Result<Vec<Departure>, MvgError>>);
+    #[cfg(feature = "ws")]
+    let live_connected = use_ref(cx, || false);
+    let is_fetching = use_state(cx, || false);
+    let retry_count = use_state(cx, || 0u32);
+    let next_refresh = use_state(cx, || None);
+    let fetch_generation = use_state(cx, || 0u64);
+    let platform_changes = use_state(cx, Vec::<PlatformChangeEvent>::new);
+    let time_diffs = use_state(cx, Vec::<DepartureDiff>::new);
+    let delay_samples = use_state(cx, HashMap::<String, VecDeque<Option<i64>>>::new);
+    let last_announced = use_state(cx, || None::<(String, String, i64)>);
+    let fetcher = use_coroutine(cx, |mut rx: UnboundedReceiver<FetchCommand>| {
+        let settings = (*settings).clone();
+        let next_refresh = next_refresh.to_owned();
+        let watch_config = (*watch_config).clone();
+        let notified = (*notified).clone();
+        let history = (*history).clone();
+        let is_fetching = is_fetching.to_owned();
+        let retry_count = retry_count.to_owned();
+        let current_response = current_response.to_owned();
+        let return_response = return_response.to_owned();
+        let fetch_generation = fetch_generation.to_owned();
+        let platform_changes = platform_changes.to_owned();
+        let time_diffs = time_diffs.to_owned();
+        let delay_samples = delay_samples.to_owned();
+        let last_announced = last_announced.to_owned();
+        let board_departures = (*board_departures).clone();
+        #[cfg(feature = "ws")]
+        let live_connected = live_connected.to_owned();
+        let latest_config = latest_config.to_owned();
+        let latest_active = latest_active.to_owned();
+        let is_cached = is_cached.to_owned();
+        let last_successful_fetch = last_successful_fetch.to_owned();
+        let fetches_saved = fetches_saved.to_owned();
+        let new_banner_hashes = new_banner_hashes.to_owned();
+        let banner_hashes = (*banner_hashes).clone();
+        let delay_stats = (*delay_stats).clone();
+        let fetch_metrics = (*fetch_metrics).clone();
+        let fewer_results = fewer_results.to_owned();
+        let load_progress = load_progress.to_owned();
+        async move {
+            let mut previous_departures: Option<Vec<Departure>> = None;
+            let mut notified_deadline = None;
+            let mut departure_cache = DepartureCache::default();
+            let mut live_update = None;
+            loop {
+                #[cfg(feature = "ws")]
+                if live_update.is_none() && *live_connected.read() {
+                    // The socket pushes new departures, so only fetch when asked to
+                    let interval_secs = settings.current().refresh_interval_secs;
+                    let command = tokio::select! {
+                        _ = tokio::time::sleep(std::time::Duration::from_secs(interval_secs)) => None,
+                        Some(command) = rx.next() => Some(command),
+                    };
+                    match command {
+                        None => continue,
+                        Some(command) => live_update = command.into_live_update(),
+                    }
+                }
+                let config = latest_config.read().clone();
+                let (generation, response, return_departures) = match live_update.take() {
+                    Some((generation, response)) => (generation, response, None),
+                    None => {
+                        is_fetching.set(true);
+                        load_progress.set(0);
+                        let generation = *fetch_generation.current();
+                        let mut attempt = 0;
+                        let return_config = settings
+                            .current()
+                            .return_station
+                            .as_ref()
+                            .map(|return_station| return_station.station(&config));
+                        let (response, return_departures) = loop {
+                            wait_for_rate_limit().await;
+                            let started = std::time::Instant::now();
+                            let (response, return_departures) = match &return_config {
+                                Some(return_config) => {
+                                    let (response, return_departures) =
+                                        get_both_directions(&config, return_config).await;
+                                    (response, Some(return_departures))
+                                }
+                                None => (get_response(&config).await, None),
+                            };
+                            {
+                                let mut metrics = fetch_metrics.write_silent();
+                                update_latency_histogram(&mut metrics, started.elapsed().as_millis() as u64);
+                                if response.is_err() {
+                                    metrics.total_errors += 1;
+                                }
+                                if let Err(e) = metrics.save() {
+                                    tracing::error!("Saving fetch metrics failed: {e}");
+                                }
+                            }
+                            if let Err(e) = &response {
+                                if attempt < RETRY_DELAYS_SECS.len() && e.is_retryable() {
+                                    tracing::warn!(attempt, error = %e, "Fetching departures failed, retrying");
+                                    retry_count.set(attempt as u32 + 1);
+                                    tokio::time::sleep(std::time::Duration::from_secs(
+                                        RETRY_DELAYS_SECS[attempt],
+                                    ))
+                                    .await;
+                                    attempt += 1;
+                                    continue;
+                                }
+                            }
+                            break (response, return_departures);
+                        };
+                        retry_count.set(0);
+                        (generation, response, Some(return_departures))
+                    }
+                };
+                if is_stale_fetch(generation, *fetch_generation.current(), &mut rx) {
+                    tracing::debug!(generation, "Discarding departures for outdated settings");
+                    // The loop may wait before fetching again, e.g. for the live
+                    // socket, so the discarded fetch must not leave the bar running
+                    is_fetching.set(false);
+                    load_progress.set(100);
+                    continue;
+                }
+                if let Some(return_departures) = return_departures {
+                    return_response.set(return_departures);
+                }
+                if let Ok(departures) = &response {
+                    if let Err(e) = save_cached_departures(&config, departures) {
+                        tracing::error!("Caching departures failed: {e}");
+                    }
+                    is_cached.set(false);
+                    last_successful_fetch.set(Some(Local::now()));
+                    let departed = departure_cache
+                        .previous(&config.global_id)
+                        .map(|previous| departed_departures(previous, departures))
+                        .unwrap_or_default();
+                    if let Some(previous) = departure_cache.previous(&config.global_id) {
+                        let changes = detect_platform_changes(previous, departures, Local::now());
+                        if !changes.is_empty() || !platform_changes.current().is_empty() {
+                            platform_changes.with_mut(|platform_changes| {
+                                platform_changes.retain(|change| change.is_active(Local::now()));
+                                platform_changes.extend(changes);
+                            });
+                        }
+                    }
+                    let diffs = departure_cache.update(&config.global_id, departures);
+                    for diff in &diffs {
+                        tracing::info!(
+                            line = %diff.vehicle_label,
+                            destination = %diff.destination,
+                            old_time = %diff.old_time.format("%H:%M:%S"),
+                            new_time = %diff.new_time.format("%H:%M:%S"),
+                            delta_seconds = diff.delta_seconds,
+                            "Departure time changed"
+                        );
+                    }
+                    time_diffs.set(diffs);
+                    board_departures
+                        .write_silent()
+                        .insert(config.global_id.clone(), departures.clone());
+                    delay_samples.with_mut(|samples| record_delay_samples(samples, departures));
+                    #[cfg(feature = "tray")]
+                    if *latest_active.read() {
+                        tray::update(departures, Local::now());
+                    }
+                    fewer_results.set(departures.len() < config.limit as usize);
+                    notify_watched_departures(
+                        departures,
+                        &watch_config.current(),
+                        &mut notified.write_silent(),
+                    );
+                    if settings.current().csv_logging {
+                        if let Err(e) = log_departures_csv(departures, Local::now()) {
+                            tracing::error!("Logging departures to CSV failed: {e}");
+                        }
+                    }
+                    let stats_changed = delay_stats.write_silent().accumulate(
+                        &departed,
+                        Local::now(),
+                        settings.current().delay_stats_days,
+                    );
+                    if stats_changed {
+                        if let Err(e) = delay_stats.read().save() {
+                            tracing::error!("Saving delay statistics failed: {e}");
+                        }
+                    }
+                    let new_hashes =
+                        track_banner_hashes(departures, &mut banner_hashes.write_silent());
+                    if new_hashes > 0 {
+                        new_banner_hashes.modify(|count| count + new_hashes);
+                    }
+                    if *latest_active.read() && settings.current().tts_enabled {
+                        announce_next_departure(departures, Local::now(), &last_announced);
+                    }
+                    if *latest_active.read() {
+                        notify_connection_alert(
+                            departures,
+                            settings.current().connection_alert.as_ref(),
+                            &mut notified_deadline,
+                        );
+                    }
+                }
+                let record = FetchRecord {
+                    fetched_at: Local::now(),
+                    departure_count: response.as_ref().map_or(0, Vec::len),
+                    error: response.as_ref().err().map(ToString::to_string),
+                };
+                history.with_mut(|history| {
+                    history.push_front(record);
+                    history.truncate(HISTORY_LENGTH);
+                });
+                let departures = response.as_ref().ok().cloned();
+                if is_unchanged_fetch(previous_departures.as_deref(), departures.as_deref()) {
+                    fetches_saved.modify(|saved| saved + 1);
+                } else {
+                    current_response.set(Some(response));
+                }
+                previous_departures = departures;
+                is_fetching.set(false);
+                load_progress.set(100);
+                let interval_secs =
+                    settings.current().refresh_interval_secs * rate_limit().interval_factor();
+                next_refresh.set(Some(Local::now() + Duration::seconds(interval_secs as i64)));
+                tokio::select! {
+                    _ = tokio::time::sleep(std::time::Duration::from_secs(interval_secs)) => {}
+                    Some(command) = rx.next() => live_update = command.into_live_update(),
+                }
+                next_refresh.set(None);
+            }
+        }
+        .instrument(tracing::info_span!("fetcher", station = %station_config.global_id))
+    });
+    // Restarts the socket whenever the station or the fetch mode changes. Updates
+    // go through the fetcher, so they get the same checks as polled departures.
+    #[cfg(feature = "ws")]
+    use_future(
+        cx,
+        (&station_config.global_id, &settings.fetch_mode),
+        |(global_id, fetch_mode)| {
+            let fetcher = fetcher.clone();
+            let fetch_generation = fetch_generation.to_owned();
+            let live_connected = live_connected.to_owned();
+            async move {
+                *live_connected.write_silent() = false;
+                let FetchMode::WebSocket(url) = fetch_mode else {
+                    return;
+                };
+                live::run(
+                    &url.replace("{global_id}", &global_id),
+                    |connected| *live_connected.write_silent() = connected,
+                    |response| {
+                        fetcher.send(FetchCommand::Live(*fetch_generation.current(), response))
+                    },
+                )
+                .await;
+            }
+        },
+    );
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let is_fetching = is_fetching.to_owned();
+        let load_progress = load_progress.to_owned();
+        async move {
+            loop {
+                tokio::time::sleep(LOAD_PROGRESS_TICK).await;
+                if *is_fetching.current() {
+                    load_progress.modify(|progress| advance_load_progress(*progress));
+                }
+            }
+        }
+    });
+    if *latest_active.read() != *active {
+        *latest_active.write_silent() = *active;
+    }
+    if *latest_config.read() != *station_config {
+        *latest_config.write_silent() = station_config.clone();
+        fetch_generation.modify(|generation| generation + 1);
+        fetcher.send(FetchCommand::Refresh);
+    }
+    if *seen_refresh_generation.read() != *refresh_generation {
+        *seen_refresh_generation.write_silent() = *refresh_generation;
+        if *active {
+            fetcher.send(FetchCommand::Refresh);
+        }
+    }
+    let travel_times = use_state(cx, || None::<TravelTimeCache>);
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let settings = (*settings).clone();
+        let latest_config = latest_config.to_owned();
+        let travel_times = travel_times.to_owned();
+        async move {
+            let mut fetched_for = None;
+            loop {
+                let route = settings.current().home_stop.as_ref().map(|home_stop| {
+                    (
+                        latest_config.read().global_id.clone(),
+                        home_stop.global_id.clone(),
+                    )
+                });
+                let is_fresh = travel_times
+                    .current()
+                    .as_ref()
+                    .as_ref()
+                    .is_some_and(|cache| cache.is_fresh(Local::now()));
+                if route != fetched_for || !is_fresh {
+                    match &route {
+                        Some((from, to)) => match get_connection(from, to, Local::now()).await {
+                            Ok(journeys) => travel_times.set(Some(TravelTimeCache::from_journeys(
+                                &journeys,
+                                Local::now(),
+                            ))),
+                            Err(e) => tracing::warn!(error = %e, "Fetching travel times failed"),
+                        },
+                        None => travel_times.set(None),
+                    }
+                    fetched_for = route;
+                }
+                tokio::time::sleep(TRAVEL_TIME_CHECK_INTERVAL).await;
+            }
+        }
+    });
+    let show_sev = use_state(cx, || true);
+    let dragged_pin = use_ref(cx, || None::<(String, String)>);
+    let export_status = use_state(cx, || None::<String>);
+    let eval = use_eval(cx);
+    let page = use_state(cx, || 0usize);
+    let query = use_state(cx, String::new);
+    let filter = use_state(cx, || {
+        DepartureFilter::builder()
+            .transport_types(settings.transport_type_filter.iter().cloned())
+            .build()
+    });
+    if *active {
+        if let Some(deep_link_filter) = deep_link_filter.write_silent().take() {
+            filter.set(deep_link_filter);
+        }
+    }
+    let shown_departures = match current_response.get() {
+        Some(Ok(departures)) => filter.get().apply(departures),
+        _ => Vec::new(),
+    };
+    let has_imminent = shown_departures
+        .iter()
+        .any(|departure| departure.is_imminent(*now, settings.imminent_threshold_secs));
+    if has_imminent != imminent_stations.read().contains(&station_config.global_id) {
+        let mut imminent_stations = imminent_stations.write_silent();
+        if has_imminent {
+            imminent_stations.insert(station_config.global_id.clone());
+        } else {
+            imminent_stations.remove(&station_config.global_id);
+        }
+    }
+    if *active {
+        let lines = shown_departures
+            .iter()
+            .map(|departure| departure.vehicle_label.clone())
+            .collect::<HashSet<_>>();
+        if *displayed_lines.read() != lines {
+            *displayed_lines.write_silent() = lines;
+        }
+    }
+    let stop_point_id = match current_response.get() {
+        Some(Ok(departures)) => departures
+            .first()
+            .map(|departure| departure.stop_point_global_id.clone()),
+        _ => None,
+    };
+    let missed_connection_alert = match (current_response.get(), &settings.connection_alert) {
+        (Some(Ok(departures)), Some(alert)) if alert.remaining(departures, *now) == Some(0) => {
+            Some((alert, alert.deadline.format("%H:%M")))
+        }
+        _ => None,
+    };
+    let refresh_progress = next_refresh.get().map(|next_refresh| {
+        let remaining = (next_refresh - *now).num_milliseconds().max(0) as f64;
+        let interval = (settings.refresh_interval_secs * 1000) as f64;
+        (100.0 * (1.0 - remaining / interval))
+            .clamp(0.0, 100.0)
+            .round() as u8
+    });
+    let tile_actions = use_coroutine(cx, |mut rx: UnboundedReceiver<TileAction>| {
+        let watch_config = (*watch_config).clone();
+        let settings = (*settings).clone();
+        let focus = (*focus).clone();
+        let schedule = (*schedule).clone();
+        async move {
+            while let Some(action) = rx.next().await {
+                match action {
+                    TileAction::ToggleWatched(departure) => {
+                        toggle_watched(&watch_config, &departure)
+                    }
+                    TileAction::TogglePinned(departure) => toggle_pinned(&settings, &departure),
+                    TileAction::Focus(label, destination) => focus.set(Some((label, destination))),
+                    TileAction::ShowSchedule(label, destination) => {
+                        schedule.set(Some((label, destination)))
+                    }
+                }
+            }
+        }
+    });
+    let render_tile = |response: &'a Departure| {
+        let key = format!(
+            "{}{}{}",
+            response.vehicle_label, response.destination, response.planned_time
+        );
+        let countdown = format_departure_time(*time_display_mode, response, *now);
+        let urgency = if response.cancelled {
+            "normal"
+        } else {
+            urgency_class(response.minutes_until(*now))
+        };
+        let shows_relative = time_display_mode.shows_relative(response, *now);
+        let time_changed = time_diffs.iter().any(|diff| diff.is_for(response));
+        let imminent = response.is_imminent(*now, settings.imminent_threshold_secs);
+        let walk_hint = walk_hint(response, *now, station_config.offset_minutes);
+        let delay_trend = delay_samples
+            .get()
+            .get(&response.vehicle_label)
+            .map(compute_delay_trend)
+            .unwrap_or_default();
+        let arrival = settings
+            .home_stop
+            .as_ref()
+            .filter(|_| !response.cancelled)
+            .and_then(|_| {
+                estimated_arrival(
+                    response,
+                    &settings.travel_times,
+                    travel_times.get().as_ref(),
+                )
+            })
+            .map(|arrival| arrival.format("%H:%M").to_string())
+            .unwrap_or_default();
+        let Tile = match settings.display_style {
+            DisplayStyle::Compact => ResponseTile,
+            DisplayStyle::Card => DepartureCard,
+        };
+        rsx!(Tile {
+            key: "{key}",
+            departure: response.clone(),
+            countdown: countdown,
+            urgency: urgency,
+            shows_relative: shows_relative,
+            imminent: imminent,
+            walk_hint: walk_hint,
+            delay_trend: delay_trend,
+            walk_minutes: station_config.offset_minutes,
+            query: query.get().clone(),
+            arrival: arrival,
+            is_watched: watch_config.is_watched(response),
+            is_pinned: settings.pinned.contains(&response.pin_key()),
+            time_changed: time_changed,
+            actions: tile_actions.clone()
+        })
+    };
+    let tile_body = match current_response.get() {
+        Some(Ok(_)) if filter.transport_types.is_empty() && !filter.inverted => {
+            rsx! { "No transport types selected" }
+        }
+        Some(Ok(responses)) => {
+            let (sev_departures, departures): (Vec<_>, Vec<_>) = filter
+                .get()
+                .apply(responses)
+                .into_iter()
+                .filter(|response| {
+                    focus.as_ref().is_none_or(|(label, destination)| {
+                        &response.vehicle_label == label && &response.destination == destination
+                    })
+                })
+                .filter(|response| matches_query(&response.destination, query))
+                .sorted_by_key(|response| response.sort_key(&settings.sort))
+                .filter(|response| {
+                    !settings.hide_night_service_daytime
+                        || is_night_time(*now)
+                        || !is_night_service(&response.vehicle_label)
+                })
+                .partition(|response| response.sev);
+            let mut departures = departures.into_iter();
+            let focus_next = focus.is_some().then(|| departures.next()).flatten();
+            let departures = departures.collect_vec();
+            let grid_departures = departures.iter().copied().cloned().collect_vec();
+            let departures = if settings.display_mode == DisplayMode::Grid {
+                departures
+            } else {
+                apply_pinned_ordering(&settings.pinned, &departures)
+            };
+            let pinned_count = departures
+                .iter()
+                .take_while(|departure| settings.pinned.contains(&departure.pin_key()))
+                .count();
+            let (pinned_departures, departures) = departures.split_at(pinned_count);
+            let (pinned_departures, departures) = (pinned_departures.to_vec(), departures.to_vec());
+            let (range, page_count) = page_range(departures.len(), **page, PAGE_SIZE);
+            let current_page = range.start / PAGE_SIZE;
+            let departures = departures[range].to_vec();
+            rsx! {
+                if let Some(next) = focus_next {
+                    rsx!(div { class: "focus_next", render_tile(next) })
+                }
+                if !pinned_departures.is_empty() {
+                    rsx!(
+                        div {
+                            class: "pinned_section",
+                            div { class: "pinned_section_header", "Pinned" }
+                            pinned_departures.into_iter().map(|departure| {
+                                let pin_key = departure.pin_key();
+                                rsx!(
+                                    div {
+                                        key: "pinned{departure.vehicle_label}{departure.destination}{departure.planned_time}",
+                                        draggable: "true",
+                                        prevent_default: "ondragover",
+                                        ondragstart: move |_| *dragged_pin.write_silent() = Some(pin_key.clone()),
+                                        ondragover: |_| {},
+                                        ondrop: move |_| {
+                                            if let Some(dragged) = dragged_pin.write_silent().take() {
+                                                move_pinned(settings, &dragged, &departure.pin_key());
+                                            }
+                                        },
+                                        render_tile(departure)
+                                    }
+                                )
+                            })
+                        }
+                    )
+                }
+                match settings.display_mode {
+                    DisplayMode::Chronological => rsx!(departures.into_iter().map(render_tile)),
+                    DisplayMode::GroupedByLine => rsx!(
+                        group_by_line(&departures).into_iter().map(|(label, group)| rsx!(
+                            div {
+                                key: "{label}",
+                                class: "line_group",
+                                div { class: "line_group_header", "{label} ({group.len()})" }
+                                group.into_iter().map(render_tile)
+                            }
+                        ))
+                    ),
+                    DisplayMode::Grid => rsx!(MacroGrid {
+                        departures: grid_departures,
+                        now: *now,
+                        time_display_mode: *time_display_mode
+                    }),
+                }
+                if page_count > 1 && settings.display_mode != DisplayMode::Grid {
+                    rsx!(
+                        div {
+                            class: "pagination",
+                            button {
+                                disabled: current_page == 0,
+                                onclick: move |_| page.set(current_page.saturating_sub(1)),
+                                "Previous"
+                            }
+                            " Page {current_page + 1} of {page_count} "
+                            button {
+                                disabled: current_page + 1 >= page_count,
+                                onclick: move |_| page.set(current_page + 1),
+                                "Next"
+                            }
+                        }
+                    )
+                }
+                if !sev_departures.is_empty() {
+                    rsx!(
+                        div {
+                            class: "sev_section",
+                            div {
+                                class: "sev_section_header",
+                                onclick: move |_| show_sev.set(!show_sev.get()),
+                                if *show_sev.get() { "▾ " } else { "▸ " },
+                                "Schienenersatzverkehr ({sev_departures.len()})"
+                            }
+                            if *show_sev.get() {
+                                rsx!(sev_departures.into_iter().map(render_tile))
+                            }
+                        }
+                    )
+                }
+            }
+        }
+        Some(Err(e)) => rsx! { "Fetching data failed: {e}"  },
+        None => rsx! { ""  },
+    };
+    let summary = match current_response.get() {
+        Some(Ok(responses)) => next_departure_summary(responses, *now),
+        _ => None,
+    };
+    let platforms = match current_response.get() {
+        Some(Ok(responses)) => responses
+            .iter()
+            .map(|response| response.platform)
+            .chain(filter.platforms.iter().flatten().copied())
+            .sorted()
+            .dedup()
+            .collect(),
+        _ => Vec::new(),
+    };
+    let last_updated = last_successful_fetch.get().map(|last_fetch| {
+        (
+            last_fetch.format("%H:%M:%S"),
+            is_stale(last_fetch, *now, settings.refresh_interval_secs),
+        )
+    });
+    let networks = match current_response.get() {
+        Some(Ok(responses)) => responses
+            .iter()
+            .map(|response| response.network.clone())
+            .chain(filter.network.clone())
+            .sorted()
+            .dedup()
+            .collect(),
+        _ => Vec::new(),
+    };
+    let audio_departures = (*active && settings.sound_enabled && !*muted)
+        .then(|| shown_departures.iter().copied().cloned().collect_vec());
+    cx.render(rsx!(
+        div {
+            class: if *active { "station_board" } else { "station_board hidden" },
+            ProgressBar { active: *is_fetching.get(), progress: *load_progress.get() }
+            if let Some(departures) = audio_departures {
+                rsx!(DepartureAudio { departures: departures, volume: settings.sound_volume })
+            }
+            div {
+                class: "parent",
+                if *is_cached.get() {
+                    rsx!(div { class: "child", span { class: "cached_badge", "cached" } })
+                }
+                if *is_fetching.get() {
+                    rsx!(div { class: "child", div { class: "loader" } })
+                }
+                if let Some((updated_at, stale)) = last_updated {
+                    rsx!(
+                        div {
+                            class: if stale { "child last_updated stale" } else { "child last_updated" },
+                            "Updated: {updated_at}"
+                        }
+                    )
+                }
+                if cfg!(debug_assertions) {
+                    rsx!(div { class: "child fetches_saved", "Unchanged: {fetches_saved}" })
+                }
+                if **new_banner_hashes > 0 {
+                    rsx!(
+                        div {
+                            class: "child new_banner_hashes",
+                            title: "The MVG API returned banner hashes never seen before",
+                            onclick: move |_| new_banner_hashes.set(0),
+                            "New API fingerprints: {new_banner_hashes} ✕"
+                        }
+                    )
+                }
+                if *retry_count.get() > 0 {
+                    rsx!(div { class: "child", "Retrying (attempt {retry_count})…" })
+                }
+                div {
+                    class: "child",
+                    button {
+                        onclick: move |_| {
+                            if station_config.offset_minutes > 0 {
+                                on_reset_offset.call(());
+                            } else {
+                                fetcher.send(FetchCommand::Refresh);
+                            }
+                        },
+                        "Refresh now"
+                    }
+                }
+            }
+            if let Some(progress) = refresh_progress {
+                rsx!(
+                    div {
+                        class: "refresh_progress",
+                        div { class: "refresh_progress_bar", style: "width: {progress}%" }
+                    }
+                )
+            }
+            div {
+                class: if *show_filters { "filter_row" } else { "filter_row hidden" },
+                button {
+                    class: "filter_button active",
+                    onclick: move |_| {
+                        update_settings(settings, |settings| {
+                            settings.display_mode = settings.display_mode.next();
+                        });
+                    },
+                    settings.display_mode.label()
+                }
+                button {
+                    class: "filter_button",
+                    title: "Save the shown departures as an SVG image",
+                    disabled: shown_departures.is_empty(),
+                    onclick: move |_| {
+                        let departures = shown_departures.iter().copied().cloned().collect_vec();
+                        match export_departures_svg(&departures, Local::now(), &station_config.label) {
+                            Ok(path) => export_status.set(Some(format!("Saved to {}", path.display()))),
+                            Err(e) => {
+                                tracing::error!("Exporting departures failed: {e}");
+                                export_status.set(Some(format!("Export failed: {e}")));
+                            }
+                        }
+                    },
+                    "Export"
+                }
+                button {
+                    class: "filter_button",
+                    title: "Copy a link to this station with the current filter",
+                    onclick: move |_| {
+                        let link = DeepLink {
+                            global_id: station_config.global_id.clone(),
+                            limit: Some(station_config.limit),
+                            filter: filter.get().clone(),
+                        }
+                        .to_link();
+                        let link = serde_json::to_string(&link).unwrap_or_default();
+                        if let Err(e) = eval(&format!("navigator.clipboard.writeText({link})")) {
+                            tracing::error!("Copying the station link failed: {e:?}");
+                        }
+                    },
+                    "Copy link"
+                }
+                if let Some(status) = export_status.get() {
+                    rsx!(span { class: "export_status", "{status}" })
+                }
+                FilterPanel {
+                    filter: filter.get(),
+                    platforms: platforms,
+                    networks: networks,
+                    on_change: move |new_filter| filter.set(new_filter)
+                }
+            }
+            if let Some((label, destination)) = focus.get() {
+                rsx!(
+                    div {
+                        class: "focus_badge",
+                        title: "Show all lines",
+                        onclick: move |_| focus.set(None),
+                        "Watching: {label} → {destination} ✕"
+                    }
+                )
+            }
+            if let Some(summary) = summary {
+                rsx!(div { class: "next_departure_summary", "{summary}" })
+            }
+            platform_changes.iter().enumerate().filter(|(_, change)| change.is_active(*now)).map(|(index, change)| rsx!(
+                div {
+                    key: "{change.vehicle_label}{change.detected_at}",
+                    class: "platform_change_alert",
+                    "⚠ {change.vehicle_label} to {change.destination}: now platform {change.new_platform} (was {change.old_platform}) "
+                    button {
+                        title: "Dismiss",
+                        onclick: move |_| platform_changes.with_mut(|platform_changes| {
+                            platform_changes.remove(index);
+                        }),
+                        "✕"
+                    }
+                }
+            ))
+            if let Some((alert, deadline)) = missed_connection_alert {
+                rsx!(
+                    div {
+                        class: "connection_alert",
+                        "No {alert.label} to {alert.destination_prefix} leaves before {deadline}"
+                    }
+                )
+            }
+            if *fewer_results.get() {
+                rsx!(
+                    div {
+                        class: "fewer_results",
+                        "Fewer results than requested ({station_config.limit})"
+                    }
+                )
+            }
+            div {
+                class: layout.css_class(),
+                div {
+                    class: "board_column",
+                    SearchPanel { query: query.clone() }
+                    tile_body
+                }
+                if let Some(return_station) = &settings.return_station {
+                    let next = match return_response.get() {
+                        Some(Ok(departures)) => departures
+                            .iter()
+                            .find(|departure| !departure.cancelled && *departure.displayed_time() >= *now)
+                            .map_or_else(
+                                || "–".to_string(),
+                                |departure| departure.displayed_time().format("%H:%M").to_string(),
+                            ),
+                        _ => "–".to_string(),
+                    };
+                    rsx!(
+                        div {
+                            class: "board_column return_section",
+                            div {
+                                class: "return_section_header",
+                                "↩ {return_station.label} "
+                                span { class: "return_next", "{next}" }
+                            }
+                            match return_response.get() {
+                                Some(Ok(departures)) => rsx!(
+                                    departures.iter().take(RETURN_DEPARTURE_COUNT).map(render_tile)
+                                ),
+                                Some(Err(e)) => rsx!("Fetching return departures failed: {e}"),
+                                None => rsx!(""),
+                            }
+                        }
+                    )
+                }
+            }
+            if let Some(stop_point_id) = stop_point_id {
+                rsx!(StopPointPanel {
+                    station_id: station_config.global_id.clone(),
+                    stop_point_id: stop_point_id
+                })
+            }
+        }
+    ))
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Shortcut {
+    Refresh,
+    Quit,
+    ToggleFilters,
+    SwitchTab(usize),
+    ClosePanels,
+    ToggleHelp,
+    ToggleMute,
+}
+
+impl Shortcut {
+    const HELP: [(&'static str, &'static str); 9] = [
+        ("R", "Refresh now"),
+        ("Q", "Quit"),
+        ("F", "Toggle filters"),
+        ("M", "Mute chimes"),
+        ("1–6", "Switch station tab"),
+        ("Alt+1–5", "Switch to favorite"),
+        ("Esc", "Close panels"),
+        ("F11", "Kiosk mode (Esc ×3 to exit)"),
+        ("?", "Show this help"),
+    ];
+
+    fn from_key(key: &str) -> Option<Shortcut> {
+        match key {
+            "r" | "R" => Some(Shortcut::Refresh),
+            "q" | "Q" => Some(Shortcut::Quit),
+            "f" | "F" => Some(Shortcut::ToggleFilters),
+            "m" | "M" => Some(Shortcut::ToggleMute),
+            "Escape" => Some(Shortcut::ClosePanels),
+            "?" => Some(Shortcut::ToggleHelp),
+            _ => match key.parse::<usize>() {
+                Ok(number @ 1..=MAX_TABS) => Some(Shortcut::SwitchTab(number - 1)),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct UiState {
+    active_tab: usize,
+    show_filters: bool,
+    show_history: bool,
+    show_journey: bool,
+    show_qr: bool,
+    show_map: bool,
+    show_transfers: bool,
+    show_merged: bool,
+    show_stats: bool,
+    show_help: bool,
+    muted: bool,
+    refresh_generation: u64,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        UiState {
+            active_tab: 0,
+            show_filters: true,
+            show_history: false,
+            show_journey: false,
+            show_qr: false,
+            show_map: false,
+            show_transfers: false,
+            show_merged: false,
+            show_stats: false,
+            show_help: false,
+            muted: false,
+            refresh_generation: 0,
+        }
+    }
+}
+
+fn favorite_shortcut(code: &Code) -> Option<usize> {
+    let index = match code {
+        Code::Digit1 => 0,
+        Code::Digit2 => 1,
+        Code::Digit3 => 2,
+        Code::Digit4 => 3,
+        Code::Digit5 => 4,
+        _ => return None,
+    };
+    (index < MAX_VISIBLE_FAVORITES).then_some(index)
+}
+
+fn update_favorites(settings: &UseState<Config>, apply: impl FnOnce(&mut Vec<StationConfig>)) {
+    update_settings(settings, |settings| {
+        apply(&mut settings.favorites);
+    });
+}
+
+fn handle_shortcut(key: &str, ui: &mut UiState, tab_count: usize) -> Option<Shortcut> {
+    let shortcut = Shortcut::from_key(key)?;
+    match shortcut {
+        Shortcut::Refresh => ui.refresh_generation += 1,
+        Shortcut::Quit => {}
+        Shortcut::ToggleFilters => ui.show_filters = !ui.show_filters,
+        Shortcut::SwitchTab(index) if index < tab_count => ui.active_tab = index,
+        Shortcut::SwitchTab(_) => return None,
+        Shortcut::ClosePanels => {
+            ui.show_history = false;
+            ui.show_journey = false;
+            ui.show_qr = false;
+            ui.show_map = false;
+            ui.show_transfers = false;
+            ui.show_merged = false;
+            ui.show_stats = false;
+            ui.show_help = false;
+        }
+        Shortcut::ToggleHelp => ui.show_help = !ui.show_help,
+        Shortcut::ToggleMute => ui.muted = !ui.muted,
+    }
+    Some(shortcut)
+}
+
+#[inline_props]
+fn HelpOverlay<'a>(cx: Scope, on_close: EventHandler<'a, MouseEvent>) -> Element<'a> {
+    cx.render(rsx!(
+        div {
+            class: "help_overlay",
+            onclick: move |evt| on_close.call(evt),
+            table {
+                Shortcut::HELP.iter().map(|(key, description)| rsx!(
+                    tr {
+                        td { kbd { "{key}" } }
+                        td { "{description}" }
+                    }
+                ))
+            }
+        }
+    ))
+}
+
+#[derive(Parser, Clone, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// Global ID of the station to show, overriding the config file
+    #[arg(long, value_name = "GLOBAL_ID")]
+    station: Option<String>,
+    /// Number of departures to fetch
+    #[arg(long, value_name = "N")]
+    limit: Option<u8>,
+    /// Print the departures to stdout once instead of opening a window
+    #[arg(long)]
+    no_gui: bool,
+    /// Print the departures as a JSON array instead of a table, implies --no-gui
+    #[arg(long)]
+    json: bool,
+    /// Print compact instead of pretty-printed JSON
+    #[arg(long, requires = "json")]
+    compact: bool,
+    /// Write log output as JSON lines
+    #[arg(long)]
+    log_json: bool,
+    /// mvg_dioxus://station/<GLOBAL_ID>?... link to open, as passed by the OS URI handler
+    #[arg(value_name = "LINK")]
+    link: Option<String>,
+    #[cfg(feature = "statusbar")]
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[cfg(feature = "statusbar")]
+#[derive(clap::Subcommand, Clone, Debug)]
+enum Command {
+    /// Print the next departures as an i3bar protocol stream, for i3bar or swaybar
+    Statusbar,
+}
+
+impl Cli {
+    fn station_tabs(&self, config: &Config) -> Vec<StationConfig> {
+        let tabs = match &self.station {
+            Some(global_id) => vec![StationConfig {
+                global_id: global_id.clone(),
+                label: global_id.clone(),
+                ..config.station()
+            }],
+            None => load_station_tabs(config),
+        };
+        tabs.into_iter()
+            .map(|tab| StationConfig {
+                limit: self.limit.unwrap_or(tab.limit),
+                ..tab
+            })
+            .collect()
+    }
+}
+
+fn format_departure_row(departure: &Departure, now: DateTime<Local>) -> String {
+    let status = if departure.cancelled {
+        " cancelled".to_string()
+    } else {
+        match departure.delay() {
+            Some(delay) if delay > Duration::zero() => {
+                format!(" +{}", delay.num_minutes())
+            }
+            _ => String::new(),
+        }
+    };
+    format!(
+        "{} {:>3} min  {:<5} {:<30} Pl. {}{status}",
+        departure.displayed_time().format("%H:%M"),
+        departure.minutes_until(now),
+        departure.vehicle_label,
+        departure.destination,
+        platform_label(departure.platform),
+    )
+}
+
+fn format_departures_text(departures: &[Departure], now: DateTime<Local>) -> String {
+    departures
+        .iter()
+        .map(|departure| format_departure_row(departure, now))
+        .join("\n")
+}
+
+// svg writes text nodes verbatim, so station names like "A & B" would break the document
+fn svg_text(x: u32, y: u32, content: String) -> svg::node::element::Text {
+    let content = content
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    svg::node::element::Text::new()
+        .set("x", x)
+        .set("y", y)
+        .add(svg::node::Text::new(content))
+}
+
+fn render_departure_list_svg(
+    departures: &[Departure],
+    now: DateTime<Local>,
+    title: &str,
+) -> String {
+    let height = 2 * SVG_PADDING + SVG_ROW_HEIGHT * (departures.len() as u32 + 1);
+    let mut document = svg::Document::new()
+        .set("viewBox", (0, 0, SVG_WIDTH, height))
+        .set("width", SVG_WIDTH)
+        .set("height", height)
+        .set("font-family", "monospace")
+        .set("font-size", 13)
+        .add(
+            svg::node::element::Rectangle::new()
+                .set("width", "100%")
+                .set("height", "100%")
+                .set("fill", "#ffffff"),
+        )
+        .add(
+            svg_text(
+                SVG_PADDING,
+                SVG_PADDING + SVG_ROW_HEIGHT / 2,
+                format!("{title} – {}", now.format("%d.%m.%Y %H:%M")),
+            )
+            .set("font-weight", "bold"),
+        );
+    for (index, departure) in departures.iter().enumerate() {
+        let y = SVG_PADDING + SVG_ROW_HEIGHT * (index as u32 + 1) + SVG_ROW_HEIGHT / 2;
+        let fill = if departure.cancelled {
+            "#999999"
+        } else {
+            "#000000"
+        };
+        document = document
+            .add(svg_text(SVG_PADDING, y, format_departure_row(departure, now)).set("fill", fill));
+    }
+    document.to_string()
+}
+
+fn export_departures_svg(
+    departures: &[Departure],
+    now: DateTime<Local>,
+    title: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let directory = directories::UserDirs::new()
+        .and_then(|dirs| dirs.download_dir().map(PathBuf::from))
+        .or_else(|| project_dirs().map(|dirs| dirs.data_dir().to_path_buf()))
+        .ok_or("No download directory available")?;
+    std::fs::create_dir_all(&directory)?;
+    let path = directory.join(format!("departures-{}.svg", now.format("%Y%m%d-%H%M%S")));
+    std::fs::write(&path, render_departure_list_svg(departures, now, title))?;
+    Ok(path)
+}
+
+#[derive(Serialize)]
+struct DepartureJson<'a> {
+    actual_time: DateTime<Local>,
+    planned_time: DateTime<Local>,
+    delay_minutes: Option<i64>,
+    destination: &'a str,
+    vehicle_label: &'a str,
+    transport_type: &'a TransportType,
+    cancelled: bool,
+    occupancy: Occupancy,
+    platform: u16,
+    messages: &'a [String],
+}
+
+impl<'a> From<&'a Departure> for DepartureJson<'a> {
+    fn from(departure: &'a Departure) -> Self {
+        DepartureJson {
+            actual_time: departure.actual_time,
+            planned_time: departure.planned_time,
+            delay_minutes: departure.delay().map(|delay| delay.num_minutes()),
+            destination: &departure.destination,
+            vehicle_label: &departure.vehicle_label,
+            transport_type: &departure.transport_type,
+            cancelled: departure.cancelled,
+            occupancy: departure.occupancy,
+            platform: departure.platform,
+            messages: &departure.messages,
+        }
+    }
+}
+
+fn format_departures_json(departures: &[Departure], compact: bool) -> serde_json::Result<String> {
+    let departures = departures.iter().map(DepartureJson::from).collect_vec();
+    if compact {
+        serde_json::to_string(&departures)
+    } else {
+        serde_json::to_string_pretty(&departures)
+    }
+}
+
+fn print_departures(station: &StationConfig, cli: &Cli) -> Result<(), MvgError> {
+    let runtime = tokio::runtime::Runtime::new().expect("Creating the tokio runtime failed");
+    let departures = runtime.block_on(get_response(station))?;
+    if cli.json {
+        println!("{}", format_departures_json(&departures, cli.compact)?);
+    } else {
+        println!("{}", format_departures_text(&departures, Local::now()));
+    }
+    Ok(())
+}
+
+fn window_title(always_on_top: bool) -> String {
+    if always_on_top {
+        format!("📌 {WINDOW_TITLE}")
+    } else {
+        WINDOW_TITLE.to_string()
+    }
+}
+
+fn save_window_geometry(window: &dioxus_desktop::DesktopContext, settings: &UseState<Config>) {
+    let Some(geometry) = WindowGeometry::of_window(window) else {
+        return;
+    };
+    let mut config = (*settings.current()).clone();
+    config.window_geometry = Some(geometry);
+    if let Err(e) = config.save() {
+        tracing::error!("Saving window geometry failed: {e}");
+    }
+    settings.set(config);
+}
+
+// Logical window size, kept up to date from resize events.
+fn use_window_size(cx: &ScopeState) -> (u32, u32) {
+    let window = dioxus_desktop::use_window(cx);
+    let logical_size = |window: &dioxus_desktop::DesktopContext,
+                        size: dioxus_desktop::PhysicalSize<u32>| {
+        let size = size.to_logical::<f64>(window.scale_factor());
+        (size.width.round() as u32, size.height.round() as u32)
+    };
+    let size = use_state(cx, || logical_size(window, window.inner_size()));
+    dioxus_desktop::use_wry_event_handler(cx, {
+        let window = window.clone();
+        let size = size.to_owned();
+        move |event, _| {
+            if let dioxus_desktop::tao::event::Event::WindowEvent {
+                event: dioxus_desktop::tao::event::WindowEvent::Resized(new_size),
+                ..
+            } = event
+            {
+                size.set(logical_size(&window, *new_size));
+            }
+        }
+    });
+    **size
+}
+
+fn toggle_watch_mode(settings: &UseState<Config>, last_activity: &UseRef<std::time::Instant>) {
+    *last_activity.write_silent() = std::time::Instant::now();
+    update_settings(settings, |settings| {
+        settings.watch_mode = !settings.watch_mode;
+    });
+}
+
+// Presses of the exit key further apart than this start counting again
+const KIOSK_EXIT_WINDOW: std::time::Duration = std::time::Duration::from_secs(2);
+
+fn set_kiosk_mode(
+    window: &dioxus_desktop::DesktopContext,
+    settings: &UseState<Config>,
+    kiosk_mode: bool,
+) {
+    window.set_fullscreen(kiosk_mode);
+    update_settings(settings, |settings| {
+        settings.kiosk_mode = kiosk_mode;
+    });
+}
+
+// Records a press of the exit key and returns whether kiosk mode should end.
+fn register_kiosk_exit_press(presses: &mut Vec<std::time::Instant>, required: u8) -> bool {
+    presses.retain(|pressed_at| pressed_at.elapsed() < KIOSK_EXIT_WINDOW);
+    presses.push(std::time::Instant::now());
+    if presses.len() >= required.max(1) as usize {
+        presses.clear();
+        true
+    } else {
+        false
+    }
+}
+
+struct AppProps {
+    cli: Cli,
+    config: Config,
+    deep_links: std::cell::RefCell<Option<tokio::sync::mpsc::UnboundedReceiver<String>>>,
+}
+
+// Switches the active tab to the linked station and applies the linked filter
+fn open_deep_link(
+    link: &str,
+    tabs: &UseState<Vec<StationConfig>>,
+    ui: &UseState<UiState>,
+    deep_link_filter: &UseRef<Option<DepartureFilter>>,
+) -> Result<(), FilterParseError> {
+    let deep_link = DeepLink::parse(link)?;
+    let active = ui.current().active_tab.min(tabs.current().len() - 1);
+    *deep_link_filter.write_silent() = Some(deep_link.filter);
+    update_station_tabs(tabs, |tabs| {
+        let tab = &mut tabs[active];
+        if tab.global_id != deep_link.global_id {
+            tab.label = deep_link.global_id.clone();
+            tab.global_id = deep_link.global_id;
+            tab.coordinates = None;
+        }
+        tab.limit = deep_link.limit.unwrap_or(tab.limit);
+    });
+    ui.with_mut(|ui| ui.refresh_generation += 1);
+    Ok(())
+}
+
+fn app(cx: Scope<AppProps>) -> Element {
+    let window = dioxus_desktop::use_window(cx);
+    let settings = use_state(cx, || cx.props.config.clone());
+    dioxus_desktop::use_wry_event_handler(cx, {
+        let window = window.clone();
+        let settings = settings.clone();
+        move |event, _| {
+            if let dioxus_desktop::tao::event::Event::WindowEvent {
+                event: dioxus_desktop::tao::event::WindowEvent::CloseRequested,
+                ..
+            } = event
+            {
+                save_window_geometry(&window, &settings);
+            }
+        }
+    });
+    let tabs = use_state(cx, || cx.props.cli.station_tabs(settings));
+    let ui = use_state(cx, UiState::default);
+    let watch_config = use_state(cx, WatchConfig::load);
+    let notified = use_ref(cx, HashSet::new);
+    let banner_hashes = use_ref(cx, BannerHashTracker::load);
+    let delay_stats = use_ref(cx, DelayStatistics::load);
+    let fetch_metrics = use_ref(cx, FetchMetrics::load);
+    let history = use_state(cx, VecDeque::new);
+    let time = use_state(cx, Local::now);
+    let time_display_mode = use_state(cx, TimeDisplayMode::default);
+    let focus = use_state(cx, || None::<(String, String)>);
+    let schedule = use_state(cx, || None::<(String, String)>);
+    let imminent_stations = use_ref(cx, HashSet::new);
+    let last_activity = use_ref(cx, std::time::Instant::now);
+    let displayed_lines = use_ref(cx, HashSet::new);
+    let kiosk_exit_presses = use_ref(cx, Vec::new);
+    let deep_link_filter = use_ref(cx, || None::<DepartureFilter>);
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let deep_links = cx.props.deep_links.borrow_mut().take();
+        let tabs = tabs.to_owned();
+        let ui = ui.to_owned();
+        let deep_link_filter = deep_link_filter.clone();
+        async move {
+            let Some(mut deep_links) = deep_links else {
+                return;
+            };
+            while let Some(link) = deep_links.recv().await {
+                if let Err(e) = open_deep_link(&link, &tabs, &ui, &deep_link_filter) {
+                    tracing::warn!("Opening {link} failed: {e}");
+                }
+            }
+        }
+    });
+    let board_departures = use_ref(cx, HashMap::<String, Vec<Departure>>::new);
+    let disruptions = use_state(cx, Vec::<Disruption>::new);
+    let disruptions_dismissed = use_state(cx, || false);
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let disruptions = disruptions.to_owned();
+        let disruptions_dismissed = disruptions_dismissed.to_owned();
+        async move {
+            let mut seen = HashSet::new();
+            loop {
+                match get_disruptions().await {
+                    Ok(fetched) => {
+                        let keys = fetched
+                            .iter()
+                            .map(|disruption| (disruption.title.clone(), disruption.valid_from))
+                            .collect::<HashSet<_>>();
+                        if !keys.is_subset(&seen) {
+                            disruptions_dismissed.set(false);
+                        }
+                        seen = keys;
+                        disruptions.set(fetched);
+                    }
+                    Err(e) => tracing::warn!(error = %e, "Fetching disruptions failed"),
+                }
+                tokio::time::sleep(DISRUPTION_POLL_INTERVAL).await;
+            }
+        }
+    });
+    let tickers = use_state(cx, Vec::<Ticker>::new);
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let tickers = tickers.to_owned();
+        async move {
+            loop {
+                match get_tickers().await {
+                    Ok(fetched) => tickers.set(fetched),
+                    Err(e) => tracing::warn!(error = %e, "Fetching the service ticker failed"),
+                }
+                tokio::time::sleep(TICKER_POLL_INTERVAL).await;
+            }
+        }
+    });
+    #[cfg(feature = "tray")]
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let window = window.clone();
+        let settings = settings.clone();
+        let ui = ui.clone();
+        let last_activity = last_activity.clone();
+        async move {
+            if let Err(e) = tray::init() {
+                tracing::error!("Creating the tray icon failed: {e}");
+                return;
+            }
+            loop {
+                while let Some(command) = tray::poll_command() {
+                    match command {
+                        tray::TrayCommand::ShowWindow => {
+                            *last_activity.write_silent() = std::time::Instant::now();
+                            window.set_visible(true);
+                            window.set_minimized(false);
+                            window.set_focus();
+                        }
+                        tray::TrayCommand::Refresh => {
+                            ui.with_mut(|ui| ui.refresh_generation += 1);
+                        }
+                        tray::TrayCommand::ToggleWatchMode => {
+                            toggle_watch_mode(&settings, &last_activity);
+                        }
+                        tray::TrayCommand::Quit => {
+                            save_window_geometry(&window, &settings);
+                            window.close();
+                        }
+                    }
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            }
+        }
+    });
+    let eval = use_eval(cx);
+    let theme = settings.theme.resolve(window.theme());
+    let font_size = if settings.kiosk_mode {
+        FontSize::ExtraLarge
+    } else {
+        settings.font_size
+    };
+    use_effect(cx, (&font_size,), |(font_size,)| {
+        let eval = eval.clone();
+        async move {
+            let script = format!(
+                "document.documentElement.style.setProperty('--base-font-size', '{}px');",
+                font_size.pixels()
+            );
+            if let Err(e) = eval(&script) {
+                tracing::error!("Applying the font size failed: {e:?}");
+            }
+        }
+    });
+    use_effect(cx, (&theme,), |(theme,)| {
+        let eval = eval.clone();
+        async move {
+            if let Err(e) = eval(&format!(
+                "document.documentElement.dataset.theme = {theme:?};"
+            )) {
+                tracing::error!("Applying the {theme} theme failed: {e:?}");
+            }
+        }
+    });
+    let _: &Coroutine<()> = use_coroutine(cx, |_rx| {
+        let time = time.to_owned();
+        let window = window.clone();
+        let settings = settings.clone();
+        let imminent_stations = imminent_stations.clone();
+        let last_activity = last_activity.clone();
+        async move {
+            let mut is_hidden = false;
+            loop {
+                tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                time.set(Local::now());
+                if !settings.current().watch_mode {
+                    continue;
+                }
+                if !imminent_stations.read().is_empty() {
+                    *last_activity.write_silent() = std::time::Instant::now();
+                    if is_hidden {
+                        window.set_visible(true);
+                        is_hidden = false;
+                    }
+                } else if !is_hidden && last_activity.read().elapsed() >= WATCH_MODE_IDLE_TIMEOUT {
+                    window.set_visible(false);
+                    is_hidden = true;
+                }
+            }
+        }
+    });
+
+    let now = *time.get();
+    let time = now.format("%H:%M:%S");
+    let (window_width, window_height) = use_window_size(cx);
+    let layout = select_layout(window_width, window_height, settings.layout_mode);
+    let active = ui.active_tab.min(tabs.len() - 1);
+    let set_active_tab = move |index: usize| ui.with_mut(|ui| ui.active_tab = index);
+    let switch_to_favorite = move |favorite: StationConfig| {
+        if favorite == tabs[active] {
+            ui.with_mut(|ui| ui.refresh_generation += 1);
+        } else {
+            // Changing the station config makes the board fetch right away
+            update_station_tabs(tabs, |tabs| tabs[active] = favorite);
+        }
+    };
+    let is_favorite = settings
+        .favorites
+        .iter()
+        .any(|favorite| favorite.global_id == tabs[active].global_id);
+    let relevant_disruptions = disruptions
+        .iter()
+        .filter(|disruption| disruption.valid_to.is_none_or(|valid_to| valid_to >= now))
+        .filter(|disruption| {
+            let displayed_lines = displayed_lines.read();
+            disruption
+                .lines
+                .iter()
+                .any(|line| displayed_lines.contains(line))
+        })
+        .sorted_by_key(|disruption| std::cmp::Reverse(disruption.severity))
+        .cloned()
+        .collect_vec();
+    let ticker_text = relevant_tickers(tickers, &displayed_lines.read())
+        .into_iter()
+        .map(|ticker| ticker.text.as_str())
+        .join("  +++  ");
+    cx.render(rsx!(
+        div {
+        class: if settings.kiosk_mode { "app kiosk" } else { "app" },
+        tabindex: "0",
+        autofocus: true,
+        onmousemove: move |_| *last_activity.write_silent() = std::time::Instant::now(),
+        onkeydown: move |evt| {
+            let key = evt.key().to_string();
+            if settings.kiosk_mode {
+                // Other shortcuts stay disabled so the display can't be changed by accident
+                if key == settings.kiosk_exit_key
+                    && register_kiosk_exit_press(&mut kiosk_exit_presses.write_silent(), settings.kiosk_exit_presses)
+                {
+                    set_kiosk_mode(window, settings, false);
+                }
+                return;
+            }
+            if key == "F11" {
+                set_kiosk_mode(window, settings, true);
+                return;
+            }
+            if evt.modifiers().contains(Modifiers::ALT) {
+                if let Some(favorite) = favorite_shortcut(&evt.code()).and_then(|index| settings.favorites.get(index)) {
+                    switch_to_favorite(favorite.clone());
+                }
+                return;
+            }
+            let mut next_ui = ui.get().clone();
+            if let Some(shortcut) = handle_shortcut(&key, &mut next_ui, tabs.len()) {
+                ui.set(next_ui);
+                if shortcut == Shortcut::ClosePanels {
+                    focus.set(None);
+                    schedule.set(None);
+                }
+                if shortcut == Shortcut::Quit {
+                    save_window_geometry(window, settings);
+                    window.close();
+                }
+            }
+        },
+        if !**disruptions_dismissed {
+            rsx!(DisruptionBanner {
+                disruptions: relevant_disruptions,
+                on_dismiss: move |_| disruptions_dismissed.set(true)
+            })
+        }
+        settings.connection_rules.iter().enumerate().map(|(index, rule)| rsx!(
+            ConnectionSummary { key: "{index}", rule: rule.clone() }
+        ))
+        div {
+            class: "tab_bar",
+            tabs.iter().enumerate().map(|(index, tab)| rsx!(
+                button {
+                    key: "{index}",
+                    class: if index == active { "tab active" } else { "tab" },
+                    onclick: move |_| set_active_tab(index),
+                    "{tab.label}"
+                }
+            ))
+            button {
+                class: if is_favorite { "favorite_star active" } else { "favorite_star" },
+                title: if is_favorite { "Remove from favorites" } else { "Add current station to favorites" },
+                onclick: move |_| {
+                    let station = tabs[active].clone();
+                    update_favorites(settings, |favorites| {
+                        if is_favorite {
+                            favorites.retain(|favorite| favorite.global_id != station.global_id);
+                        } else {
+                            favorites.push(station);
+                        }
+                    });
+                },
+                if is_favorite { "★" } else { "☆" }
+            }
+            button {
+                disabled: tabs.len() >= MAX_TABS,
+                onclick: move |_| {
+                    update_station_tabs(tabs, |tabs| tabs.push(settings.station()));
+                    set_active_tab(tabs.len());
+                },
+                "+"
+            }
+            button {
+                disabled: active == 0,
+                onclick: move |_| {
+                    update_station_tabs(tabs, |tabs| tabs.swap(active, active - 1));
+                    set_active_tab(active - 1);
+                },
+                "◀"
+            }
+            button {
+                disabled: active + 1 >= tabs.len(),
+                onclick: move |_| {
+                    update_station_tabs(tabs, |tabs| tabs.swap(active, active + 1));
+                    set_active_tab(active + 1);
+                },
+                "▶"
+            }
+            button {
+                disabled: tabs.len() <= 1,
+                onclick: move |_| {
+                    update_station_tabs(tabs, |tabs| {
+                        tabs.remove(active);
+                    });
+                    set_active_tab(active.saturating_sub(1));
+                },
+                "✕"
+            }
+        }
+        FavoritesBar {
+            favorites: &settings.favorites,
+            active_global_id: &tabs[active].global_id,
+            on_select: switch_to_favorite,
+            on_remove: move |index| update_favorites(settings, |favorites| {
+                favorites.remove(index);
+            }),
+            on_move: move |(from, to): (usize, usize)| update_favorites(settings, |favorites| {
+                let favorite = favorites.remove(from);
+                favorites.insert(to, favorite);
+            })
+        }
+        SettingsPanel {
+            key: "{active}",
+            station_config: &tabs[active],
+            on_station_change: move |config| {
+                update_station_tabs(tabs, |tabs| tabs[active] = config);
+            },
+            watch_config: watch_config,
+            settings: settings
+        }
+        div {
+            class: "parent",
+            div { class: "child", "{time}" }
+            if cfg!(feature = "weather") {
+                rsx!(div { class: "child", weather_widget() })
+            }
+            if is_night_time(now) {
+                rsx!(div { class: "child", span { class: "night_service_badge", "🌙 Night service active" } })
+            }
+            if ui.muted && settings.sound_enabled {
+                rsx!(div { class: "child", span { title: "Chimes muted (M)", "🔇" } })
+            }
+            if tabs[active].offset_minutes > 0 {
+                rsx!(div { class: "child walk_offset", "(+{tabs[active].offset_minutes}m walk)" })
+            }
+            div {
+                class: "child",
+                button {
+                    onclick: move |_| ui.with_mut(|ui| ui.show_history = !ui.show_history),
+                    "History"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    onclick: move |_| ui.with_mut(|ui| ui.show_journey = !ui.show_journey),
+                    "Plan journey"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    onclick: move |_| ui.with_mut(|ui| ui.show_qr = !ui.show_qr),
+                    "Show QR"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    onclick: move |_| ui.with_mut(|ui| ui.show_map = !ui.show_map),
+                    "Map"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    onclick: move |_| ui.with_mut(|ui| ui.show_transfers = !ui.show_transfers),
+                    "Transfers"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    disabled: tabs.len() <= 1,
+                    title: "All station tabs in one list",
+                    onclick: move |_| ui.with_mut(|ui| ui.show_merged = !ui.show_merged),
+                    "Merge view"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    onclick: move |_| ui.with_mut(|ui| ui.show_stats = !ui.show_stats),
+                    "Stats"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    onclick: move |_| {
+                        update_settings(settings, |settings| {
+                            settings.theme = settings.theme.next();
+                        });
+                    },
+                    "{settings.theme.label()}"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    class: if settings.always_on_top { "pin_button active" } else { "pin_button" },
+                    title: "Keep the window above other windows",
+                    onclick: move |_| {
+                        let always_on_top = !settings.always_on_top;
+                        window.set_always_on_top(always_on_top);
+                        window.set_title(&window_title(always_on_top));
+                        update_settings(settings, |settings| {
+                            settings.always_on_top = always_on_top;
+                        });
+                    },
+                    "📌"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    class: if settings.watch_mode { "pin_button active" } else { "pin_button" },
+                    title: "Hide the window when idle and show it when a departure is imminent",
+                    onclick: move |_| toggle_watch_mode(settings, last_activity),
+                    "👁"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    title: "Switch between compact lines and cards",
+                    onclick: move |_| {
+                        update_settings(settings, |settings| {
+                            settings.display_style = settings.display_style.toggled();
+                        });
+                    },
+                    "Style: {settings.display_style.label()}"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    title: "Fullscreen departure board for a TV (F11)",
+                    onclick: move |_| set_kiosk_mode(window, settings, true),
+                    "Kiosk"
+                }
+            }
+            div {
+                class: "child",
+                button {
+                    onclick: move |_| time_display_mode.set(time_display_mode.next()),
+                    "Times: {time_display_mode.label()}"
+                }
+            }
+            div {
+                class: "child",
+                button { onclick: move |_| ui.with_mut(|ui| ui.show_help = !ui.show_help), "?" }
+            }
+        }
+        if ui.show_history {
+            rsx!(HistoryPanel { history: history, fetch_metrics: fetch_metrics })
+        }
+        if ui.show_journey {
+            rsx!(JourneyPanel {})
+        }
+        if let Some((label, destination)) = schedule.get() {
+            rsx!(DaySchedule {
+                station_id: tabs[active].global_id.clone(),
+                label: label.clone(),
+                destination: destination.clone(),
+                on_close: move |_| schedule.set(None)
+            })
+        }
+        if ui.show_stats {
+            rsx!(StatsPanel {
+                stats: delay_stats.read().per_line(),
+                window_days: settings.delay_stats_days
+            })
+        }
+        if ui.show_qr {
+            rsx!(QrCodePanel { station_id: &tabs[active].global_id })
+        }
+        if ui.show_transfers {
+            rsx!(ConnectionGraph { tabs: tabs, active: active, board_departures: board_departures })
+        }
+        if ui.show_merged {
+            rsx!(MergedView { tabs: tabs, refresh_interval_secs: settings.refresh_interval_secs })
+        }
+        if ui.show_map {
+            match tabs[active].coordinates {
+                Some((latitude, longitude)) => rsx!(MapView { latitude: latitude, longitude: longitude }),
+                None => rsx!(div { class: "map_view", "Select the station through the search to show its location" }),
+            }
+        }
+        if ui.show_help {
+            rsx!(HelpOverlay { on_close: move |_| ui.with_mut(|ui| ui.show_help = false) })
+        }
+        tabs.iter().enumerate().map(|(index, tab)| rsx!(
+            StationBoard {
+                key: "{index}",
+                station_config: tab.clone(),
+                active: index == active,
+                now: now,
+                watch_config: watch_config,
+                settings: settings,
+                notified: notified,
+                banner_hashes: banner_hashes,
+                delay_stats: delay_stats,
+                fetch_metrics: fetch_metrics,
+                history: history,
+                show_filters: ui.show_filters,
+                refresh_generation: ui.refresh_generation,
+                on_reset_offset: move |_| {
+                    update_station_tabs(tabs, |tabs| tabs[index].offset_minutes = 0);
+                },
+                time_display_mode: **time_display_mode,
+                focus: focus,
+                schedule: schedule,
+                imminent_stations: imminent_stations,
+                layout: layout,
+                displayed_lines: displayed_lines,
+                board_departures: board_departures,
+                deep_link_filter: deep_link_filter,
+                muted: ui.muted
+            }
+        ))
+        if settings.kiosk_mode {
+            rsx!(KioskLayout {
+                departures: board_departures.read().get(&tabs[active].global_id).cloned().unwrap_or_default(),
+                now: now
+            })
+        }
+        if !ticker_text.is_empty() {
+            rsx!(
+                div {
+                    class: "ticker_marquee",
+                    span { "{ticker_text}" }
+                }
+            )
+        }
+        }
+    ))
+}
+
+fn init_tracing(json: bool) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE);
+    if json {
+        builder.json().init();
+    } else {
+        builder.init();
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    init_tracing(cli.log_json);
+    let config = Config::load();
+    #[cfg(not(feature = "ws"))]
+    if matches!(config.fetch_mode, FetchMode::WebSocket(_)) {
+        tracing::warn!("Built without the ws feature, polling for departures instead");
+    }
+    #[cfg(feature = "statusbar")]
+    if let Some(Command::Statusbar) = cli.command {
+        let station = cli.station_tabs(&config).remove(0);
+        statusbar::print_departures(&station, config.refresh_interval_secs);
+        return;
+    }
+    if cli.no_gui || cli.json {
+        let station = cli.station_tabs(&config).remove(0);
+        if let Err(e) = print_departures(&station, &cli) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+    hot_reload_init!();
+    if config.csv_logging {
+        prune_csv_logs(Local::now().date_naive());
+    }
+    let mut window = dioxus_desktop::WindowBuilder::new()
+        .with_title(window_title(config.always_on_top))
+        .with_always_on_top(config.always_on_top)
+        .with_fullscreen(
+            config
+                .kiosk_mode
+                .then_some(dioxus_desktop::tao::window::Fullscreen::Borderless(None)),
+        );
+    match config.window_geometry {
+        Some(geometry) if geometry.is_valid() => {
+            window = window
+                .with_inner_size(dioxus_desktop::PhysicalSize::new(
+                    geometry.width,
+                    geometry.height,
+                ))
+                .with_position(dioxus_desktop::tao::dpi::PhysicalPosition::new(
+                    geometry.x, geometry.y,
+                ));
+        }
+        Some(geometry) => tracing::warn!("Ignoring invalid window geometry {geometry:?}"),
+        None => {}
+    }
+    let (deep_link_sender, deep_links) = tokio::sync::mpsc::unbounded_channel();
+    if let Some(link) = &cli.link {
+        let _ = deep_link_sender.send(link.clone());
+    }
+    dioxus_desktop::launch_with_props(
+        app,
+        AppProps {
+            cli,
+            config,
+            deep_links: std::cell::RefCell::new(Some(deep_links)),
+        },
+        dioxus_desktop::Config::new()
+            .with_window(window)
+            // Handles links clicked inside the window; links from other apps arrive as the
+            // LINK argument once the scheme is registered with the OS (see packaging/)
+            .with_custom_protocol(DEEP_LINK_SCHEME.to_string(), move |request| {
+                let _ = deep_link_sender.send(request.uri().to_string());
+                dioxus_desktop::wry::http::Response::builder()
+                    .status(204)
+                    .body(std::borrow::Cow::Borrowed(&[][..]))
+                    .map_err(Into::into)
+            })
+            .with_custom_head(r#"<link rel="stylesheet" href="public/tailwind.css">"#.to_string()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::mvg::DivaId;
+    use crate::test_helpers::{at, departure, temp_path};
+
+    fn sorted_destinations(departures: &[Departure], config: &SortConfig) -> Vec<String> {
+        departures
+            .iter()
+            .sorted_by_key(|departure| departure.sort_key(config))
+            .map(|departure| departure.destination.clone())
+            .collect()
+    }
+
+    fn cancelled_first() -> Vec<Departure> {
+        let mut cancelled = departure("S1", "Freising", at(8, 0));
+        cancelled.cancelled = true;
+        vec![cancelled, departure("S8", "Flughafen", at(8, 5))]
+    }
+
+    #[test]
+    fn sort_key_keeps_cancelled_in_time_order_by_default() {
+        let config = SortConfig::default();
+        assert_eq!(
+            sorted_destinations(&cancelled_first(), &config),
+            ["Freising", "Flughafen"]
+        );
+    }
+
+    #[test]
+    fn sort_key_moves_cancelled_to_the_end_when_configured() {
+        let config = SortConfig {
+            cancelled_to_end: true,
+            ..SortConfig::default()
+        };
+        assert_eq!(
+            sorted_destinations(&cancelled_first(), &config),
+            ["Flughafen", "Freising"]
+        );
+    }
+
+    #[test]
+    fn sort_key_breaks_ties_with_the_secondary_field() {
+        let departures = [
+            departure("S8", "Flughafen", at(8, 0)),
+            departure("S1", "Freising", at(8, 0)),
+        ];
+        let config = SortConfig {
+            primary: SortField::DisplayedTime,
+            secondary: SortField::Line,
+            cancelled_to_end: false,
+        };
+        assert_eq!(
+            sorted_destinations(&departures, &config),
+            ["Freising", "Flughafen"]
+        );
+    }
+
+    fn delayed(label: &str, time: DateTime<Local>, delay_minutes: i64) -> Departure {
+        let mut departure = departure(label, "Freising", time);
+        departure.actual_time = time + Duration::minutes(delay_minutes);
+        departure.delay_seconds = Some(delay_minutes * 60);
+        departure
+    }
+
+    #[test]
+    fn mean_minutes_of_no_samples_is_zero() {
+        assert_eq!(DelayStats::default().mean_minutes(), 0.0);
+    }
+
+    #[test]
+    fn mean_minutes_averages_the_samples() {
+        let mut stats = DelayStats::default();
+        for minutes in [1, 2, 6] {
+            stats.record(minutes);
+        }
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.max_minutes, 6);
+        assert_eq!(stats.mean_minutes(), 3.0);
+    }
+
+    #[test]
+    fn accumulate_records_departed_departures_per_line() {
+        let mut statistics = DelayStatistics::default();
+        let departed = [delayed("S1", at(8, 0), 2), delayed("S1", at(8, 20), 4)];
+        assert!(statistics.accumulate(&departed, at(8, 30), 7));
+        let per_line = statistics.per_line();
+        assert_eq!(per_line.len(), 1);
+        assert_eq!(per_line[0].0, "S1");
+        assert_eq!(per_line[0].1.count, 2);
+        assert_eq!(per_line[0].1.mean_minutes(), 3.0);
+    }
+
+    #[test]
+    fn accumulate_counts_each_departure_once() {
+        let mut statistics = DelayStatistics::default();
+        let departed = [delayed("S1", at(8, 0), 2)];
+        assert!(statistics.accumulate(&departed, at(8, 5), 7));
+        assert!(!statistics.accumulate(&departed, at(8, 6), 7));
+        assert_eq!(statistics.per_line()[0].1.count, 1);
+    }
+
+    #[test]
+    fn accumulate_skips_cancelled_and_scheduled_departures() {
+        let mut statistics = DelayStatistics::default();
+        let mut cancelled = delayed("S1", at(8, 0), 5);
+        cancelled.cancelled = true;
+        let mut scheduled = departure("S8", "Flughafen", at(8, 0));
+        scheduled.delay_seconds = None;
+        scheduled.is_real_time = false;
+        assert!(!statistics.accumulate(&[cancelled, scheduled], at(8, 5), 7));
+        assert!(statistics.per_line().is_empty());
+    }
+
+    #[test]
+    fn accumulate_drops_days_outside_the_window() {
+        let mut statistics = DelayStatistics::default();
+        statistics.accumulate(&[delayed("S1", at(8, 0), 2)], at(8, 5), 7);
+        let a_week_later = at(8, 5) + Duration::days(7);
+        assert!(statistics.accumulate(&[], a_week_later, 7));
+        assert!(statistics.per_line().is_empty());
+    }
+
+    #[test]
+    fn departed_departures_are_the_ones_gone_from_the_front() {
+        let previous = [
+            delayed("S1", at(8, 0), 3),
+            delayed("S8", at(8, 10), 0),
+            delayed("S1", at(8, 20), 1),
+        ];
+        // The S8 is still listed, with a new delay
+        let current = [delayed("S8", at(8, 10), 2), delayed("S1", at(8, 20), 1)];
+        let departed = departed_departures(&previous, &current);
+        assert_eq!(departed, [previous[0].clone()]);
+    }
+
+    #[test]
+    fn departed_departures_ignore_departures_pushed_off_the_end() {
+        let previous = [delayed("S1", at(8, 0), 0), delayed("S8", at(8, 30), 0)];
+        let current = [delayed("S1", at(8, 0), 0), delayed("S2", at(8, 10), 0)];
+        assert!(departed_departures(&previous, &current).is_empty());
+    }
+
+    #[test]
+    fn departed_departures_do_not_depend_on_the_clock() {
+        // With a walk offset the API drops departures minutes before they leave
+        let previous = [delayed("S1", at(23, 50), 4)];
+        let departed = departed_departures(&previous, &[]);
+        assert_eq!(departed.len(), 1);
+        let mut statistics = DelayStatistics::default();
+        assert!(statistics.accumulate(&departed, at(23, 40), 7));
+        assert_eq!(statistics.per_line()[0].1.max_minutes, 4);
+    }
+
+    #[test]
+    fn stale_fetch_check_keeps_current_fetches_and_their_refreshes() {
+        let (tx, mut rx) = futures_channel::mpsc::unbounded();
+        tx.unbounded_send(FetchCommand::Refresh).unwrap();
+        assert!(!is_stale_fetch(3, 3, &mut rx));
+        assert!(matches!(rx.try_next(), Ok(Some(FetchCommand::Refresh))));
+    }
+
+    #[test]
+    fn only_the_fetch_for_the_latest_settings_is_applied() {
+        let (tx, mut rx) = futures_channel::mpsc::unbounded();
+        let mut generation = 0;
+        let mut applied = Vec::new();
+        // The first fetch starts, then the settings change twice while it runs
+        let first = generation;
+        for _ in 0..2 {
+            generation += 1;
+            tx.unbounded_send(FetchCommand::Refresh).unwrap();
+        }
+        if !is_stale_fetch(first, generation, &mut rx) {
+            applied.push("first");
+        }
+        // Every queued refresh was dropped, so only one more fetch runs
+        assert!(rx.try_next().is_err());
+        let second = generation;
+        if !is_stale_fetch(second, generation, &mut rx) {
+            applied.push("second");
+        }
+        assert_eq!(applied, ["second"]);
+    }
+
+    #[test]
+    fn refresh_commands_fetch_again() {
+        assert!(FetchCommand::Refresh.into_live_update().is_none());
+    }
+
+    #[cfg(feature = "ws")]
+    #[test]
+    fn live_commands_carry_their_generation() {
+        let update = FetchCommand::Live(4, Ok(Vec::new())).into_live_update();
+        assert!(matches!(update, Some((4, Ok(departures))) if departures.is_empty()));
+    }
+
+    #[test]
+    fn departure_cache_only_compares_the_same_station() {
+        let mut cache = DepartureCache::default();
+        cache.update("de:09162:6", &[delayed("S1", at(8, 0), 0)]);
+        assert!(cache.previous("de:09162:6").is_some());
+        assert!(cache.previous("de:09162:2").is_none());
+    }
+
+    #[test]
+    fn deep_link_selects_station_limit_and_filter() {
+        let deep_link = DeepLink::parse(
+            "mvg_dioxus://station/de:09184:2000?transport=SBAHN&destination=Hbf&limit=5",
+        )
+        .unwrap();
+        assert_eq!(deep_link.global_id, "de:09184:2000");
+        assert_eq!(deep_link.limit, Some(5));
+        assert_eq!(
+            deep_link.filter,
+            DepartureFilter::builder()
+                .transport_types([TransportType::Sbahn])
+                .destination(Some("Hbf".to_string()))
+                .build()
+        );
+    }
+
+    #[test]
+    fn deep_link_rejects_other_schemes_and_missing_stations() {
+        for link in [
+            "https://station/de:09184:2000",
+            "mvg_dioxus://filter/de:09184:2000",
+            "mvg_dioxus://station/",
+            "not a link",
+        ] {
+            assert!(
+                matches!(DeepLink::parse(link), Err(FilterParseError::InvalidLink(_))),
+                "{link}"
+            );
+        }
+    }
+
+    #[test]
+    fn deep_link_rejects_invalid_values() {
+        let parse =
+            |query: &str| DeepLink::parse(&format!("mvg_dioxus://station/de:09184:2000?{query}"));
+        assert!(matches!(
+            parse("limit=0"),
+            Err(FilterParseError::InvalidLimit(_))
+        ));
+        assert!(matches!(
+            parse("limit=many"),
+            Err(FilterParseError::InvalidLimit(_))
+        ));
+        assert!(matches!(
+            parse("transport=ZEPPELIN"),
+            Err(FilterParseError::UnknownTransportType(_))
+        ));
+        assert!(matches!(
+            parse("platform=one"),
+            Err(FilterParseError::InvalidPlatform(_))
+        ));
+        assert!(matches!(
+            parse("hide_cancelled=yes"),
+            Err(FilterParseError::InvalidFlag(..))
+        ));
+    }
+
+    #[test]
+    fn link_from_the_os_handler_is_taken_from_argv() {
+        let link = "mvg_dioxus://station/de:09184:2000?line=S1";
+        let cli = Cli::try_parse_from(["mvg_dioxus", link]).unwrap();
+        assert_eq!(cli.link.as_deref(), Some(link));
+        assert!(DeepLink::parse(cli.link.as_deref().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn default_filter_has_an_empty_query() {
+        assert_eq!(DepartureFilter::default().to_url_query_string(), "");
+        assert_eq!(
+            DepartureFilter::from_url_query_string("").unwrap(),
+            DepartureFilter::default()
+        );
+    }
+
+    fn arb_filter() -> impl Strategy<Value = DepartureFilter> {
+        (
+            prop::sample::subsequence(TransportType::ALL.to_vec(), 0..=TransportType::ALL.len()),
+            prop::option::of(prop::collection::vec(any::<u16>(), 0..3)),
+            prop::option::of("[^\\s]( ?[^\\s])*"),
+            prop::option::of(prop::collection::vec("[A-Z0-9]{1,4}", 0..3)),
+            prop::option::of("[a-z&=%+]{0,6}"),
+            any::<(bool, bool, bool)>(),
+        )
+            .prop_map(
+                |(
+                    transport_types,
+                    platforms,
+                    destination,
+                    vehicle_labels,
+                    network,
+                    (hide_cancelled, real_time_only, inverted),
+                )| {
+                    DepartureFilter::builder()
+                        .transport_types(transport_types)
+                        .platforms(platforms)
+                        .destination(destination)
+                        .vehicle_labels(vehicle_labels)
+                        .network(network)
+                        .hide_cancelled(hide_cancelled)
+                        .real_time_only(real_time_only)
+                        .inverted(inverted)
+                        .build()
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn deep_link_round_trips(
+            global_id in "de:[0-9]{5}:[0-9]{1,4}",
+            limit in prop::option::of(1u8..=u8::MAX),
+            filter in arb_filter(),
+        ) {
+            let deep_link = DeepLink { global_id, limit, filter };
+            prop_assert_eq!(DeepLink::parse(&deep_link.to_link()).unwrap(), deep_link);
+        }
+
+        #[test]
+        fn filter_round_trips_through_the_query_string(filter in arb_filter()) {
+            let query = filter.to_url_query_string();
+            prop_assert_eq!(DepartureFilter::from_url_query_string(&query).unwrap(), filter);
+        }
+
+        #[test]
+        fn filter_round_trips_through_a_deep_link(filter in arb_filter(), limit in 1u8..=u8::MAX) {
+            let link = format!(
+                "mvg_dioxus://station/de:09184:2000?{}&limit={limit}",
+                filter.to_url_query_string()
+            );
+            let deep_link = DeepLink::parse(&link).unwrap();
+            prop_assert_eq!(deep_link.limit, Some(limit));
+            prop_assert_eq!(deep_link.filter, filter);
+        }
+    }
+
+    #[test]
+    fn urgency_class_boundaries() {
+        assert_eq!(urgency_class(0), "urgent");
+        assert_eq!(urgency_class(2), "urgent");
+        assert_eq!(urgency_class(3), "soon");
+        assert_eq!(urgency_class(5), "soon");
+        assert_eq!(urgency_class(6), "normal");
+        assert_eq!(urgency_class(45), "normal");
+    }
+
+    #[test]
+    fn departed_trains_are_not_urgent() {
+        assert_eq!(urgency_class(-1), "normal");
+        let departed = departure("S1", "Freising", at(8, 0));
+        assert_eq!(departed.minutes_until(at(8, 3)), -3);
+        assert_eq!(urgency_class(departed.minutes_until(at(8, 3))), "normal");
+    }
+
+    #[test]
+    fn minutes_until_counts_whole_minutes_of_the_real_time() {
+        let mut delayed = departure("S1", "Freising", at(8, 0));
+        delayed.actual_time = at(8, 4) + Duration::seconds(30);
+        assert_eq!(delayed.minutes_until(at(8, 0)), 4);
+        assert_eq!(urgency_class(delayed.minutes_until(at(8, 0))), "soon");
+    }
+
+    #[test]
+    fn cancelled_departures_count_from_the_planned_time() {
+        let mut cancelled = departure("S1", "Freising", at(8, 2));
+        cancelled.actual_time = at(8, 10);
+        cancelled.cancelled = true;
+        assert_eq!(cancelled.minutes_until(at(8, 0)), 2);
+        assert_eq!(urgency_class(cancelled.minutes_until(at(8, 0))), "urgent");
+    }
+
+    #[test]
+    fn departure_leaving_now_is_urgent() {
+        let leaving = departure("S1", "Freising", at(8, 0));
+        assert_eq!(leaving.minutes_until(at(8, 0)), 0);
+        assert_eq!(urgency_class(leaving.minutes_until(at(8, 0))), "urgent");
+    }
+
+    #[test]
+    fn mvg_web_url_links_the_line_and_stop() {
+        let mut departure = departure("S1", "Freising", at(8, 0));
+        departure.diva_id = DivaId("92M01".to_string());
+        departure.stop_point_global_id = GlobalId("de:09162:6:40:81".to_string());
+        assert_eq!(
+            departure.mvg_web_url(),
+            "https://www.mvg.de/verbindungen/fahrplan.html?line=92M01&stop=de:09162:6:40:81"
+        );
+    }
+
+    #[test]
+    fn summary_names_the_first_running_departure() {
+        let departures = [
+            departure("S1", "München Hbf", at(8, 4)),
+            departure("S8", "Flughafen", at(8, 9)),
+        ];
+        assert_eq!(
+            next_departure_summary(&departures, at(8, 0)).as_deref(),
+            Some("Next S1 to München Hbf in 4 minutes")
+        );
+    }
+
+    #[test]
+    fn summary_skips_cancelled_and_departed_trains() {
+        let mut cancelled = departure("S1", "Freising", at(8, 2));
+        cancelled.cancelled = true;
+        let departures = [
+            departure("S2", "Erding", at(7, 58)),
+            cancelled,
+            departure("S8", "Flughafen", at(8, 1)),
+        ];
+        assert_eq!(
+            next_departure_summary(&departures, at(8, 0)).as_deref(),
+            Some("Next S8 to Flughafen in 1 minute")
+        );
+    }
+
+    #[test]
+    fn summary_without_running_services() {
+        let mut cancelled = departure("S1", "Freising", at(8, 2));
+        cancelled.cancelled = true;
+        assert_eq!(
+            next_departure_summary(&[cancelled], at(8, 0)).as_deref(),
+            Some("No services running")
+        );
+    }
+
+    #[test]
+    fn identical_fetches_are_unchanged() {
+        let departures = [departure("S1", "Freising", at(8, 0))];
+        assert!(is_unchanged_fetch(Some(&departures), Some(&departures)));
+    }
+
+    #[test]
+    fn platform_change_alone_updates_the_board() {
+        let before = [departure("S1", "Freising", at(8, 0))];
+        let mut after = before.clone();
+        after[0].platform = 2;
+        assert!(!is_unchanged_fetch(Some(&before), Some(&after)));
+    }
+
+    #[test]
+    fn first_and_failed_fetches_are_never_unchanged() {
+        let departures = [departure("S1", "Freising", at(8, 0))];
+        assert!(!is_unchanged_fetch(None, Some(&departures)));
+        assert!(!is_unchanged_fetch(Some(&departures), None));
+        assert!(!is_unchanged_fetch(None, None));
+    }
+
+    #[test]
+    fn summary_of_an_empty_board_is_none() {
+        assert_eq!(next_departure_summary(&[], at(8, 0)), None);
+    }
+
+    #[test]
+    fn shortcut_keys_ignore_case() {
+        for (key, shortcut) in [
+            ("r", Shortcut::Refresh),
+            ("R", Shortcut::Refresh),
+            ("q", Shortcut::Quit),
+            ("F", Shortcut::ToggleFilters),
+            ("m", Shortcut::ToggleMute),
+            ("Escape", Shortcut::ClosePanels),
+            ("?", Shortcut::ToggleHelp),
+        ] {
+            assert_eq!(Shortcut::from_key(key), Some(shortcut), "{key}");
+        }
+    }
+
+    #[test]
+    fn number_keys_switch_to_existing_tabs_only() {
+        let mut ui = UiState::default();
+        assert_eq!(
+            handle_shortcut("3", &mut ui, 4),
+            Some(Shortcut::SwitchTab(2))
+        );
+        assert_eq!(ui.active_tab, 2);
+        assert_eq!(handle_shortcut("5", &mut ui, 4), None);
+        assert_eq!(ui.active_tab, 2);
+        assert_eq!(Shortcut::from_key("0"), None);
+        assert_eq!(Shortcut::from_key(&(MAX_TABS + 1).to_string()), None);
+    }
+
+    #[test]
+    fn escape_closes_every_panel_but_the_filters() {
+        let mut ui = UiState {
+            show_history: true,
+            show_journey: true,
+            show_qr: true,
+            show_map: true,
+            show_transfers: true,
+            show_merged: true,
+            show_stats: true,
+            show_help: true,
+            ..UiState::default()
+        };
+        handle_shortcut("Escape", &mut ui, 1);
+        assert_eq!(ui, UiState::default());
+    }
+
+    #[test]
+    fn toggle_shortcuts_flip_their_state() {
+        let mut ui = UiState::default();
+        for key in ["f", "?", "m"] {
+            handle_shortcut(key, &mut ui, 1);
+        }
+        assert!(!ui.show_filters && ui.show_help && ui.muted);
+        for key in ["F", "?", "M"] {
+            handle_shortcut(key, &mut ui, 1);
+        }
+        assert_eq!(ui, UiState::default());
+    }
+
+    #[test]
+    fn refresh_bumps_the_generation_and_quit_changes_nothing() {
+        let mut ui = UiState::default();
+        handle_shortcut("r", &mut ui, 1);
+        handle_shortcut("r", &mut ui, 1);
+        assert_eq!(ui.refresh_generation, 2);
+        let before = ui.clone();
+        assert_eq!(handle_shortcut("q", &mut ui, 1), Some(Shortcut::Quit));
+        assert_eq!(ui, before);
+    }
+
+    #[test]
+    fn unknown_keys_are_no_shortcut() {
+        let mut ui = UiState::default();
+        for key in ["x", "Enter", "", " "] {
+            assert_eq!(handle_shortcut(key, &mut ui, 1), None, "{key:?}");
+        }
+        assert_eq!(ui, UiState::default());
+    }
+
+    #[test]
+    fn absolute_mode_always_shows_the_clock() {
+        let departure = departure("S1", "Freising", at(8, 4));
+        assert_eq!(
+            format_departure_time(TimeDisplayMode::Absolute, &departure, at(8, 0)),
+            "08:04"
+        );
+    }
+
+    #[test]
+    fn relative_mode_counts_down_and_up() {
+        let departure = departure("S1", "Freising", at(8, 40));
+        let format = |now| format_departure_time(TimeDisplayMode::Relative, &departure, now);
+        assert_eq!(format(at(8, 0)), "in 40 min");
+        assert_eq!(format(at(8, 39)), "in 1 min");
+        assert_eq!(format(at(8, 40)), "now");
+        assert_eq!(format(at(8, 40) + Duration::seconds(30)), "departed");
+        assert_eq!(format(at(8, 45)), "departed 5 min ago");
+    }
+
+    #[test]
+    fn auto_mode_switches_to_the_clock_after_30_minutes() {
+        let departure = departure("S1", "Freising", at(8, 30));
+        let format = |now| format_departure_time(TimeDisplayMode::Auto, &departure, now);
+        assert_eq!(format(at(8, 0)), "in 30 min");
+        assert_eq!(format(at(7, 59)), "08:30");
+    }
+
+    #[test]
+    fn auto_mode_counts_up_for_departed_trains() {
+        let departure = departure("S1", "Freising", at(8, 0));
+        assert_eq!(
+            format_departure_time(TimeDisplayMode::Auto, &departure, at(8, 3)),
+            "departed 3 min ago"
+        );
+    }
+
+    #[test]
+    fn time_display_modes_cycle() {
+        let mode = TimeDisplayMode::default();
+        assert_eq!(mode, TimeDisplayMode::Auto);
+        assert_eq!(mode.next().next().next(), mode);
+        assert_ne!(mode.next(), mode);
+    }
+
+    #[test]
+    fn data_is_fresh_up_to_twice_the_interval() {
+        assert!(!is_stale(at(8, 0), at(8, 0), 30));
+        assert!(!is_stale(at(8, 0), at(8, 1), 30));
+    }
+
+    #[test]
+    fn data_is_stale_after_twice_the_interval() {
+        assert!(is_stale(at(8, 0), at(8, 1) + Duration::seconds(1), 30));
+        assert!(is_stale(at(8, 0), at(9, 0), 30));
+    }
+
+    #[test]
+    fn stale_threshold_follows_the_interval() {
+        assert!(!is_stale(at(8, 0), at(8, 10), 300));
+        assert!(is_stale(at(8, 0), at(8, 10) + Duration::seconds(1), 300));
+    }
+
+    #[test]
+    fn always_on_top_defaults_to_off() {
+        assert!(!Config::default().always_on_top);
+        assert_eq!(window_title(false), WINDOW_TITLE);
+    }
+
+    #[test]
+    fn flipping_always_on_top_is_persisted() {
+        let path = temp_path("config.toml");
+        let mut config = Config::default();
+        for expected in [true, false] {
+            config.always_on_top = !config.always_on_top;
+            config.save_to(&path).unwrap();
+            let saved = Config::load_from(&path);
+            assert_eq!(saved.always_on_top, expected);
+            assert_eq!(saved, config);
+        }
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn pinned_window_title_shows_the_pin() {
+        assert_eq!(window_title(true), format!("📌 {WINDOW_TITLE}"));
+    }
+
+    #[test]
+    fn ordinary_geometry_is_valid() {
+        assert!(is_valid_geometry(0, 0, 800, 600));
+        assert!(is_valid_geometry(1920, 100, 1280, 1024));
+    }
+
+    #[test]
+    fn negative_positions_are_invalid() {
+        assert!(!is_valid_geometry(-1, 0, 800, 600));
+        assert!(!is_valid_geometry(0, -32000, 800, 600));
+    }
+
+    #[test]
+    fn zero_sizes_are_invalid() {
+        assert!(!is_valid_geometry(0, 0, 0, 600));
+        assert!(!is_valid_geometry(0, 0, 800, 0));
+    }
+
+    #[test]
+    fn geometry_far_off_screen_is_invalid() {
+        assert!(!is_valid_geometry(
+            MAX_WINDOW_COORDINATE as i32,
+            0,
+            800,
+            600
+        ));
+        assert!(!is_valid_geometry(0, 0, MAX_WINDOW_COORDINATE + 1, 600));
+    }
+
+    #[test]
+    fn window_geometry_is_persisted_with_the_config() {
+        let path = temp_path("config.toml");
+        let geometry = WindowGeometry {
+            x: 40,
+            y: 80,
+            width: 1024,
+            height: 768,
+        };
+        let config = Config {
+            window_geometry: Some(geometry),
+            ..Config::default()
+        };
+        config.save_to(&path).unwrap();
+        let saved = Config::load_from(&path).window_geometry;
+        assert_eq!(saved, Some(geometry));
+        assert!(saved.is_some_and(|geometry| geometry.is_valid()));
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn default_transport_type_filter_saves_other_types_by_marker() {
+        let saved = toml::to_string(&Config::default()).unwrap();
+        assert!(
+            saved.contains(r#"transport_type_filter = ["SBAHN", "UBAHN", "BUS", "TRAM", "BAHN", "REGIONAL_SCHNELL_BAHN", "SEV", "OTHER"]"#),
+            "{saved}"
+        );
+        let loaded: Config = toml::from_str(&saved).unwrap();
+        assert_eq!(
+            loaded.transport_type_filter,
+            Config::default().transport_type_filter
+        );
+    }
+
+    #[test]
+    fn empty_transport_type_from_older_configs_is_other() {
+        let loaded: Config = toml::from_str(r#"transport_type_filter = ["SBAHN", ""]"#).unwrap();
+        assert_eq!(
+            loaded.transport_type_filter,
+            [TransportType::Sbahn, TransportType::Unknown(String::new())]
+        );
+    }
+
+    #[test]
+    fn groups_are_ordered_by_their_earliest_departure() {
+        let departures = [
+            departure("S8", "Flughafen", at(8, 10)),
+            departure("S1", "Freising", at(8, 5)),
+            departure("U6", "Klinikum", at(8, 1)),
+            departure("S1", "Freising", at(8, 25)),
+            departure("S8", "Herrsching", at(8, 3)),
+        ];
+        let groups = group_by_line(&departures.iter().collect_vec());
+        let summary = groups
+            .iter()
+            .map(|(label, group)| {
+                let times = group
+                    .iter()
+                    .map(|departure| departure.displayed_time().format("%H:%M").to_string())
+                    .collect_vec();
+                (label.as_str(), times)
+            })
+            .collect_vec();
+        assert_eq!(
+            summary,
+            [
+                ("U6", vec!["08:01".to_string()]),
+                ("S8", vec!["08:03".to_string(), "08:10".to_string()]),
+                ("S1", vec!["08:05".to_string(), "08:25".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn grouping_nothing_gives_no_groups() {
+        assert!(group_by_line(&[]).is_empty());
+    }
+
+    #[test]
+    fn cancelled_departures_are_never_imminent() {
+        let mut cancelled = departure("S1", "Freising", at(8, 1));
+        cancelled.cancelled = true;
+        for now in [at(7, 0), at(8, 0), at(8, 1), at(8, 2)] {
+            assert!(!cancelled.is_imminent(now, 120), "{now}");
+        }
+        assert!(!cancelled.is_imminent(at(8, 0), i64::MAX));
+    }
+
+    #[test]
+    fn departure_within_the_threshold_is_imminent() {
+        let departure = departure("S1", "Freising", at(8, 2));
+        assert!(departure.is_imminent(at(8, 0), 120));
+        assert!(departure.is_imminent(at(8, 2), 120));
+        assert!(!departure.is_imminent(at(8, 0) - Duration::seconds(1), 120));
+    }
+
+    #[test]
+    fn departed_trains_stop_being_imminent() {
+        let departure = departure("S1", "Freising", at(8, 0));
+        assert!(!departure.is_imminent(at(8, 0) + Duration::seconds(1), 120));
+    }
+
+    #[test]
+    fn imminent_threshold_defaults_to_two_minutes() {
+        assert_eq!(Config::default().imminent_threshold_secs, 120);
+    }
+
+    fn mixed_board() -> Vec<Departure> {
+        let mut tram = departure("N17", "Effnerplatz", at(8, 1));
+        tram.transport_type = TransportType::Tram;
+        tram.platform = 3;
+        let mut subway = departure("U6", "Klinikum Großhadern", at(8, 2));
+        subway.transport_type = TransportType::Ubahn;
+        subway.platform = 2;
+        let mut cancelled = departure("S8", "Flughafen München", at(8, 3));
+        cancelled.cancelled = true;
+        let mut regional = departure("RE 5", "Salzburg Hbf", at(8, 4));
+        regional.transport_type = TransportType::RegionalBahn;
+        regional.network = "db".to_string();
+        regional.is_real_time = false;
+        regional.platform = 14;
+        let mut cable_car = departure("SB", "Wallberg", at(8, 5));
+        cable_car.transport_type = TransportType::Unknown("SEILBAHN".to_string());
+        vec![
+            departure("S1", "Freising", at(8, 0)),
+            tram,
+            subway,
+            cancelled,
+            regional,
+            cable_car,
+        ]
+    }
+
+    fn filtered<'a>(filter: &DepartureFilter, departures: &'a [Departure]) -> Vec<&'a str> {
+        filter
+            .apply(departures)
+            .into_iter()
+            .map(|departure| departure.destination.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn default_filter_keeps_every_departure_in_order() {
+        let board = mixed_board();
+        assert_eq!(
+            filtered(&DepartureFilter::default(), &board),
+            [
+                "Freising",
+                "Effnerplatz",
+                "Klinikum Großhadern",
+                "Flughafen München",
+                "Salzburg Hbf",
+                "Wallberg"
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_of_no_departures_is_empty() {
+        let filter = DepartureFilter::builder().hide_cancelled(true).build();
+        assert!(filter.apply(&[]).is_empty());
+    }
+
+    #[test]
+    fn filter_without_transport_types_hides_everything() {
+        let filter = DepartureFilter::builder().transport_types([]).build();
+        assert!(filter.apply(&mixed_board()).is_empty());
+    }
+
+    #[test]
+    fn filter_keeps_only_the_selected_transport_types() {
+        let filter = DepartureFilter::builder()
+            .transport_types([TransportType::Ubahn, TransportType::Tram])
+            .build();
+        assert_eq!(
+            filtered(&filter, &mixed_board()),
+            ["Effnerplatz", "Klinikum Großhadern"]
+        );
+    }
+
+    #[test]
+    fn filter_toggling_a_transport_type_removes_then_restores_it() {
+        let without_sbahn = DepartureFilter::builder()
+            .toggle_transport_type(&TransportType::Sbahn)
+            .build();
+        assert_eq!(
+            filtered(&without_sbahn, &mixed_board()),
+            [
+                "Effnerplatz",
+                "Klinikum Großhadern",
+                "Salzburg Hbf",
+                "Wallberg"
+            ]
+        );
+        assert_eq!(
+            without_sbahn
+                .to_builder()
+                .toggle_transport_type(&TransportType::Sbahn)
+                .build(),
+            DepartureFilter::default()
+        );
+    }
+
+    #[test]
+    fn filter_for_other_transport_types_matches_every_unknown_one() {
+        let filter = DepartureFilter::builder()
+            .transport_types([TransportType::Unknown(String::new())])
+            .build();
+        assert_eq!(filtered(&filter, &mixed_board()), ["Wallberg"]);
+    }
+
+    #[test]
+    fn filter_keeps_only_the_selected_platforms() {
+        let filter = DepartureFilter::builder()
+            .platforms(Some(vec![2, 3]))
+            .build();
+        assert_eq!(
+            filtered(&filter, &mixed_board()),
+            ["Effnerplatz", "Klinikum Großhadern"]
+        );
+    }
+
+    #[test]
+    fn filter_destination_ignores_case_and_surrounding_whitespace() {
+        let filter = DepartureFilter::builder()
+            .destination(Some("  MÜNCHEN ".to_string()))
+            .build();
+        assert_eq!(filtered(&filter, &mixed_board()), ["Flughafen München"]);
+    }
+
+    #[test]
+    fn empty_criteria_are_no_filter() {
+        let filter = DepartureFilter::builder()
+            .platforms(Some(Vec::new()))
+            .destination(Some("   ".to_string()))
+            .vehicle_labels(Some(Vec::new()))
+            .network(Some(String::new()))
+            .build();
+        assert_eq!(filter, DepartureFilter::default());
+    }
+
+    #[test]
+    fn filter_vehicle_labels_ignore_case() {
+        let filter = DepartureFilter::builder()
+            .vehicle_labels(Some(vec!["u6".to_string(), "re 5".to_string()]))
+            .build();
+        assert_eq!(
+            filtered(&filter, &mixed_board()),
+            ["Klinikum Großhadern", "Salzburg Hbf"]
+        );
+    }
+
+    #[test]
+    fn hiding_cancelled_leaves_nothing_when_all_are_cancelled() {
+        let board = mixed_board()
+            .into_iter()
+            .map(|mut departure| {
+                departure.cancelled = true;
+                departure
+            })
+            .collect_vec();
+        let filter = DepartureFilter::builder().hide_cancelled(true).build();
+        assert!(filter.apply(&board).is_empty());
+        assert_eq!(DepartureFilter::default().apply(&board).len(), board.len());
+    }
+
+    #[test]
+    fn filter_keeps_only_the_selected_network() {
+        let filter = DepartureFilter::builder()
+            .network(Some("db".to_string()))
+            .build();
+        assert_eq!(filtered(&filter, &mixed_board()), ["Salzburg Hbf"]);
+    }
+
+    #[test]
+    fn real_time_only_hides_scheduled_departures() {
+        let filter = DepartureFilter::builder().real_time_only(true).build();
+        assert!(!filtered(&filter, &mixed_board()).contains(&"Salzburg Hbf"));
+    }
+
+    #[test]
+    fn filter_criteria_must_all_match() {
+        let filter = DepartureFilter::builder()
+            .transport_types([TransportType::Sbahn])
+            .platforms(Some(vec![1]))
+            .destination(Some("f".to_string()))
+            .hide_cancelled(true)
+            .build();
+        assert_eq!(filtered(&filter, &mixed_board()), ["Freising"]);
+    }
+
+    fn s1_board() -> Vec<Departure> {
+        let mut cancelled = departure("S1", "München Ost", at(15, 10));
+        cancelled.cancelled = true;
+        let mut delayed = departure("S1", "München Ost", at(15, 20));
+        delayed.actual_time = at(15, 35);
+        vec![
+            departure("S1", "München Ost", at(15, 0)),
+            cancelled,
+            departure("S2", "München Ost", at(15, 12)),
+            departure("S1", "Freising", at(15, 15)),
+            delayed,
+        ]
+    }
+
+    fn s1_alert(deadline: DateTime<Local>) -> ConnectionAlert {
+        ConnectionAlert {
+            label: "S1".to_string(),
+            destination_prefix: "München".to_string(),
+            deadline,
+        }
+    }
+
+    #[test]
+    fn departures_before_counts_matching_line_and_destination() {
+        assert_eq!(
+            departures_before(&s1_board(), at(15, 30), "S1", "München"),
+            1
+        );
+        assert_eq!(departures_before(&s1_board(), at(15, 30), "S1", ""), 2);
+        assert_eq!(
+            departures_before(&s1_board(), at(15, 30), "S2", "München"),
+            1
+        );
+    }
+
+    #[test]
+    fn departures_before_skips_cancelled_and_uses_the_real_time() {
+        assert_eq!(
+            departures_before(&s1_board(), at(15, 30), "S1", "München"),
+            1
+        );
+        assert_eq!(
+            departures_before(&s1_board(), at(15, 40), "S1", "München"),
+            2
+        );
+    }
+
+    #[test]
+    fn departures_before_excludes_the_deadline_itself() {
+        assert_eq!(
+            departures_before(&s1_board(), at(15, 0), "S1", "München"),
+            0
+        );
+        assert_eq!(departures_before(&[], at(15, 30), "S1", "München"), 0);
+    }
+
+    #[test]
+    fn connection_alert_ignores_departures_already_gone() {
+        let alert = s1_alert(at(15, 30));
+        assert_eq!(alert.remaining(&s1_board(), at(14, 55)), Some(1));
+        assert_eq!(alert.remaining(&s1_board(), at(15, 5)), Some(0));
+    }
+
+    #[test]
+    fn connection_alert_resets_once_the_deadline_passed() {
+        let alert = s1_alert(at(15, 30));
+        assert_eq!(alert.remaining(&s1_board(), at(15, 30)), None);
+        assert_eq!(alert.remaining(&s1_board(), at(16, 0)), None);
+    }
+
+    fn svg_texts(svg: &str) -> Vec<String> {
+        svg.split("<text")
+            .skip(1)
+            .map(|text| {
+                let content = &text[text.find('>').unwrap() + 1..];
+                content[..content.find("</text>").unwrap()]
+                    .trim()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn svg_has_a_title_and_one_row_per_departure() {
+        let departures = [
+            departure("S1", "Freising", at(8, 5)),
+            departure("U6", "Klinikum Großhadern", at(8, 12)),
+        ];
+        let texts = svg_texts(&render_departure_list_svg(
+            &departures,
+            at(8, 0),
+            "Hauptbahnhof",
+        ));
+        assert_eq!(texts.len(), 3);
+        assert_eq!(texts[0], "Hauptbahnhof – 15.01.2024 08:00");
+        assert!(
+            texts[1].starts_with("08:05   5 min  S1    Freising"),
+            "{}",
+            texts[1]
+        );
+        assert!(
+            texts[2].contains("U6    Klinikum Großhadern"),
+            "{}",
+            texts[2]
+        );
+    }
+
+    #[test]
+    fn svg_marks_delayed_and_cancelled_departures() {
+        let mut delayed = departure("S8", "Flughafen", at(8, 5));
+        delayed.actual_time = at(8, 9);
+        delayed.delay_seconds = Some(4 * 60);
+        let mut cancelled = departure("S1", "Freising", at(8, 10));
+        cancelled.cancelled = true;
+        let svg = render_departure_list_svg(&[delayed, cancelled], at(8, 0), "Hbf");
+        let texts = svg_texts(&svg);
+        assert!(texts[1].ends_with("+4"), "{}", texts[1]);
+        assert!(texts[2].ends_with("cancelled"), "{}", texts[2]);
+        assert_eq!(svg.matches(r##"fill="#999999""##).count(), 1);
+    }
+
+    #[test]
+    fn svg_height_grows_with_the_departures() {
+        let svg = render_departure_list_svg(&[], at(8, 0), "Hbf");
+        assert_eq!(svg_texts(&svg), ["Hbf – 15.01.2024 08:00"]);
+        assert!(svg.contains(r#"height="54""#), "{svg}");
+        let departures = vec![departure("S1", "Freising", at(8, 5)); 3];
+        let svg = render_departure_list_svg(&departures, at(8, 0), "Hbf");
+        assert!(svg.contains(r#"height="120""#), "{svg}");
+    }
+
+    #[test]
+    fn svg_escapes_station_names() {
+        let svg = render_departure_list_svg(&[], at(8, 0), "Karl<Theodor>&Co");
+        assert!(!svg.contains("Karl<Theodor>"), "{svg}");
+        assert!(svg.contains("Karl&lt;Theodor&gt;&amp;Co"), "{svg}");
+    }
+
+    fn seconds_after(minutes: u32, seconds: i64) -> DateTime<Local> {
+        at(8, minutes) + Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn walk_with_plenty_of_time_is_comfortable() {
+        let s1 = departure("S1", "Freising", at(8, 10));
+        assert_eq!(s1.walk_and_catch(at(8, 0), 5), WalkStatus::ComfortableWalk);
+    }
+
+    #[test]
+    fn walk_with_exactly_the_hurry_slack_is_comfortable() {
+        let s1 = departure("S1", "Freising", at(8, 10));
+        assert_eq!(
+            s1.walk_and_catch(seconds_after(8, -HURRY_SLACK_SECS), 0),
+            WalkStatus::ComfortableWalk
+        );
+        assert_eq!(s1.walk_and_catch(at(8, 3), 5), WalkStatus::ComfortableWalk);
+    }
+
+    #[test]
+    fn walk_just_under_the_hurry_slack_needs_hurry() {
+        let s1 = departure("S1", "Freising", at(8, 10));
+        assert_eq!(
+            s1.walk_and_catch(seconds_after(3, 1), 5),
+            WalkStatus::HurryNeeded
+        );
+    }
+
+    #[test]
+    fn walk_arriving_as_it_leaves_needs_hurry() {
+        let s1 = departure("S1", "Freising", at(8, 10));
+        assert_eq!(s1.walk_and_catch(at(8, 5), 5), WalkStatus::HurryNeeded);
+    }
+
+    #[test]
+    fn walk_longer_than_the_wait_misses_by_the_difference() {
+        let s1 = departure("S1", "Freising", at(8, 10));
+        assert_eq!(
+            s1.walk_and_catch(seconds_after(5, 30), 5),
+            WalkStatus::MissedBySeconds(30)
+        );
+        assert_eq!(
+            s1.walk_and_catch(at(8, 10), 5),
+            WalkStatus::MissedBySeconds(300)
+        );
+    }
+
+    #[test]
+    fn walk_to_a_departed_train_is_already_departed() {
+        let s1 = departure("S1", "Freising", at(8, 10));
+        assert_eq!(
+            s1.walk_and_catch(seconds_after(10, 1), 0),
+            WalkStatus::AlreadyDeparted
+        );
+    }
+
+    #[test]
+    fn walk_to_a_cancelled_train_is_cancelled() {
+        let mut s1 = departure("S1", "Freising", at(8, 10));
+        s1.cancelled = true;
+        assert_eq!(s1.walk_and_catch(at(8, 0), 5), WalkStatus::Cancelled);
+        assert_eq!(s1.walk_and_catch(at(9, 0), 5), WalkStatus::Cancelled);
+    }
+
+    #[test]
+    fn walk_uses_the_real_time() {
+        let mut s1 = departure("S1", "Freising", at(8, 10));
+        s1.actual_time = at(8, 14);
+        assert_eq!(s1.walk_and_catch(at(8, 6), 5), WalkStatus::ComfortableWalk);
+        assert_eq!(s1.walk_and_catch(at(8, 13), 0), WalkStatus::HurryNeeded);
+    }
+
+    #[test]
+    fn walk_hint_names_each_status() {
+        let s1 = departure("S1", "Freising", at(8, 10));
+        assert_eq!(walk_hint(&s1, at(8, 0), 5), "Walk normally");
+        assert_eq!(walk_hint(&s1, at(8, 4), 5), "Hurry!");
+        assert_eq!(
+            walk_hint(&s1, seconds_after(5, 30), 5),
+            "Missed by 30s — take next"
+        );
+        assert_eq!(walk_hint(&s1, at(8, 11), 5), "");
+    }
+
+    #[test]
+    fn walk_hint_is_empty_far_ahead_or_without_a_walk() {
+        let s1 = departure("S1", "Freising", at(8, 30));
+        assert_eq!(walk_hint(&s1, at(8, 0), 5), "");
+        assert_eq!(walk_hint(&s1, at(8, 29), 0), "");
+    }
+
+    #[test]
+    fn fixed_layout_modes_ignore_the_window_size() {
+        for (width, height) in [(1920, 1080), (600, 1000), (0, 0)] {
+            assert_eq!(
+                select_layout(width, height, LayoutMode::Landscape),
+                RenderedLayout::Landscape
+            );
+            assert_eq!(
+                select_layout(width, height, LayoutMode::Portrait),
+                RenderedLayout::Portrait
+            );
+        }
+    }
+
+    #[test]
+    fn auto_layout_goes_landscape_on_wide_windows() {
+        assert_eq!(
+            select_layout(1920, 1080, LayoutMode::Auto),
+            RenderedLayout::Landscape
+        );
+        assert_eq!(
+            select_layout(1401, 1000, LayoutMode::Auto),
+            RenderedLayout::Landscape
+        );
+    }
+
+    #[test]
+    fn auto_layout_stays_portrait_up_to_the_aspect_ratio() {
+        for (width, height) in [(1400, 1000), (1024, 768), (1000, 1000), (600, 1000)] {
+            assert_eq!(
+                select_layout(width, height, LayoutMode::Auto),
+                RenderedLayout::Portrait,
+                "{width}x{height}"
+            );
+        }
+    }
+
+    #[test]
+    fn auto_layout_of_an_empty_window_is_portrait() {
+        assert_eq!(
+            select_layout(0, 0, LayoutMode::Auto),
+            RenderedLayout::Portrait
+        );
+    }
+
+    #[test]
+    fn banner_hash_of_the_known_format_is_valid() {
+        assert!(validate_banner_hash("a1B2c3D4e5", 10));
+        assert!(validate_banner_hash("0123456789abcdef", 16));
+    }
+
+    #[test]
+    fn malformed_banner_hashes_are_invalid() {
+        for hash in [
+            "",
+            "a1B2c3D4e",
+            "a1B2c3D4e5f",
+            "a1B2c3-4e5",
+            "a1B2c3 4e5",
+            "a1B2c3D4é",
+        ] {
+            assert!(!validate_banner_hash(hash, 10), "{hash:?}");
+        }
+    }
+
+    #[test]
+    fn banner_hash_tracker_reports_each_hash_once() {
+        let mut tracker = BannerHashTracker::default();
+        let mut first = departure("S1", "Freising", at(8, 0));
+        first.banner_hash = "abc123".to_string();
+        let mut second = departure("S8", "Flughafen", at(8, 5));
+        second.banner_hash = "def456".to_string();
+        let unbannered = departure("U6", "Klinikum", at(8, 7));
+        assert_eq!(
+            tracker.observe(&[first.clone(), unbannered.clone()], at(8, 0)),
+            ["abc123"]
+        );
+        assert_eq!(
+            tracker.observe(&[first, second, unbannered], at(8, 1)),
+            ["def456"]
+        );
+        assert_eq!(tracker.first_seen.len(), 2);
+    }
+
+    fn departures_csv(departures: &[Departure], with_header: bool) -> String {
+        let mut csv = Vec::new();
+        write_departures_csv(&mut csv, departures, at(8, 0), with_header).unwrap();
+        String::from_utf8(csv).unwrap()
+    }
+
+    #[test]
+    fn csv_row_has_every_column_in_order() {
+        let mut delayed = departure("S8", "Flughafen München", at(8, 5));
+        delayed.actual_time = at(8, 8);
+        delayed.delay_seconds = Some(3 * 60);
+        delayed.occupancy = Occupancy::High;
+        delayed.platform = 2;
+        assert_eq!(
+            departures_csv(&[delayed], true),
+            format!(
+                "fetched_at,vehicle_label,destination,planned_time,actual_time,delay_minutes,cancelled,occupancy,platform\n\
+                 {},S8,Flughafen München,{},{},3,false,High,2\n",
+                at(8, 0).to_rfc3339(),
+                at(8, 5).to_rfc3339(),
+                at(8, 8).to_rfc3339()
+            )
+        );
+    }
+
+    #[test]
+    fn csv_row_leaves_the_delay_empty_without_real_time_data() {
+        let mut scheduled = departure("S1", "Freising", at(8, 5));
+        scheduled.delay_seconds = None;
+        scheduled.cancelled = true;
+        let row = departures_csv(&[scheduled], false);
+        assert!(row.ends_with(",,true,Unknown,1\n"), "{row}");
+    }
+
+    #[test]
+    fn csv_fields_with_separators_are_quoted() {
+        let destinations = ["Ost, Gleis 1", "\"Express\"", "Zeile\nZwei", "Zeile\rZwei"];
+        let departures = destinations.map(|destination| departure("S1", destination, at(8, 5)));
+        let csv = departures_csv(&departures, false);
+        assert!(csv.contains(",\"Zeile\rZwei\","), "{csv}");
+        let parsed = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(csv.as_bytes())
+            .records()
+            .map(|record| record.unwrap()[2].to_string())
+            .collect_vec();
+        assert_eq!(parsed, destinations);
+    }
+
+    #[test]
+    fn csv_log_file_is_named_after_the_day() {
+        let path = csv_log_path(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()).unwrap();
+        assert_eq!(path.file_name().unwrap(), "departures_2024-01-15.csv");
+    }
+
+    fn ical_property(event: &str, name: &str) -> String {
+        event
+            .replace("\r\n ", "")
+            .split("\r\n")
+            .find_map(|line| line.strip_prefix(&format!("{name}:")))
+            .unwrap_or_else(|| panic!("{name} missing from {event}"))
+            .to_string()
+    }
+
+    #[test]
+    fn ical_event_is_a_crlf_terminated_vevent() {
+        let event = departure("S1", "Freising", at(8, 10)).to_ical_event(5);
+        assert!(event.starts_with("BEGIN:VEVENT\r\n"), "{event}");
+        assert!(event.ends_with("END:VEVENT\r\n"), "{event}");
+        assert!(!event.replace("\r\n", "").contains('\n'), "{event}");
+        for name in [
+            "UID",
+            "DTSTAMP",
+            "DTSTART",
+            "DTEND",
+            "SUMMARY",
+            "DESCRIPTION",
+        ] {
+            ical_property(&event, name);
+        }
+    }
+
+    #[test]
+    fn ical_event_lasts_from_leaving_until_the_departure() {
+        let mut delayed = departure("S1", "Freising", at(8, 10));
+        delayed.actual_time = at(8, 13);
+        let event = delayed.to_ical_event(5);
+        let utc = |time: DateTime<Local>| {
+            time.with_timezone(&Utc)
+                .format(ICAL_DATETIME_FORMAT)
+                .to_string()
+        };
+        assert_eq!(ical_property(&event, "DTSTART"), utc(at(8, 8)));
+        assert_eq!(ical_property(&event, "DTEND"), utc(at(8, 13)));
+        assert!(ical_property(&event, "DTEND").ends_with('Z'));
+    }
+
+    #[test]
+    fn ical_event_summary_and_description_name_the_trip() {
+        let mut delayed = departure("S8", "Flughafen München", at(8, 10));
+        delayed.delay_seconds = Some(4 * 60);
+        delayed.platform = 2;
+        let event = delayed.to_ical_event(0);
+        assert_eq!(
+            ical_property(&event, "SUMMARY"),
+            "Catch S8 to Flughafen München"
+        );
+        assert!(ical_property(&event, "DESCRIPTION")
+            .starts_with("Platform 2\\nDelayed by 4 min\\nhttps://www.mvg.de/"));
+        assert_eq!(
+            ical_property(&event, "UID"),
+            format!(
+                "S8>Flughafen München@{}@mvg_dioxus",
+                at(8, 10).timestamp_millis()
+            )
+        );
+    }
+
+    #[test]
+    fn ical_text_escapes_special_characters() {
+        assert_eq!(
+            ical_text("Ost; Gleis 1, 2\\3\nneu"),
+            r"Ost\; Gleis 1\, 2\\3\nneu"
+        );
+    }
+
+    #[test]
+    fn long_ical_lines_fold_at_75_octets() {
+        let folded = fold_ical_line(&format!("SUMMARY:{}", "ü".repeat(60)));
+        let lines = folded.trim_end_matches("\r\n").split("\r\n").collect_vec();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.len() <= ICAL_LINE_OCTETS));
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        assert_eq!(
+            folded.replace("\r\n ", ""),
+            format!("SUMMARY:{}\r\n", "ü".repeat(60))
+        );
+        assert_eq!(fold_ical_line("END:VEVENT"), "END:VEVENT\r\n");
+    }
+
+    #[test]
+    fn on_time_departure_displays_as_on_time() {
+        assert_eq!(
+            crate::fixtures::on_time().to_string(),
+            "[S1] Freising @ 08:10 (on time)"
+        );
+    }
+
+    #[test]
+    fn delayed_departure_displays_the_real_time_and_delay() {
+        assert_eq!(
+            crate::fixtures::delayed().to_string(),
+            "[S8] Flughafen München @ 08:14 (+4 min)"
+        );
+    }
+
+    #[test]
+    fn cancelled_departure_displays_the_planned_time() {
+        let mut cancelled = crate::fixtures::cancelled();
+        cancelled.actual_time = at(8, 20);
+        assert_eq!(cancelled.to_string(), "[S1] Ostbahnhof @ 08:10 (cancelled)");
+    }
+
+    #[test]
+    fn sev_departure_is_marked() {
+        assert_eq!(
+            crate::fixtures::sev().to_string(),
+            "[S7] Wolfratshausen @ 08:10 (on time, SEV)"
+        );
+    }
+
+    #[test]
+    fn departure_without_real_time_data_displays_as_scheduled() {
+        assert_eq!(
+            String::from(&crate::fixtures::scheduled_only()),
+            "[U6] Klinikum Großhadern @ 08:10 (scheduled)"
+        );
+    }
+
+    fn trend(samples: &[Option<i64>]) -> DelayTrend {
+        compute_delay_trend(&samples.iter().copied().collect())
+    }
+
+    #[test]
+    fn delay_trend_without_samples_is_stable() {
+        assert_eq!(trend(&[]), DelayTrend::Stable);
+    }
+
+    #[test]
+    fn delay_trend_without_real_time_data_is_stable() {
+        assert_eq!(trend(&[None; 10]), DelayTrend::Stable);
+    }
+
+    #[test]
+    fn delay_trend_of_a_single_sample_is_stable() {
+        assert_eq!(trend(&[Some(5)]), DelayTrend::Stable);
+        assert_eq!(trend(&[None, Some(5), None]), DelayTrend::Stable);
+    }
+
+    #[test]
+    fn delay_trend_of_equal_delays_is_stable() {
+        assert_eq!(trend(&[Some(3); 10]), DelayTrend::Stable);
+    }
+
+    #[test]
+    fn rising_delays_are_worsening() {
+        assert_eq!(
+            trend(&[Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)]),
+            DelayTrend::Worsening
+        );
+        assert_eq!(trend(&[Some(0), Some(2)]), DelayTrend::Worsening);
+    }
+
+    #[test]
+    fn falling_delays_are_improving() {
+        assert_eq!(
+            trend(&[Some(6), Some(5), Some(4), Some(3), Some(2), Some(1)]),
+            DelayTrend::Improving
+        );
+    }
+
+    #[test]
+    fn delay_trend_ignores_changes_within_the_threshold() {
+        assert_eq!(
+            trend(&[Some(2), Some(2), Some(2), Some(2), Some(2), Some(3)]),
+            DelayTrend::Stable
+        );
+        assert_eq!(
+            trend(&[Some(2), Some(2), Some(2), Some(3), Some(3), Some(2)]),
+            DelayTrend::Worsening
+        );
+    }
+
+    #[test]
+    fn delay_trend_only_compares_the_latest_windows() {
+        assert_eq!(
+            trend(&[
+                Some(9),
+                Some(9),
+                Some(9),
+                Some(1),
+                Some(1),
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4)
+            ]),
+            DelayTrend::Worsening
+        );
+    }
+
+    #[test]
+    fn delay_trend_skips_samples_without_real_time_data() {
+        assert_eq!(
+            trend(&[Some(1), None, Some(1), None, Some(4), Some(4)]),
+            DelayTrend::Worsening
+        );
+    }
+
+    #[test]
+    fn delay_samples_keep_the_latest_per_line() {
+        let mut samples = HashMap::new();
+        for minute in 0..12 {
+            let mut s1 = departure("S1", "Freising", at(8, minute));
+            s1.delay_seconds = Some(minute as i64 * 60);
+            let later_s1 = departure("S1", "Freising", at(8, minute + 20));
+            let mut cancelled_s8 = departure("S8", "Flughafen", at(8, minute));
+            cancelled_s8.cancelled = true;
+            record_delay_samples(&mut samples, &[cancelled_s8, s1, later_s1]);
+        }
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples["S1"], (2..12).map(Some).collect::<VecDeque<_>>());
+    }
+
+    fn tickers() -> Vec<Ticker> {
+        serde_json::from_str(
+            r#"[
+                {"text": "Signalstörung am Ostbahnhof", "lines": ["S1", "S8"]},
+                {"text": "Bauarbeiten zwischen Giesel- und Odeonsplatz", "lines": ["U3", "U6"]},
+                {"text": "Allgemeine Information"}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    fn relevant(displayed_lines: &[&str]) -> Vec<String> {
+        let displayed_lines = displayed_lines.iter().map(ToString::to_string).collect();
+        relevant_tickers(&tickers(), &displayed_lines)
+            .into_iter()
+            .map(|ticker| ticker.text.clone())
+            .collect()
+    }
+
+    #[test]
+    fn tickers_for_any_displayed_line_are_relevant() {
+        assert_eq!(relevant(&["S8"]), ["Signalstörung am Ostbahnhof"]);
+        assert_eq!(
+            relevant(&["S1", "U6", "Bus 54"]),
+            [
+                "Signalstörung am Ostbahnhof",
+                "Bauarbeiten zwischen Giesel- und Odeonsplatz"
+            ]
+        );
+    }
+
+    #[test]
+    fn tickers_for_other_lines_are_not_relevant() {
+        assert!(relevant(&["S2", "U1"]).is_empty());
+        assert!(relevant(&[]).is_empty());
+    }
+
+    #[test]
+    fn tickers_without_lines_are_never_relevant() {
+        let tickers = tickers();
+        assert!(tickers[2].lines.is_empty());
+        assert!(!relevant(&["S1", "S8", "U3", "U6"]).contains(&tickers[2].text));
+    }
+
+    #[test]
+    fn ticker_lines_must_match_exactly() {
+        assert!(relevant(&["s1", "S", "U"]).is_empty());
+    }
+
+    fn connections(
+        from: &[Departure],
+        to: &[Departure],
+        travel_minutes: u32,
+    ) -> Vec<(String, String, i64)> {
+        find_connections(from, to, travel_minutes)
+            .into_iter()
+            .map(|pair| {
+                (
+                    pair.from_departure.to_string(),
+                    pair.to_departure.to_string(),
+                    pair.slack_minutes,
+                )
+            })
+            .collect()
+    }
+
+    fn line(label: &str, times: &[(u32, u32)]) -> Vec<Departure> {
+        times
+            .iter()
+            .map(|&(hour, minute)| departure(label, "Ostbahnhof", at(hour, minute)))
+            .collect()
+    }
+
+    #[test]
+    fn connection_with_time_to_spare_is_comfortable() {
+        let from = line("S1", &[(8, 0)]);
+        let to = line("U5", &[(8, 20)]);
+        assert_eq!(
+            connections(&from, &to, 10),
+            [(from[0].to_string(), to[0].to_string(), 10)]
+        );
+    }
+
+    #[test]
+    fn connection_arriving_as_it_leaves_has_no_slack() {
+        let from = line("S1", &[(8, 0)]);
+        let to = line("U5", &[(8, 10)]);
+        assert_eq!(connections(&from, &to, 10)[0].2, 0);
+    }
+
+    #[test]
+    fn just_missed_connection_takes_the_next_one() {
+        let from = line("S1", &[(8, 0)]);
+        let to = line("U5", &[(8, 9), (8, 19)]);
+        assert_eq!(
+            connections(&from, &to, 10),
+            [(from[0].to_string(), to[1].to_string(), 9)]
+        );
+    }
+
+    #[test]
+    fn connection_waits_for_the_earliest_onward_departure() {
+        let from = line("S1", &[(8, 0)]);
+        let to = line("U5", &[(8, 30), (8, 15), (8, 25)]);
+        assert_eq!(connections(&from, &to, 10)[0].1, to[1].to_string());
+    }
+
+    #[test]
+    fn departures_without_an_onward_connection_are_left_out() {
+        let from = line("S1", &[(8, 0), (8, 20), (8, 40)]);
+        let to = line("U5", &[(8, 15), (8, 35)]);
+        assert_eq!(
+            connections(&from, &to, 10),
+            [
+                (from[0].to_string(), to[0].to_string(), 5),
+                (from[1].to_string(), to[1].to_string(), 5)
+            ]
+        );
+        assert!(connections(&from, &[], 10).is_empty());
+    }
+
+    #[test]
+    fn cancelled_departures_make_no_connection() {
+        let mut from = line("S1", &[(8, 0), (8, 5)]);
+        from[0].cancelled = true;
+        let mut to = line("U5", &[(8, 16), (8, 26)]);
+        to[0].cancelled = true;
+        assert_eq!(
+            connections(&from, &to, 10),
+            [(from[1].to_string(), to[1].to_string(), 11)]
+        );
+    }
+
+    #[test]
+    fn connection_uses_the_real_times() {
+        let mut from = line("S1", &[(8, 0)]);
+        from[0].actual_time = at(8, 4);
+        let mut to = line("U5", &[(8, 12)]);
+        to[0].actual_time = at(8, 16);
+        assert_eq!(connections(&from, &to, 10)[0].2, 2);
+    }
+
+    fn bucket_of(latency_ms: u64) -> usize {
+        let mut metrics = FetchMetrics::default();
+        update_latency_histogram(&mut metrics, latency_ms);
+        metrics
+            .latency_ms_buckets
+            .iter()
+            .position(|&count| count == 1)
+            .unwrap()
+    }
+
+    #[test]
+    fn latency_below_the_first_bound_goes_into_the_first_bucket() {
+        assert_eq!(bucket_of(0), 0);
+        assert_eq!(bucket_of(49), 0);
+    }
+
+    #[test]
+    fn latency_on_a_bound_goes_into_the_next_bucket() {
+        for (index, &bound) in LATENCY_BUCKET_BOUNDS_MS.iter().enumerate() {
+            assert_eq!(bucket_of(bound - 1), index, "{}ms", bound - 1);
+            assert_eq!(bucket_of(bound), index + 1, "{bound}ms");
+        }
+    }
+
+    #[test]
+    fn slow_latencies_go_into_the_last_bucket() {
+        assert_eq!(bucket_of(5000), 9);
+        assert_eq!(bucket_of(u64::MAX), 9);
+    }
+
+    #[test]
+    fn histogram_update_counts_fetches_and_percentiles() {
+        let mut metrics = FetchMetrics::default();
+        for _ in 0..99 {
+            update_latency_histogram(&mut metrics, 120);
+        }
+        update_latency_histogram(&mut metrics, 6000);
+        assert_eq!(metrics.total_fetches, 100);
+        assert_eq!(metrics.latency_ms_buckets[2], 99);
+        assert!(
+            (100.0..200.0).contains(&metrics.p50_ms),
+            "{}",
+            metrics.p50_ms
+        );
+        assert_eq!(metrics.p99_ms, 200.0);
+        assert_eq!(metrics.percentile(100.0), 5000.0);
+    }
+
+    #[test]
+    fn percentiles_of_no_fetches_are_zero() {
+        assert_eq!(FetchMetrics::default().percentile(50.0), 0.0);
+    }
+
+    #[test]
+    fn histogram_scales_the_bars_to_the_fullest_bucket() {
+        let mut metrics = FetchMetrics::default();
+        metrics.latency_ms_buckets[0] = 4;
+        metrics.latency_ms_buckets[9] = 1;
+        let rows = metrics.histogram().lines().map(String::from).collect_vec();
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[0], format!("   <50ms |{} 4", "█".repeat(20)));
+        assert_eq!(rows[1], "  <100ms | 0");
+        assert_eq!(rows[9], format!(">=5000ms |{} 1", "█".repeat(5)));
+    }
+
+    fn pin(label: &str, destination: &str) -> (String, String) {
+        (label.to_string(), destination.to_string())
+    }
+
+    fn pinned_order(pinned: &[(String, String)], departures: &[Departure]) -> Vec<String> {
+        apply_pinned_ordering(pinned, &departures.iter().collect_vec())
+            .into_iter()
+            .map(|departure| {
+                format!(
+                    "{} {}",
+                    departure.vehicle_label,
+                    departure.minutes_until(at(8, 0))
+                )
+            })
+            .collect()
+    }
+
+    fn pinning_board() -> Vec<Departure> {
+        vec![
+            departure("S1", "Freising", at(8, 1)),
+            departure("U6", "Klinikum", at(8, 2)),
+            departure("S8", "Flughafen", at(8, 3)),
+            departure("S1", "Freising", at(8, 4)),
+            departure("S8", "Flughafen", at(8, 5)),
+        ]
+    }
+
+    #[test]
+    fn pinned_lines_come_first_in_pinned_order() {
+        let pinned = [pin("S8", "Flughafen"), pin("S1", "Freising")];
+        assert_eq!(
+            pinned_order(&pinned, &pinning_board()),
+            ["S8 3", "S8 5", "S1 1", "S1 4", "U6 2"]
+        );
+    }
+
+    #[test]
+    fn without_pins_the_order_is_kept() {
+        assert_eq!(
+            pinned_order(&[], &pinning_board()),
+            ["S1 1", "U6 2", "S8 3", "S1 4", "S8 5"]
+        );
+    }
+
+    #[test]
+    fn pins_match_line_and_destination() {
+        let pinned = [pin("S8", "Freising"), pin("U6", "Klinikum")];
+        assert_eq!(
+            pinned_order(&pinned, &pinning_board()),
+            ["U6 2", "S1 1", "S8 3", "S1 4", "S8 5"]
+        );
+    }
+
+    #[test]
+    fn reordering_moves_the_dragged_line_to_the_target() {
+        let mut pinned = vec![pin("S1", "A"), pin("S2", "B"), pin("S3", "C")];
+        reorder_pinned(&mut pinned, &pin("S3", "C"), &pin("S1", "A"));
+        assert_eq!(pinned, [pin("S3", "C"), pin("S1", "A"), pin("S2", "B")]);
+        reorder_pinned(&mut pinned, &pin("S3", "C"), &pin("S2", "B"));
+        assert_eq!(pinned, [pin("S1", "A"), pin("S2", "B"), pin("S3", "C")]);
+    }
+
+    #[test]
+    fn reordering_with_an_unknown_line_changes_nothing() {
+        let mut pinned = vec![pin("S1", "A"), pin("S2", "B")];
+        reorder_pinned(&mut pinned, &pin("S9", "Z"), &pin("S1", "A"));
+        reorder_pinned(&mut pinned, &pin("S2", "B"), &pin("S9", "Z"));
+        assert_eq!(pinned, [pin("S1", "A"), pin("S2", "B")]);
+    }
+
+    // Hide mode must show exactly what show mode leaves out
+    fn assert_inversion_is_the_complement(filter: DepartureFilter) {
+        let board = mixed_board();
+        let shown = filtered(&filter, &board);
+        let hidden = filtered(&filter.clone().invert(), &board);
+        assert!(shown
+            .iter()
+            .all(|destination| !hidden.contains(destination)));
+        assert_eq!(shown.len() + hidden.len(), board.len(), "{filter:?}");
+    }
+
+    #[test]
+    fn inverting_transport_types_gives_the_complement() {
+        assert_inversion_is_the_complement(
+            DepartureFilter::builder()
+                .transport_types([TransportType::Bus, TransportType::Tram])
+                .build(),
+        );
+        let everything_but_trams = DepartureFilter::builder()
+            .transport_types([TransportType::Tram])
+            .build()
+            .invert();
+        assert!(!filtered(&everything_but_trams, &mixed_board()).contains(&"Effnerplatz"));
+    }
+
+    #[test]
+    fn inverting_lines_gives_the_complement() {
+        let filter = DepartureFilter::builder()
+            .vehicle_labels(Some(vec!["S1".to_string(), "u6".to_string()]))
+            .build();
+        assert_inversion_is_the_complement(filter.clone());
+        assert_eq!(
+            filtered(&filter.invert(), &mixed_board()),
+            [
+                "Effnerplatz",
+                "Flughafen München",
+                "Salzburg Hbf",
+                "Wallberg"
+            ]
+        );
+    }
+
+    #[test]
+    fn inverting_types_and_lines_together_gives_the_complement() {
+        assert_inversion_is_the_complement(
+            DepartureFilter::builder()
+                .transport_types([TransportType::Sbahn, TransportType::Ubahn])
+                .vehicle_labels(Some(vec!["S8".to_string(), "U6".to_string()]))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn inverting_everything_or_nothing_gives_the_complement() {
+        assert_inversion_is_the_complement(DepartureFilter::default());
+        assert_inversion_is_the_complement(DepartureFilter::builder().transport_types([]).build());
+        assert!(DepartureFilter::default()
+            .invert()
+            .apply(&mixed_board())
+            .is_empty());
+    }
+
+    #[test]
+    fn inverting_twice_restores_the_filter() {
+        let filter = DepartureFilter::builder()
+            .transport_types([TransportType::Bus])
+            .build();
+        assert!(filter.clone().invert().inverted);
+        assert_eq!(filter.clone().invert().invert(), filter);
+    }
+
+    #[test]
+    fn inverting_keeps_the_other_criteria() {
+        let filter = DepartureFilter::builder()
+            .transport_types([TransportType::Tram])
+            .hide_cancelled(true)
+            .real_time_only(true)
+            .build()
+            .invert();
+        assert_eq!(
+            filtered(&filter, &mixed_board()),
+            ["Freising", "Klinikum Großhadern", "Wallberg"]
+        );
+    }
+
+    #[test]
+    fn effective_delay_of_an_early_departure_is_negative() {
+        let early = crate::fixtures::early();
+        assert_eq!(early.effective_delay_minutes(), Some(-1.0));
+        assert_eq!(early.delay(), Some(Duration::minutes(-1)));
+    }
+
+    #[test]
+    fn effective_delay_keeps_fractions_of_a_minute() {
+        let mut late = departure("S1", "Freising", at(8, 0));
+        late.delay_seconds = Some(150);
+        assert_eq!(late.effective_delay_minutes(), Some(2.5));
+        assert_eq!(late.delay(), Some(Duration::minutes(3)));
+        assert_eq!(
+            crate::fixtures::delayed().effective_delay_minutes(),
+            Some(4.0)
+        );
+    }
+
+    #[test]
+    fn effective_delay_without_real_time_data_is_none() {
+        let scheduled = crate::fixtures::scheduled_only();
+        assert_eq!(scheduled.effective_delay_minutes(), None);
+        assert_eq!(scheduled.delay(), None);
+    }
+
+    fn merged(lists: &[(String, Vec<Departure>)]) -> Vec<(String, String)> {
+        merge_departure_lists(lists)
+            .into_iter()
+            .map(|(station, departure)| (station.to_string(), departure.to_string()))
+            .collect()
+    }
+
+    fn station_list(station: &str, departures: &[(&str, u32)]) -> (String, Vec<Departure>) {
+        (
+            station.to_string(),
+            departures
+                .iter()
+                .map(|&(label, minute)| departure(label, "Ostbahnhof", at(8, minute)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn merged_departures_are_sorted_and_keep_their_station() {
+        let lists = [
+            station_list("Hauptbahnhof", &[("S1", 2), ("S8", 6)]),
+            station_list("Karlsplatz", &[("U4", 1), ("U5", 4)]),
+        ];
+        let stations = merged(&lists)
+            .into_iter()
+            .map(|(station, departure)| format!("{station} {departure}"))
+            .collect_vec();
+        assert_eq!(
+            stations,
+            [
+                "Karlsplatz [U4] Ostbahnhof @ 08:01 (on time)",
+                "Hauptbahnhof [S1] Ostbahnhof @ 08:02 (on time)",
+                "Karlsplatz [U5] Ostbahnhof @ 08:04 (on time)",
+                "Hauptbahnhof [S8] Ostbahnhof @ 08:06 (on time)"
+            ]
+        );
+    }
+
+    #[test]
+    fn merged_departures_at_the_same_time_keep_the_list_order() {
+        let lists = [
+            station_list("Hauptbahnhof", &[("S1", 5), ("S2", 5)]),
+            station_list("Karlsplatz", &[("U4", 5)]),
+            station_list("Stachus", &[("19", 5)]),
+        ];
+        let labels = merge_departure_lists(&lists)
+            .into_iter()
+            .map(|(_, departure)| departure.vehicle_label.as_str())
+            .collect_vec();
+        assert_eq!(labels, ["S1", "S2", "U4", "19"]);
+    }
+
+    #[test]
+    fn merged_departures_use_the_real_time() {
+        let mut lists = [
+            station_list("Hauptbahnhof", &[("S1", 1)]),
+            station_list("Karlsplatz", &[("U4", 3)]),
+        ];
+        lists[0].1[0].actual_time = at(8, 5);
+        assert_eq!(
+            merged(&lists)
+                .into_iter()
+                .map(|(station, _)| station)
+                .collect_vec(),
+            ["Karlsplatz", "Hauptbahnhof"]
+        );
+    }
+
+    #[test]
+    fn merging_empty_lists_gives_nothing() {
+        assert!(merge_departure_lists(&[]).is_empty());
+        assert_eq!(
+            merged(&[
+                station_list("Hauptbahnhof", &[]),
+                station_list("Karlsplatz", &[("U4", 1)])
+            ]),
+            [(
+                "Karlsplatz".to_string(),
+                "[U4] Ostbahnhof @ 08:01 (on time)".to_string()
+            )]
+        );
+    }
+
+    fn delayed_by(mut departure: Departure, minutes: i64) -> Departure {
+        departure.actual_time += Duration::minutes(minutes);
+        departure
+    }
+
+    #[test]
+    fn unchanged_departures_have_no_diff() {
+        let board = [
+            departure("S1", "Freising", at(8, 0)),
+            departure("S8", "Flughafen", at(8, 5)),
+        ];
+        assert!(diff_departures(&board, &board).is_empty());
+    }
+
+    #[test]
+    fn later_departure_time_is_a_positive_diff() {
+        let before = departure("S1", "Freising", at(8, 0));
+        let after = delayed_by(before.clone(), 3);
+        assert_eq!(
+            diff_departures(&[before], &[after]),
+            [DepartureDiff {
+                vehicle_label: "S1".to_string(),
+                destination: "Freising".to_string(),
+                old_time: at(8, 0),
+                new_time: at(8, 3),
+                delta_seconds: 180,
+            }]
+        );
+    }
+
+    #[test]
+    fn earlier_departure_time_is_a_negative_diff() {
+        let before = delayed_by(departure("S1", "Freising", at(8, 0)), 5);
+        let after = delayed_by(departure("S1", "Freising", at(8, 0)), 2);
+        assert_eq!(diff_departures(&[before], &[after])[0].delta_seconds, -180);
+    }
+
+    #[test]
+    fn added_and_removed_departures_have_no_diff() {
+        let removed = departure("S1", "Freising", at(8, 0));
+        let kept = departure("S8", "Flughafen", at(8, 5));
+        let added = departure("S2", "Erding", at(8, 7));
+        assert!(diff_departures(&[removed, kept.clone()], &[kept, added]).is_empty());
+        assert!(diff_departures(&[], &[departure("S1", "Freising", at(8, 0))]).is_empty());
+    }
+
+    #[test]
+    fn diffs_match_line_and_planned_time() {
+        let s1 = departure("S1", "Freising", at(8, 0));
+        let s8 = departure("S8", "Freising", at(8, 0));
+        let diffs = diff_departures(&[s1.clone(), s8.clone()], &[s1, delayed_by(s8.clone(), 1)]);
+        assert_eq!(diffs.len(), 1);
+        assert!(diffs[0].is_for(&delayed_by(s8.clone(), 1)));
+        assert!(!diffs[0].is_for(&s8));
+    }
+
+    #[test]
+    fn departure_cache_only_diffs_the_same_station() {
+        let before = [departure("S1", "Freising", at(8, 0))];
+        let after = [delayed_by(before[0].clone(), 2)];
+        let mut cache = DepartureCache::default();
+        assert!(cache.update("de:09162:6", &before).is_empty());
+        assert!(cache.update("de:09162:1", &after).is_empty());
+        assert_eq!(cache.update("de:09162:1", &before).len(), 1);
+        assert_eq!(cache.previous("de:09162:1"), Some(&before[..]));
+        assert_eq!(cache.previous("de:09162:6"), None);
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> reqwest::header::HeaderMap {
+        pairs
+            .iter()
+            .map(|&(name, value)| {
+                (
+                    reqwest::header::HeaderName::from_static(name),
+                    reqwest::header::HeaderValue::from_str(value).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn retry_after_in_seconds_is_parsed() {
+        assert_eq!(
+            parse_retry_after(&headers(&[("retry-after", "120")])),
+            Some(std::time::Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after(&headers(&[("retry-after", " 0 ")])),
+            Some(std::time::Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn retry_after_as_an_http_date_waits_until_then() {
+        let retry_at = Utc::now() + Duration::seconds(90);
+        let value = retry_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        let retry_after = parse_retry_after(&headers(&[("retry-after", &value)])).unwrap();
+        assert!(
+            (85..=90).contains(&retry_after.as_secs()),
+            "{retry_after:?}"
+        );
+    }
+
+    #[test]
+    fn retry_after_in_the_past_is_ignored() {
+        assert_eq!(
+            parse_retry_after(&headers(&[(
+                "retry-after",
+                "Wed, 21 Oct 2015 07:28:00 GMT"
+            )])),
+            None
+        );
+    }
+
+    #[test]
+    fn missing_or_invalid_retry_after_is_none() {
+        assert_eq!(parse_retry_after(&headers(&[])), None);
+        for value in ["-5", "soon", "1.5"] {
+            assert_eq!(
+                parse_retry_after(&headers(&[("retry-after", value)])),
+                None,
+                "{value}"
+            );
+        }
+    }
+
+    #[test]
+    fn rate_limit_remaining_is_parsed() {
+        assert_eq!(
+            parse_rate_limit_remaining(&headers(&[("x-ratelimit-remaining", "42")])),
+            Some(42)
+        );
+        assert_eq!(parse_rate_limit_remaining(&headers(&[])), None);
+        assert_eq!(
+            parse_rate_limit_remaining(&headers(&[("x-ratelimit-remaining", "-1")])),
+            None
+        );
+    }
+
+    #[test]
+    fn few_remaining_requests_double_the_poll_interval() {
+        let mut rate_limit = RateLimit::default();
+        assert_eq!(rate_limit.interval_factor(), 1);
+        rate_limit.record(&headers(&[("x-ratelimit-remaining", "1")]));
+        assert_eq!(rate_limit.interval_factor(), 2);
+        rate_limit.record(&headers(&[("x-ratelimit-remaining", "2")]));
+        assert_eq!(rate_limit.interval_factor(), 1);
+        assert_eq!(rate_limit.retry_at, None);
+    }
+
+    #[tokio::test]
+    async fn retry_after_suspends_until_the_given_time() {
+        let mut rate_limit = RateLimit::default();
+        let before = tokio::time::Instant::now();
+        rate_limit.record(&headers(&[("retry-after", "30")]));
+        let retry_at = rate_limit.retry_at.unwrap();
+        assert!(retry_at >= before + std::time::Duration::from_secs(30));
+        assert!(retry_at <= tokio::time::Instant::now() + std::time::Duration::from_secs(30));
+    }
 }
@@ -0,0 +1,970 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
+
+use chrono::prelude::*;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum TransportType {
+    Sbahn,
+    Ubahn,
+    Bus,
+    Tram,
+    RegionalBahn,
+    RegionalSchnellBahn,
+    Sev,
+    Unknown(String),
+}
+
+#[derive(Debug)]
+pub struct ParseTransportTypeError(String);
+
+impl Display for ParseTransportTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown transport type \"{}\"", self.0)
+    }
+}
+
+impl Error for ParseTransportTypeError {}
+
+impl TryFrom<&str> for TransportType {
+    type Error = ParseTransportTypeError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "SBAHN" => Ok(TransportType::Sbahn),
+            "UBAHN" => Ok(TransportType::Ubahn),
+            "BUS" => Ok(TransportType::Bus),
+            "TRAM" => Ok(TransportType::Tram),
+            "BAHN" => Ok(TransportType::RegionalBahn),
+            "REGIONAL_SCHNELL_BAHN" => Ok(TransportType::RegionalSchnellBahn),
+            "SEV" => Ok(TransportType::Sev),
+            _ => Err(ParseTransportTypeError(value.to_string())),
+        }
+    }
+}
+
+impl Serialize for TransportType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.api_name())
+    }
+}
+
+struct TransportTypeVisitor;
+
+impl<'de> serde::de::Visitor<'de> for TransportTypeVisitor {
+    type Value = TransportType;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a transport type string")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        Ok(TransportType::try_from(value).unwrap_or_else(|e| {
+            tracing::error!("{e}");
+            TransportType::Unknown(value.to_string())
+        }))
+    }
+}
+
+impl<'de> Deserialize<'de> for TransportType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(TransportTypeVisitor)
+    }
+}
+
+impl TransportType {
+    pub const ALL: [TransportType; 8] = [
+        TransportType::Sbahn,
+        TransportType::Ubahn,
+        TransportType::Bus,
+        TransportType::Tram,
+        TransportType::RegionalBahn,
+        TransportType::RegionalSchnellBahn,
+        TransportType::Sev,
+        TransportType::Unknown(String::new()),
+    ];
+
+    pub fn api_name(&self) -> &str {
+        match self {
+            TransportType::Sbahn => "SBAHN",
+            TransportType::Ubahn => "UBAHN",
+            TransportType::Bus => "BUS",
+            TransportType::Tram => "TRAM",
+            TransportType::RegionalBahn => "BAHN",
+            TransportType::RegionalSchnellBahn => "REGIONAL_SCHNELL_BAHN",
+            TransportType::Sev => "SEV",
+            TransportType::Unknown(name) => name,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransportType::Sbahn => "S-Bahn",
+            TransportType::Ubahn => "U-Bahn",
+            TransportType::Bus => "Bus",
+            TransportType::Tram => "Tram",
+            TransportType::RegionalBahn => "Regionalbahn",
+            TransportType::RegionalSchnellBahn => "RSB",
+            TransportType::Sev => "SEV",
+            TransportType::Unknown(_) => "Other",
+        }
+    }
+
+    pub fn matches_filter(&self, filter: &HashSet<TransportType>) -> bool {
+        match self {
+            TransportType::Unknown(_) => filter
+                .iter()
+                .any(|transport_type| matches!(transport_type, TransportType::Unknown(_))),
+            transport_type => filter.contains(transport_type),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Occupancy {
+    Low,
+    Medium,
+    High,
+    Unknown,
+}
+
+impl From<&str> for Occupancy {
+    fn from(value: &str) -> Self {
+        match value {
+            "LOW" => Occupancy::Low,
+            "MEDIUM" => Occupancy::Medium,
+            "HIGH" => Occupancy::High,
+            _ => Occupancy::Unknown,
+        }
+    }
+}
+
+impl Occupancy {
+    pub fn css_class(&self) -> &'static str {
+        match self {
+            Occupancy::Low => "occupancy occupancy_low",
+            Occupancy::Medium => "occupancy occupancy_medium",
+            Occupancy::High => "occupancy occupancy_high",
+            Occupancy::Unknown => "occupancy occupancy_unknown",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Occupancy::Low => "Low",
+            Occupancy::Medium => "Medium",
+            Occupancy::High => "High",
+            Occupancy::Unknown => "Unknown",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(transparent)]
+pub struct DivaId(pub String);
+
+impl Display for DivaId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(transparent)]
+pub struct GlobalId(pub String);
+
+impl Display for GlobalId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawDeparture {
+    #[serde(rename = "plannedDepartureTime")]
+    planned_departure_time_ms: u64,
+    // false when MVG has no live position for the vehicle, e.g. before the trip has
+    // started or for lines without tracking. realtimeDepartureTime then only
+    // repeats the planned time and delayInMinutes is missing or 0, so neither
+    // confirms that the vehicle is on time.
+    #[serde(rename = "realtime")]
+    is_real_time: bool,
+    #[serde(rename = "delayInMinutes", default)]
+    delay_minutes: u16,
+    #[serde(rename = "realtimeDepartureTime")]
+    real_departure_time_ms: u64,
+    transport_type: TransportType,
+    #[serde(rename = "label")]
+    vehicle_label: String,
+    diva_id: String,
+    network: String,
+    train_type: String,
+    destination: String,
+    cancelled: bool,
+    sev: bool,
+    platform: u16,
+    messages: Vec<String>,
+    banner_hash: String,
+    occupancy: String,
+    stop_point_global_id: String,
+}
+
+// Like the derived Debug, but without the banner hash
+impl std::fmt::Debug for RawDeparture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawDeparture")
+            .field("planned_departure_time_ms", &self.planned_departure_time_ms)
+            .field("is_real_time", &self.is_real_time)
+            .field("delay_minutes", &self.delay_minutes)
+            .field("real_departure_time_ms", &self.real_departure_time_ms)
+            .field("transport_type", &self.transport_type)
+            .field("vehicle_label", &self.vehicle_label)
+            .field("diva_id", &self.diva_id)
+            .field("network", &self.network)
+            .field("train_type", &self.train_type)
+            .field("destination", &self.destination)
+            .field("cancelled", &self.cancelled)
+            .field("sev", &self.sev)
+            .field("platform", &self.platform)
+            .field("messages", &self.messages)
+            .field("banner_hash", &"<redacted>")
+            .field("occupancy", &self.occupancy)
+            .field("stop_point_global_id", &self.stop_point_global_id)
+            .finish()
+    }
+}
+
+mod local_ts_millis {
+    use chrono::{DateTime, Local, Utc};
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time: &DateTime<Local>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        chrono::serde::ts_milliseconds::serialize(&time.with_timezone(&Utc), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<DateTime<Local>, D::Error> {
+        chrono::serde::ts_milliseconds::deserialize(deserializer)
+            .map(|time| time.with_timezone(&Local))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Departure {
+    #[serde(with = "local_ts_millis")]
+    pub actual_time: DateTime<Local>,
+    #[serde(with = "local_ts_millis")]
+    pub planned_time: DateTime<Local>,
+    // Negative when the vehicle leaves early, None without real-time data
+    pub delay_seconds: Option<i64>,
+    pub destination: String,
+    pub cancelled: bool,
+    pub vehicle_label: String,
+    pub transport_type: TransportType,
+    pub occupancy: Occupancy,
+    pub messages: Vec<String>,
+    pub sev: bool,
+    pub platform: u16,
+    pub diva_id: DivaId,
+    pub stop_point_global_id: GlobalId,
+    pub network: String,
+    pub train_type: Option<String>,
+    #[serde(default)]
+    pub banner_hash: String,
+    #[serde(default)]
+    pub trip_id: Option<String>,
+    #[serde(default)]
+    pub is_real_time: bool,
+    #[serde(default)]
+    pub cancellation_reason: Option<String>,
+}
+
+// Departures sort by the time shown on the board, so the list stays in time order
+// even with cancellations; moving those last is up to the sort settings. The trip
+// id keeps departures at the same time in a stable order. Only two snapshots of
+// the same trip tie on all of that, and comparing every other field keeps the
+// order consistent with PartialEq.
+impl Ord for Departure {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.displayed_time(), &self.planned_time, &self.trip_id)
+            .cmp(&(other.displayed_time(), &other.planned_time, &other.trip_id))
+            .then_with(|| {
+                (
+                    &self.actual_time,
+                    self.cancelled,
+                    &self.delay_seconds,
+                    &self.vehicle_label,
+                    &self.destination,
+                    &self.transport_type,
+                    self.platform,
+                    &self.occupancy,
+                    &self.messages,
+                )
+                    .cmp(&(
+                        &other.actual_time,
+                        other.cancelled,
+                        &other.delay_seconds,
+                        &other.vehicle_label,
+                        &other.destination,
+                        &other.transport_type,
+                        other.platform,
+                        &other.occupancy,
+                        &other.messages,
+                    ))
+            })
+            .then_with(|| {
+                (
+                    self.sev,
+                    &self.diva_id,
+                    &self.stop_point_global_id,
+                    &self.network,
+                    &self.train_type,
+                    &self.banner_hash,
+                    self.is_real_time,
+                    &self.cancellation_reason,
+                )
+                    .cmp(&(
+                        other.sev,
+                        &other.diva_id,
+                        &other.stop_point_global_id,
+                        &other.network,
+                        &other.train_type,
+                        &other.banner_hash,
+                        other.is_real_time,
+                        &other.cancellation_reason,
+                    ))
+            })
+    }
+}
+
+impl PartialOrd for Departure {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Departure {
+    pub fn displayed_time(&self) -> &DateTime<Local> {
+        if self.cancelled {
+            &self.planned_time
+        } else {
+            &self.actual_time
+        }
+    }
+}
+
+pub fn is_mvv_network(network: &str) -> bool {
+    network.eq_ignore_ascii_case("mvv")
+}
+
+fn merge_departures(departures: Vec<Departure>) -> Vec<Departure> {
+    let mut merged: Vec<Departure> = Vec::with_capacity(departures.len());
+    let mut index_by_trip: HashMap<String, usize> = HashMap::new();
+    for departure in departures {
+        let Some(trip_id) = departure.trip_id.clone() else {
+            merged.push(departure);
+            continue;
+        };
+        match index_by_trip.entry(trip_id) {
+            Entry::Occupied(entry) => {
+                let existing = &mut merged[*entry.get()];
+                if existing.delay_seconds.is_none() && departure.delay_seconds.is_some() {
+                    *existing = departure;
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(merged.len());
+                merged.push(departure);
+            }
+        }
+    }
+    merged
+}
+
+pub fn local_time_from_millis(millis: u64) -> Result<DateTime<Local>, MvgError> {
+    i64::try_from(millis)
+        .ok()
+        .and_then(|millis| Local.timestamp_millis_opt(millis).single())
+        .ok_or(MvgError::InvalidTimestamp(millis))
+}
+
+// MVG puts the reason for a cancellation first in the messages, if it gives one
+fn extract_cancellation_reason(cancelled: bool, messages: &[String]) -> Option<String> {
+    if !cancelled {
+        return None;
+    }
+    messages
+        .iter()
+        .map(|message| message.trim())
+        .find(|message| !message.is_empty())
+        .map(ToString::to_string)
+}
+
+impl TryFrom<RawDeparture> for Departure {
+    type Error = MvgError;
+
+    fn try_from(value: RawDeparture) -> Result<Self, Self::Error> {
+        let actual_time = local_time_from_millis(value.real_departure_time_ms)?;
+        let planned_time = local_time_from_millis(value.planned_departure_time_ms)?;
+        if !is_mvv_network(&value.network) {
+            tracing::warn!(
+                "Unexpected network {:?} for {} to {}",
+                value.network,
+                value.vehicle_label,
+                value.destination
+            );
+        }
+        // delayInMinutes is unsigned, so early departures only show in the timestamps
+        let delay_seconds = value.is_real_time.then(|| {
+            (value.real_departure_time_ms as i64 - value.planned_departure_time_ms as i64) / 1000
+        });
+        let cancellation_reason = extract_cancellation_reason(value.cancelled, &value.messages);
+        // The diva id names the line, not its direction, so opposite runs that
+        // leave in the same minute only differ by destination
+        let trip_id = (!value.diva_id.is_empty()).then(|| {
+            format!(
+                "{}>{}@{}",
+                value.diva_id, value.destination, value.planned_departure_time_ms
+            )
+        });
+        Ok(Departure {
+            actual_time,
+            planned_time,
+            delay_seconds,
+            destination: value.destination,
+            cancelled: value.cancelled,
+            vehicle_label: value.vehicle_label,
+            transport_type: value.transport_type,
+            occupancy: Occupancy::from(value.occupancy.as_str()),
+            messages: value.messages,
+            sev: value.sev,
+            platform: value.platform,
+            diva_id: DivaId(value.diva_id.clone()),
+            stop_point_global_id: GlobalId(value.stop_point_global_id),
+            network: value.network,
+            train_type: Some(value.train_type).filter(|train_type| !train_type.is_empty()),
+            banner_hash: value.banner_hash,
+            trip_id,
+            is_real_time: value.is_real_time,
+            cancellation_reason,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum MvgError {
+    Network(reqwest::Error),
+    Timeout,
+    Parse(serde_json::Error),
+    InvalidTimestamp(u64),
+    RateLimited,
+}
+
+impl MvgError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            // A 4xx response won't change on a retry, only server errors might
+            MvgError::Network(e) => {
+                e.is_connect()
+                    || e.is_timeout()
+                    || e.is_request()
+                    || e.status().is_some_and(|status| status.is_server_error())
+            }
+            MvgError::Timeout | MvgError::RateLimited => true,
+            MvgError::Parse(_) | MvgError::InvalidTimestamp(_) => false,
+        }
+    }
+}
+
+impl Display for MvgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MvgError::Network(e) if e.is_connect() => write!(f, "No internet connection"),
+            MvgError::Timeout => write!(f, "Request timed out — MVG API may be down"),
+            MvgError::Network(e) => write!(f, "Network error: {e}"),
+            MvgError::Parse(_) => {
+                write!(f, "API response changed format — please update the app")
+            }
+            MvgError::InvalidTimestamp(millis) => {
+                write!(f, "API returned an invalid timestamp: {millis}")
+            }
+            MvgError::RateLimited => write!(f, "Too many requests — the API is rate limiting us"),
+        }
+    }
+}
+
+impl Error for MvgError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MvgError::Network(e) => Some(e),
+            MvgError::Parse(e) => Some(e),
+            MvgError::Timeout | MvgError::InvalidTimestamp(_) | MvgError::RateLimited => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for MvgError {
+    fn from(value: reqwest::Error) -> Self {
+        if value.is_timeout() {
+            MvgError::Timeout
+        } else {
+            MvgError::Network(value)
+        }
+    }
+}
+
+impl From<serde_json::Error> for MvgError {
+    fn from(value: serde_json::Error) -> Self {
+        MvgError::Parse(value)
+    }
+}
+
+pub fn parse_departures(body: &[u8]) -> Result<Vec<Departure>, MvgError> {
+    let mut parse_errors = 0u32;
+    let departures = serde_json::from_slice::<Vec<RawDeparture>>(body)?
+        .into_iter()
+        .filter_map(|raw| match Departure::try_from(raw) {
+            Ok(departure) => Some(departure),
+            Err(e) => {
+                parse_errors += 1;
+                tracing::warn!(error = %e, "Skipping departure that failed to convert");
+                None
+            }
+        })
+        .collect_vec();
+    tracing::Span::current().record("parse_errors", parse_errors);
+    Ok(merge_departures(departures).into_iter().sorted().collect())
+}
+
+pub async fn departures_from_response(
+    response: reqwest::Response,
+) -> Result<Vec<Departure>, MvgError> {
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(MvgError::RateLimited);
+    }
+    let body = response.error_for_status()?.bytes().await?;
+    parse_departures(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::test_helpers::{at, departure};
+
+    // Few distinct values, so departures often tie on some or all of the sort key
+    fn arb_departure() -> impl Strategy<Value = Departure> {
+        (
+            0i64..3,
+            -1i64..3,
+            any::<bool>(),
+            0usize..2,
+            prop::option::of(0u8..2),
+            0u16..2,
+        )
+            .prop_map(|(planned, delay, cancelled, label, trip, platform)| {
+                let planned_time = at(8, 0) + Duration::minutes(planned);
+                let mut departure = departure(["S1", "S8"][label], "Freising", planned_time);
+                departure.actual_time = planned_time + Duration::minutes(delay);
+                departure.cancelled = cancelled;
+                departure.trip_id = trip.map(|trip| format!("trip-{trip}"));
+                departure.platform = platform;
+                departure
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn ordering_is_antisymmetric(a in arb_departure(), b in arb_departure()) {
+            prop_assert_eq!(a.cmp(&b), b.cmp(&a).reverse());
+        }
+
+        #[test]
+        fn ordering_is_transitive(
+            a in arb_departure(),
+            b in arb_departure(),
+            c in arb_departure(),
+        ) {
+            let mut sorted = [a, b, c];
+            sorted.sort();
+            prop_assert!(sorted[0] <= sorted[2]);
+            prop_assert!(sorted[0].cmp(&sorted[1]) != std::cmp::Ordering::Greater);
+            prop_assert!(sorted[1].cmp(&sorted[2]) != std::cmp::Ordering::Greater);
+        }
+
+        #[test]
+        fn ordering_agrees_with_eq(a in arb_departure(), b in arb_departure()) {
+            prop_assert_eq!(a.cmp(&b) == std::cmp::Ordering::Equal, a == b);
+            prop_assert_eq!(a.cmp(&a.clone()), std::cmp::Ordering::Equal);
+        }
+
+        #[test]
+        fn sorting_keeps_displayed_time_order(
+            departures in prop::collection::vec(arb_departure(), 0..8),
+        ) {
+            let mut departures = departures;
+            departures.sort();
+            prop_assert!(departures
+                .windows(2)
+                .all(|pair| pair[0].displayed_time() <= pair[1].displayed_time()));
+        }
+    }
+
+    fn raw_departure(destination: &str, realtime: bool) -> serde_json::Value {
+        serde_json::json!({
+            "plannedDepartureTime": 1700000600000u64,
+            "realtime": realtime,
+            "realtimeDepartureTime": 1700000660000u64,
+            "transportType": "SBAHN",
+            "label": "S1",
+            "divaId": "92M01",
+            "network": "mvv",
+            "trainType": "",
+            "destination": destination,
+            "cancelled": false,
+            "sev": false,
+            "platform": 1,
+            "messages": [],
+            "bannerHash": "",
+            "occupancy": "LOW",
+            "stopPointGlobalId": "de:09184:2000:1:1",
+        })
+    }
+
+    fn parse(raw: &[serde_json::Value]) -> Vec<Departure> {
+        parse_departures(&serde_json::to_vec(raw).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn merge_departures_keeps_one_entry_per_trip() {
+        let first = departure("S1", "Freising", at(8, 0));
+        let merged = merge_departures(vec![first.clone(), first.clone()]);
+        assert_eq!(merged, [first]);
+    }
+
+    #[test]
+    fn merge_departures_prefers_real_time_data() {
+        let mut scheduled = departure("S1", "Freising", at(8, 0));
+        scheduled.delay_seconds = None;
+        scheduled.is_real_time = false;
+        let mut live = scheduled.clone();
+        live.delay_seconds = Some(60);
+        live.is_real_time = true;
+        let merged = merge_departures(vec![scheduled, live.clone()]);
+        assert_eq!(merged, [live]);
+    }
+
+    #[test]
+    fn merge_departures_keeps_distinct_trips_in_order() {
+        let departures = vec![
+            departure("S1", "Freising", at(8, 0)),
+            departure("S8", "Flughafen", at(8, 0)),
+            departure("S1", "Freising", at(8, 20)),
+        ];
+        assert_eq!(merge_departures(departures.clone()), departures);
+    }
+
+    #[test]
+    fn merge_departures_keeps_departures_without_trip_id() {
+        let mut unknown = departure("S1", "Freising", at(8, 0));
+        unknown.trip_id = None;
+        let merged = merge_departures(vec![unknown.clone(), unknown.clone()]);
+        assert_eq!(merged.len(), 2);
+    }
+
+    #[test]
+    fn trip_id_includes_the_destination() {
+        let departures = parse(&[
+            raw_departure("Freising", true),
+            raw_departure("Ostbahnhof", true),
+        ]);
+        assert_eq!(departures.len(), 2);
+        assert_eq!(
+            departures[0].trip_id.as_deref(),
+            Some("92M01>Freising@1700000600000")
+        );
+    }
+
+    #[test]
+    fn duplicated_trips_from_the_api_are_merged() {
+        let departures = parse(&[
+            raw_departure("Freising", false),
+            raw_departure("Freising", true),
+        ]);
+        assert_eq!(departures.len(), 1);
+        assert!(departures[0].is_real_time);
+    }
+
+    #[test]
+    fn cancelled_departures_sort_by_planned_time() {
+        let mut cancelled = departure("S1", "Freising", at(8, 0));
+        cancelled.actual_time = at(8, 30);
+        cancelled.cancelled = true;
+        let later = departure("S8", "Flughafen", at(8, 10));
+        let mut departures = vec![later.clone(), cancelled.clone()];
+        departures.sort();
+        assert_eq!(departures, [cancelled, later]);
+    }
+
+    #[test]
+    fn snapshots_of_one_trip_only_compare_equal_when_equal() {
+        let snapshot = departure("S1", "Freising", at(8, 0));
+        let changes: [fn(&mut Departure); 6] = [
+            |departure| departure.occupancy = Occupancy::High,
+            |departure| departure.messages.push("Zugausfall".to_string()),
+            |departure| departure.banner_hash = "abc123".to_string(),
+            |departure| departure.is_real_time = false,
+            |departure| departure.cancellation_reason = Some("Streik".to_string()),
+            |departure| departure.actual_time += Duration::milliseconds(1),
+        ];
+        for change in changes {
+            let mut changed = snapshot.clone();
+            change(&mut changed);
+            assert_ne!(snapshot, changed);
+            assert_ne!(snapshot.cmp(&changed), std::cmp::Ordering::Equal);
+            assert_eq!(snapshot.cmp(&changed), changed.cmp(&snapshot).reverse());
+        }
+        assert_eq!(snapshot.cmp(&snapshot.clone()), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn messages_survive_the_conversion_in_full() {
+        let messages = [
+            "Platform change: this train departs from platform 2".to_string(),
+            "Reduced frequency due to engineering works between Ostbahnhof and Leuchtenbergring. "
+                .repeat(20),
+            "Schienenersatzverkehr zwischen Grafing und Ebersberg – bitte Aushänge beachten"
+                .to_string(),
+        ];
+        let mut raw = raw_departure("Freising", true);
+        raw["messages"] = serde_json::json!(messages);
+        let departures = parse(&[raw]);
+        assert_eq!(departures[0].messages, messages);
+    }
+
+    #[test]
+    fn departures_without_messages_have_none() {
+        let departures = parse(&[raw_departure("Freising", true)]);
+        assert!(departures[0].messages.is_empty());
+    }
+
+    #[test]
+    fn stop_and_line_ids_are_carried_as_typed_fields() {
+        let departures = parse(&[raw_departure("Freising", true)]);
+        assert_eq!(departures[0].diva_id, DivaId("92M01".to_string()));
+        assert_eq!(
+            departures[0].stop_point_global_id,
+            GlobalId("de:09184:2000:1:1".to_string())
+        );
+        assert_eq!(departures[0].diva_id.to_string(), "92M01");
+        assert_eq!(
+            departures[0].stop_point_global_id.to_string(),
+            "de:09184:2000:1:1"
+        );
+    }
+
+    #[test]
+    fn transport_types_parse_from_their_api_names() {
+        for transport_type in TransportType::ALL
+            .into_iter()
+            .filter(|transport_type| !matches!(transport_type, TransportType::Unknown(_)))
+        {
+            assert_eq!(
+                TransportType::try_from(transport_type.api_name()).unwrap(),
+                transport_type
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_transport_type_is_an_error_naming_it() {
+        let Err(error) = TransportType::try_from("SEILBAHN") else {
+            panic!("SEILBAHN parsed as a known transport type");
+        };
+        assert_eq!(error.to_string(), "Unknown transport type \"SEILBAHN\"");
+    }
+
+    #[test]
+    fn unknown_transport_type_deserializes_as_unknown() {
+        let transport_type: TransportType = serde_json::from_str("\"SEILBAHN\"").unwrap();
+        assert_eq!(
+            transport_type,
+            TransportType::Unknown("SEILBAHN".to_string())
+        );
+        assert_eq!(
+            serde_json::to_string(&transport_type).unwrap(),
+            "\"SEILBAHN\""
+        );
+    }
+
+    #[test]
+    fn mvv_network_is_recognised_in_any_case() {
+        assert!(is_mvv_network("mvv"));
+        assert!(is_mvv_network("MVV"));
+        assert!(!is_mvv_network("db"));
+        assert!(!is_mvv_network(""));
+        assert!(!is_mvv_network("mvv-regional"));
+    }
+
+    #[test]
+    fn network_is_carried_through() {
+        let mut raw = raw_departure("Salzburg Hbf", true);
+        raw["network"] = serde_json::json!("db");
+        let departures = parse(&[raw]);
+        assert_eq!(departures[0].network, "db");
+    }
+
+    #[test]
+    fn empty_train_type_is_none() {
+        let departures = parse(&[raw_departure("Freising", true)]);
+        assert_eq!(departures[0].train_type, None);
+        let mut raw = raw_departure("Salzburg Hbf", true);
+        raw["trainType"] = serde_json::json!("RE");
+        assert_eq!(parse(&[raw])[0].train_type.as_deref(), Some("RE"));
+    }
+
+    #[test]
+    fn planned_time_of_zero_does_not_panic() {
+        let mut raw = raw_departure("Freising", true);
+        raw["plannedDepartureTime"] = 0.into();
+        let departures = parse(&[raw]);
+        assert_eq!(departures.len(), 1);
+        assert_eq!(departures[0].planned_time.timestamp_millis(), 0);
+    }
+
+    #[test]
+    fn out_of_range_timestamps_are_an_error() {
+        for millis in [u64::MAX, i64::MAX as u64] {
+            assert!(
+                matches!(
+                    local_time_from_millis(millis),
+                    Err(MvgError::InvalidTimestamp(invalid)) if invalid == millis
+                ),
+                "{millis}"
+            );
+        }
+    }
+
+    #[test]
+    fn departures_with_invalid_timestamps_are_skipped() {
+        let mut invalid = raw_departure("Flughafen", true);
+        invalid["realtimeDepartureTime"] = u64::MAX.into();
+        let departures = parse(&[invalid, raw_departure("Freising", true)]);
+        assert_eq!(
+            departures
+                .iter()
+                .map(|departure| departure.destination.as_str())
+                .collect_vec(),
+            ["Freising"]
+        );
+    }
+
+    #[test]
+    fn raw_departure_debug_redacts_the_banner_hash() {
+        let mut raw = raw_departure("Freising", true);
+        raw["bannerHash"] = "s3cr3th4sh".into();
+        let raw: RawDeparture = serde_json::from_value(raw).unwrap();
+        let debug = format!("{raw:?}");
+        assert!(!debug.contains("s3cr3th4sh"), "{debug}");
+        assert!(debug.contains(r#"banner_hash: "<redacted>""#), "{debug}");
+        assert!(debug.contains(r#"destination: "Freising""#), "{debug}");
+    }
+
+    fn messages(messages: &[&str]) -> Vec<String> {
+        messages.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn departures_that_run_have_no_cancellation_reason() {
+        for messages in [
+            messages(&[]),
+            messages(&["Fahrt fällt aus"]),
+            messages(&["", "Bauarbeiten"]),
+        ] {
+            assert_eq!(extract_cancellation_reason(false, &messages), None);
+        }
+    }
+
+    #[test]
+    fn cancellation_reason_is_the_first_message() {
+        assert_eq!(
+            extract_cancellation_reason(
+                true,
+                &messages(&["Fahrt fällt aus wegen einer Signalstörung", "Bauarbeiten"])
+            ),
+            Some("Fahrt fällt aus wegen einer Signalstörung".to_string())
+        );
+    }
+
+    #[test]
+    fn cancellation_reason_skips_blank_messages_and_trims() {
+        assert_eq!(
+            extract_cancellation_reason(true, &messages(&["", "  ", " Streik \n"])),
+            Some("Streik".to_string())
+        );
+    }
+
+    #[test]
+    fn cancellation_without_messages_has_no_reason() {
+        assert_eq!(extract_cancellation_reason(true, &[]), None);
+        assert_eq!(extract_cancellation_reason(true, &messages(&[" "])), None);
+    }
+
+    #[test]
+    fn cancellation_reason_survives_the_conversion() {
+        let mut cancelled = raw_departure("Freising", true);
+        cancelled["cancelled"] = true.into();
+        cancelled["messages"] = serde_json::json!(["Fahrt fällt aus"]);
+        let mut running = raw_departure("Erding", true);
+        running["messages"] = serde_json::json!(["Fahrt fällt aus"]);
+        let departures = parse(&[cancelled, running]);
+        let reasons = departures
+            .iter()
+            .map(|departure| {
+                (
+                    departure.destination.as_str(),
+                    departure.cancellation_reason.as_deref(),
+                )
+            })
+            .sorted()
+            .collect_vec();
+        assert_eq!(
+            reasons,
+            [("Erding", None), ("Freising", Some("Fahrt fällt aus"))]
+        );
+    }
+
+    fn delay_of(planned_ms: u64, real_ms: u64, realtime: bool) -> Option<i64> {
+        let mut raw = raw_departure("Freising", realtime);
+        raw["plannedDepartureTime"] = planned_ms.into();
+        raw["realtimeDepartureTime"] = real_ms.into();
+        raw["delayInMinutes"] = 0.into();
+        parse(&[raw])[0].delay_seconds
+    }
+
+    #[test]
+    fn early_departures_have_a_negative_delay() {
+        assert_eq!(delay_of(1700000600000, 1700000510000, true), Some(-90));
+    }
+
+    #[test]
+    fn late_departures_have_a_positive_delay() {
+        assert_eq!(delay_of(1700000600000, 1700000840000, true), Some(240));
+    }
+
+    #[test]
+    fn departures_without_real_time_data_have_no_delay() {
+        assert_eq!(delay_of(1700000600000, 1700000840000, false), None);
+    }
+}
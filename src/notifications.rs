@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+
+use chrono::prelude::*;
+use once_cell::sync::Lazy;
+
+const DELIVERY_INTERVAL: Duration = Duration::from_secs(1);
+const EXPIRY: Duration = Duration::from_secs(60);
+
+pub struct Notification {
+    // (label, destination, departure time), notifications with the same key are only queued once
+    pub key: (String, String, DateTime<Local>),
+    pub summary: String,
+    pub body: String,
+    enqueued_at: Instant,
+}
+
+impl Notification {
+    pub fn new(key: (String, String, DateTime<Local>), summary: String, body: String) -> Self {
+        Notification {
+            key,
+            summary,
+            body,
+            enqueued_at: Instant::now(),
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.enqueued_at.elapsed() >= EXPIRY
+    }
+}
+
+#[derive(Default)]
+pub struct AnnouncementQueue {
+    pending: Mutex<VecDeque<Notification>>,
+}
+
+impl AnnouncementQueue {
+    pub fn enqueue(&self, notification: Notification) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.retain(|pending| !pending.is_expired());
+        if pending
+            .iter()
+            .all(|pending| pending.key != notification.key)
+        {
+            pending.push_back(notification);
+        }
+    }
+
+    pub fn drain_one(&self) -> Option<Notification> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.retain(|pending| !pending.is_expired());
+        pending.pop_front()
+    }
+}
+
+static QUEUE: Lazy<AnnouncementQueue> = Lazy::new(AnnouncementQueue::default);
+static DRAINER: Once = Once::new();
+
+// Shows at most one notification per second so many imminent watches don't flood the desktop
+async fn drain(queue: &AnnouncementQueue, mut show: impl FnMut(Notification)) {
+    let mut interval = tokio::time::interval(DELIVERY_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Some(notification) = queue.drain_one() {
+            show(notification);
+        }
+    }
+}
+
+fn show(notification: Notification) {
+    if let Err(e) = notify_rust::Notification::new()
+        .summary(&notification.summary)
+        .body(&notification.body)
+        .show()
+    {
+        tracing::error!("Showing notification failed: {e}");
+    }
+}
+
+pub fn notify(notification: Notification) {
+    DRAINER.call_once(|| {
+        tokio::spawn(drain(&QUEUE, show));
+    });
+    QUEUE.enqueue(notification);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_helpers::at;
+
+    fn imminent(label: &str, minute: u32) -> Notification {
+        Notification::new(
+            (label.to_string(), "Freising".to_string(), at(8, minute)),
+            format!("{label} leaves soon"),
+            String::new(),
+        )
+    }
+
+    fn pending(queue: &AnnouncementQueue) -> Vec<String> {
+        std::iter::from_fn(|| queue.drain_one())
+            .map(|notification| notification.summary)
+            .collect()
+    }
+
+    #[test]
+    fn queue_delivers_in_enqueue_order() {
+        let queue = AnnouncementQueue::default();
+        queue.enqueue(imminent("S1", 0));
+        queue.enqueue(imminent("S8", 1));
+        assert_eq!(pending(&queue), ["S1 leaves soon", "S8 leaves soon"]);
+        assert!(queue.drain_one().is_none());
+    }
+
+    #[test]
+    fn queue_drops_duplicates_of_pending_notifications() {
+        let queue = AnnouncementQueue::default();
+        queue.enqueue(imminent("S1", 0));
+        queue.enqueue(imminent("S1", 0));
+        queue.enqueue(imminent("S1", 10));
+        assert_eq!(pending(&queue), ["S1 leaves soon", "S1 leaves soon"]);
+        queue.enqueue(imminent("S1", 0));
+        assert_eq!(pending(&queue), ["S1 leaves soon"]);
+    }
+
+    #[test]
+    fn queue_expires_undelivered_notifications() {
+        let queue = AnnouncementQueue::default();
+        let mut stale = imminent("S1", 0);
+        stale.enqueued_at = Instant::now() - EXPIRY;
+        queue.enqueue(stale);
+        queue.enqueue(imminent("S8", 1));
+        assert_eq!(pending(&queue), ["S8 leaves soon"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn simultaneous_notifications_show_at_most_one_per_second() {
+        let queue = Arc::new(AnnouncementQueue::default());
+        for minute in 0..10 {
+            queue.enqueue(imminent(&format!("S{minute}"), minute));
+        }
+        let shown = Arc::new(Mutex::new(Vec::new()));
+        let drainer = tokio::spawn({
+            let (queue, shown) = (queue.clone(), shown.clone());
+            async move {
+                drain(&queue, |_| {
+                    shown.lock().unwrap().push(tokio::time::Instant::now())
+                })
+                .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+        drainer.abort();
+        let shown = shown.lock().unwrap();
+        assert_eq!(shown.len(), 4);
+        assert!(shown
+            .windows(2)
+            .all(|pair| pair[1] - pair[0] >= DELIVERY_INTERVAL));
+        assert_eq!(pending(&queue).len(), 6);
+    }
+}
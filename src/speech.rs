@@ -0,0 +1,29 @@
+use std::sync::mpsc;
+
+use once_cell::sync::Lazy;
+
+// The speech engine isn't Send on every platform, so it lives on its own thread
+static SPEECH: Lazy<mpsc::Sender<String>> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel::<String>();
+    std::thread::spawn(move || {
+        let mut tts = match tts::Tts::default() {
+            Ok(tts) => tts,
+            Err(e) => {
+                tracing::error!("Initializing text-to-speech failed: {e}");
+                return;
+            }
+        };
+        for text in receiver {
+            if let Err(e) = tts.speak(text, true) {
+                tracing::error!("Speaking failed: {e}");
+            }
+        }
+    });
+    sender
+});
+
+pub fn speak(text: String) {
+    if SPEECH.send(text).is_err() {
+        tracing::warn!("Text-to-speech is unavailable");
+    }
+}
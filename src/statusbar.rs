@@ -0,0 +1,188 @@
+use std::io::Write;
+
+use chrono::{DateTime, Local};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{get_response, Departure, StationConfig};
+
+const STATUSBAR_DEPARTURES: usize = 2;
+const COLOR_GREEN: &str = "#00ff00";
+const COLOR_AMBER: &str = "#ffbf00";
+const COLOR_RED: &str = "#ff0000";
+
+#[derive(Serialize)]
+struct I3barHeader {
+    version: u8,
+}
+
+// A single block of the i3bar protocol
+#[derive(Serialize)]
+struct I3barBlock {
+    full_text: String,
+    color: &'static str,
+}
+
+fn status_color(minutes: i64) -> &'static str {
+    match minutes {
+        ..=1 => COLOR_RED,
+        2..=5 => COLOR_AMBER,
+        _ => COLOR_GREEN,
+    }
+}
+
+fn status_block(departures: &[Departure], now: DateTime<Local>) -> I3barBlock {
+    let upcoming = departures
+        .iter()
+        .filter(|departure| !departure.cancelled && departure.minutes_until(now) >= 0)
+        .take(STATUSBAR_DEPARTURES)
+        .collect_vec();
+    let Some(next) = upcoming.first() else {
+        return I3barBlock {
+            full_text: "No departures".to_string(),
+            color: COLOR_RED,
+        };
+    };
+    I3barBlock {
+        full_text: upcoming
+            .iter()
+            .map(|departure| {
+                format!(
+                    "{} in {}min",
+                    departure.vehicle_label,
+                    departure.minutes_until(now)
+                )
+            })
+            .join(" | "),
+        color: status_color(next.minutes_until(now)),
+    }
+}
+
+// The header and the opening of the endless array of status lines
+fn write_header(out: &mut impl Write) -> std::io::Result<()> {
+    let header = serde_json::to_string(&I3barHeader { version: 1 })?;
+    writeln!(out, "{header}")?;
+    writeln!(out, "[")?;
+    out.flush()
+}
+
+// Every status line is an array of blocks, followed by the comma of the endless array
+fn write_status_line(out: &mut impl Write, block: &I3barBlock) -> std::io::Result<()> {
+    let line = serde_json::to_string(&[block])?;
+    writeln!(out, "{line},")?;
+    out.flush()
+}
+
+// Prints one status line per refresh until the status bar closes stdout
+pub fn print_departures(station: &StationConfig, refresh_interval_secs: u64) {
+    let runtime = tokio::runtime::Runtime::new().expect("Creating the tokio runtime failed");
+    let mut stdout = std::io::stdout();
+    if write_header(&mut stdout).is_err() {
+        return;
+    }
+    loop {
+        let block = match runtime.block_on(get_response(station)) {
+            Ok(departures) => status_block(&departures, Local::now()),
+            Err(e) => I3barBlock {
+                full_text: format!("MVG: {e}"),
+                color: COLOR_RED,
+            },
+        };
+        if write_status_line(&mut stdout, &block).is_err() {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_secs(refresh_interval_secs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::test_helpers::{at, departure};
+
+    const HEADER: &str = include_str!("../tests/fixtures/i3bar_header.txt");
+    const STREAM: &str = include_str!("../tests/fixtures/i3bar_stream.txt");
+
+    fn board() -> Vec<Departure> {
+        vec![
+            departure("S1", "Freising", at(8, 4)),
+            departure("S8", "Flughafen", at(8, 9)),
+            departure("S2", "Erding", at(8, 15)),
+        ]
+    }
+
+    fn stream(blocks: &[I3barBlock]) -> String {
+        let mut out = Vec::new();
+        write_header(&mut out).unwrap();
+        for block in blocks {
+            write_status_line(&mut out, block).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn header_matches_the_fixture() {
+        assert_eq!(stream(&[]), HEADER);
+        let header: serde_json::Value =
+            serde_json::from_str(HEADER.lines().next().unwrap()).unwrap();
+        assert_eq!(header["version"], 1);
+        assert_eq!(HEADER.lines().nth(1), Some("["));
+    }
+
+    #[test]
+    fn status_lines_match_the_fixture() {
+        let now = at(8, 0);
+        let blocks = [
+            status_block(&board(), now),
+            status_block(&board(), now + Duration::minutes(3)),
+        ];
+        assert_eq!(stream(&blocks), STREAM);
+    }
+
+    // Every status line is an array of blocks with a full_text string and a #rrggbb colour
+    #[test]
+    fn fixture_status_lines_follow_the_protocol() {
+        let status_lines = STREAM.lines().skip(2).collect_vec();
+        assert!(!status_lines.is_empty());
+        for line in status_lines {
+            let line = line
+                .strip_suffix(',')
+                .expect("Status lines end with a comma");
+            let blocks: Vec<serde_json::Value> = serde_json::from_str(line).unwrap();
+            assert!(!blocks.is_empty());
+            for block in blocks {
+                assert!(block["full_text"].is_string());
+                let color = block["color"].as_str().unwrap();
+                assert!(color.len() == 7 && color.starts_with('#'), "{color}");
+                assert!(color[1..].chars().all(|c| c.is_ascii_hexdigit()), "{color}");
+            }
+        }
+    }
+
+    #[test]
+    fn color_follows_the_next_departure() {
+        assert_eq!(status_color(0), COLOR_RED);
+        assert_eq!(status_color(1), COLOR_RED);
+        assert_eq!(status_color(2), COLOR_AMBER);
+        assert_eq!(status_color(5), COLOR_AMBER);
+        assert_eq!(status_color(6), COLOR_GREEN);
+    }
+
+    #[test]
+    fn block_skips_cancelled_and_departed_trains() {
+        let mut departures = board();
+        departures[1].cancelled = true;
+        let block = status_block(&departures, at(8, 5));
+        assert_eq!(block.full_text, "S2 in 10min");
+        assert_eq!(block.color, COLOR_GREEN);
+    }
+
+    #[test]
+    fn block_without_departures_is_red() {
+        let block = status_block(&[], at(8, 0));
+        assert_eq!(block.full_text, "No departures");
+        assert_eq!(block.color, COLOR_RED);
+    }
+}
@@ -0,0 +1,219 @@
+// A minimal MVG API stand-in, so tests run without internet access
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeZone};
+use reqwest::StatusCode;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+use crate::mvg::{DivaId, GlobalId};
+use crate::{Departure, Occupancy, TransportType};
+
+pub const DEPARTURES: &str = include_str!("../../tests/fixtures/departures.json");
+pub const EMPTY: &str = include_str!("../../tests/fixtures/empty.json");
+pub const MALFORMED: &str = include_str!("../../tests/fixtures/malformed.json");
+pub const UNKNOWN_TRANSPORT_TYPE: &str =
+    include_str!("../../tests/fixtures/unknown_transport_type.json");
+
+const MAX_REQUEST_HEAD_BYTES: usize = 8 * 1024;
+
+// A file in a fresh directory of its own, so tests never touch the real config
+pub fn temp_path(file_name: &str) -> PathBuf {
+    static NEXT_DIRECTORY: AtomicUsize = AtomicUsize::new(0);
+    let directory = std::env::temp_dir().join(format!(
+        "mvg_dioxus_test_{}_{}",
+        std::process::id(),
+        NEXT_DIRECTORY.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = std::fs::remove_dir_all(&directory);
+    directory.join(file_name)
+}
+
+// A fixed local time on a weekday, so tests don't depend on the clock
+pub fn at(hour: u32, minute: u32) -> DateTime<Local> {
+    Local
+        .with_ymd_and_hms(2024, 1, 15, hour, minute, 0)
+        .single()
+        .expect("The test time is ambiguous")
+}
+
+// An on-time S-Bahn departure; tests change the fields they care about
+pub fn departure(vehicle_label: &str, destination: &str, time: DateTime<Local>) -> Departure {
+    Departure {
+        actual_time: time,
+        planned_time: time,
+        delay_seconds: Some(0),
+        destination: destination.to_string(),
+        cancelled: false,
+        vehicle_label: vehicle_label.to_string(),
+        transport_type: TransportType::Sbahn,
+        occupancy: Occupancy::Unknown,
+        messages: Vec::new(),
+        sev: false,
+        platform: 1,
+        diva_id: DivaId(format!("92M{vehicle_label}")),
+        stop_point_global_id: GlobalId("de:09184:2000:1:1".to_string()),
+        network: "mvv".to_string(),
+        train_type: None,
+        banner_hash: String::new(),
+        trip_id: Some(format!(
+            "{vehicle_label}>{destination}@{}",
+            time.timestamp_millis()
+        )),
+        is_real_time: true,
+        cancellation_reason: None,
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+}
+
+impl RecordedRequest {
+    pub fn query_value(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct MockResponse {
+    status: StatusCode,
+    body: &'static str,
+    latency: Duration,
+}
+
+pub struct MockMvgServer {
+    address: SocketAddr,
+    response: Arc<Mutex<MockResponse>>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockMvgServer {
+    // Serves the departures fixture with status 200 until told otherwise
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Binding the mock MVG server failed");
+        let address = listener
+            .local_addr()
+            .expect("The mock MVG server has no address");
+        let response = Arc::new(Mutex::new(MockResponse {
+            status: StatusCode::OK,
+            body: DEPARTURES,
+            latency: Duration::ZERO,
+        }));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let (shutdown, mut shutdown_rx) = oneshot::channel();
+        let (task_response, task_requests) = (response.clone(), requests.clone());
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown_rx => break,
+                    accepted = listener.accept() => {
+                        let Ok((stream, _)) = accepted else {
+                            continue;
+                        };
+                        tokio::spawn(respond(stream, task_response.clone(), task_requests.clone()));
+                    }
+                }
+            }
+        });
+        MockMvgServer {
+            address,
+            response,
+            requests,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    pub fn with_latency(self, latency: Duration) -> Self {
+        self.response.lock().unwrap().latency = latency;
+        self
+    }
+
+    pub fn with_status(self, status: StatusCode) -> Self {
+        self.response.lock().unwrap().status = status;
+        self
+    }
+
+    pub fn with_body(self, body: &'static str) -> Self {
+        self.response.lock().unwrap().body = body;
+        self
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockMvgServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn respond(
+    mut stream: TcpStream,
+    response: Arc<Mutex<MockResponse>>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+    requests.lock().unwrap().push(request);
+    let (status, body, latency) = {
+        let response = response.lock().unwrap();
+        (response.status, response.body, response.latency)
+    };
+    tokio::time::sleep(latency).await;
+    // Closing every connection keeps the shared HTTP client from reusing one
+    // across the per-test runtimes
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default(),
+        body.len()
+    );
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(body.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+// GET requests have no body, so the request ends with the blank line after the headers
+async fn read_request(stream: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 1024];
+    while !head.ends_with(b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 || head.len() > MAX_REQUEST_HEAD_BYTES {
+            return None;
+        }
+        head.extend_from_slice(&buffer[..read]);
+    }
+    let head = String::from_utf8(head).ok()?;
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = reqwest::Url::parse(&format!("http://localhost{}", request_line.next()?)).ok()?;
+    Some(RecordedRequest {
+        method,
+        path: target.path().to_string(),
+        query: target.query_pairs().into_owned().collect(),
+    })
+}
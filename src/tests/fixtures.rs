@@ -0,0 +1,58 @@
+// Known departures for the tile snapshot tests, all leaving Hauptbahnhof at 08:10
+use chrono::Duration;
+
+use crate::test_helpers::{at, departure};
+use crate::{Departure, Occupancy, TransportType};
+
+pub fn on_time() -> Departure {
+    departure("S1", "Freising", at(8, 10))
+}
+
+pub fn delayed() -> Departure {
+    let mut departure = departure("S8", "Flughafen München", at(8, 10));
+    departure.actual_time += Duration::minutes(4);
+    departure.delay_seconds = Some(4 * 60);
+    departure.occupancy = Occupancy::High;
+    departure
+}
+
+pub fn early() -> Departure {
+    let mut departure = departure("S2", "Erding", at(8, 10));
+    departure.actual_time -= Duration::minutes(1);
+    departure.delay_seconds = Some(-60);
+    departure
+}
+
+pub fn cancelled() -> Departure {
+    let mut departure = departure("S1", "Ostbahnhof", at(8, 10));
+    departure.cancelled = true;
+    departure.messages = vec!["Fahrt fällt aus".to_string()];
+    departure.cancellation_reason = Some("Fahrt fällt aus".to_string());
+    departure
+}
+
+pub fn sev() -> Departure {
+    let mut departure = departure("S7", "Wolfratshausen", at(8, 10));
+    departure.transport_type = TransportType::Sev;
+    departure.sev = true;
+    departure.messages =
+        vec!["Ersatzverkehr mit Bussen zwischen Höllriegelskreuth und Wolfratshausen".to_string()];
+    departure
+}
+
+pub fn scheduled_only() -> Departure {
+    let mut departure = departure("U6", "Klinikum Großhadern", at(8, 10));
+    departure.transport_type = TransportType::Ubahn;
+    departure.delay_seconds = None;
+    departure.is_real_time = false;
+    departure
+}
+
+pub fn regional_express() -> Departure {
+    let mut departure = departure("RE 5", "Salzburg Hbf", at(8, 10));
+    departure.transport_type = TransportType::RegionalBahn;
+    departure.train_type = Some("RE".to_string());
+    departure.network = "db".to_string();
+    departure.platform = 14;
+    departure
+}
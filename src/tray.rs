@@ -0,0 +1,131 @@
+use std::cell::RefCell;
+use std::error::Error;
+
+use chrono::{DateTime, Local};
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{ClickType, Icon, TrayIcon, TrayIconBuilder, TrayIconEvent};
+
+use crate::Departure;
+
+const ICON_SIZE: u32 = 16;
+const ICON_COLOR: [u8; 4] = [0x34, 0x98, 0xdb, 0xff];
+const PEEK_LENGTH: usize = 3;
+
+pub enum TrayCommand {
+    ShowWindow,
+    Refresh,
+    ToggleWatchMode,
+    Quit,
+}
+
+struct Tray {
+    icon: TrayIcon,
+    peek: Vec<MenuItem>,
+    show_window: MenuItem,
+    refresh: MenuItem,
+    toggle_watch_mode: MenuItem,
+    quit: MenuItem,
+}
+
+thread_local! {
+    static TRAY: RefCell<Option<Tray>> = const { RefCell::new(None) };
+}
+
+pub fn init() -> Result<(), Box<dyn Error>> {
+    let peek = (0..PEEK_LENGTH)
+        .map(|_| MenuItem::new("–", false, None))
+        .collect::<Vec<_>>();
+    let show_window = MenuItem::new("Show window", true, None);
+    let refresh = MenuItem::new("Refresh now", true, None);
+    let toggle_watch_mode = MenuItem::new("Toggle watch mode", true, None);
+    let quit = MenuItem::new("Quit", true, None);
+    let menu = Menu::new();
+    for item in &peek {
+        menu.append(item)?;
+    }
+    menu.append_items(&[
+        &PredefinedMenuItem::separator(),
+        &show_window,
+        &refresh,
+        &toggle_watch_mode,
+        &quit,
+    ])?;
+    let rgba = ICON_COLOR.repeat((ICON_SIZE * ICON_SIZE) as usize);
+    let icon = TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_tooltip(crate::WINDOW_TITLE)
+        .with_icon(Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE)?)
+        .build()?;
+    TRAY.with(|tray| {
+        *tray.borrow_mut() = Some(Tray {
+            icon,
+            peek,
+            show_window,
+            refresh,
+            toggle_watch_mode,
+            quit,
+        })
+    });
+    Ok(())
+}
+
+pub fn update(departures: &[Departure], now: DateTime<Local>) {
+    TRAY.with(|tray| {
+        let tray = tray.borrow();
+        let Some(tray) = tray.as_ref() else {
+            return;
+        };
+        let mut upcoming = departures
+            .iter()
+            .filter(|departure| !departure.cancelled && *departure.displayed_time() >= now);
+        let tooltip = match upcoming.clone().next() {
+            Some(departure) => format!(
+                "Next: {} in {} min",
+                departure.vehicle_label,
+                departure.minutes_until(now)
+            ),
+            None => "No services running".to_string(),
+        };
+        if let Err(e) = tray.icon.set_tooltip(Some(tooltip)) {
+            tracing::error!("Updating the tray tooltip failed: {e}");
+        }
+        for item in &tray.peek {
+            let text = upcoming.next().map_or_else(
+                || "–".to_string(),
+                |departure| {
+                    format!(
+                        "{} {} → {}",
+                        departure.displayed_time().format("%H:%M"),
+                        departure.vehicle_label,
+                        departure.destination
+                    )
+                },
+            );
+            item.set_text(text);
+        }
+    });
+}
+
+pub fn poll_command() -> Option<TrayCommand> {
+    if let Ok(event) = TrayIconEvent::receiver().try_recv() {
+        if event.click_type == ClickType::Left {
+            return Some(TrayCommand::ShowWindow);
+        }
+    }
+    let event = MenuEvent::receiver().try_recv().ok()?;
+    TRAY.with(|tray| {
+        let tray = tray.borrow();
+        let tray = tray.as_ref()?;
+        if event.id == *tray.show_window.id() {
+            Some(TrayCommand::ShowWindow)
+        } else if event.id == *tray.refresh.id() {
+            Some(TrayCommand::Refresh)
+        } else if event.id == *tray.toggle_watch_mode.id() {
+            Some(TrayCommand::ToggleWatchMode)
+        } else if event.id == *tray.quit.id() {
+            Some(TrayCommand::Quit)
+        } else {
+            None
+        }
+    })
+}
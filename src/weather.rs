@@ -0,0 +1,118 @@
+use serde::Deserialize;
+
+use crate::{MvgError, HTTP_CLIENT};
+
+const OPEN_METEO_URL: &str = "https://api.open-meteo.com/v1/forecast";
+// Open-Meteo only reports WMO weather codes, so the icons come from OpenWeatherMap's set
+const ICON_BASE_URL: &str = "https://openweathermap.org/img/wn";
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct WeatherData {
+    pub temperature_c: f32,
+    pub condition: String,
+    pub icon_url: String,
+}
+
+#[derive(Deserialize)]
+struct RawForecast {
+    current: RawCurrentWeather,
+}
+
+#[derive(Deserialize)]
+struct RawCurrentWeather {
+    #[serde(rename = "temperature_2m")]
+    temperature_c: f32,
+    weather_code: u8,
+    is_day: u8,
+}
+
+// WMO weather interpretation codes as documented by Open-Meteo, with the matching icon
+fn describe_weather_code(code: u8) -> (&'static str, &'static str) {
+    match code {
+        0 => ("Clear", "01"),
+        1 | 2 => ("Partly cloudy", "02"),
+        3 => ("Overcast", "04"),
+        45 | 48 => ("Fog", "50"),
+        51..=57 => ("Drizzle", "09"),
+        61..=67 | 80..=82 => ("Rain", "10"),
+        71..=77 | 85 | 86 => ("Snow", "13"),
+        95..=99 => ("Thunderstorm", "11"),
+        _ => ("Unknown", "03"),
+    }
+}
+
+fn parse_weather(body: &[u8]) -> Result<WeatherData, MvgError> {
+    let current = serde_json::from_slice::<RawForecast>(body)?.current;
+    let (condition, icon) = describe_weather_code(current.weather_code);
+    let time_of_day = if current.is_day == 0 { "n" } else { "d" };
+    Ok(WeatherData {
+        temperature_c: current.temperature_c,
+        condition: condition.to_string(),
+        icon_url: format!("{ICON_BASE_URL}/{icon}{time_of_day}@2x.png"),
+    })
+}
+
+pub async fn get_weather(lat: f64, lon: f64) -> Result<WeatherData, MvgError> {
+    let url = reqwest::Url::parse_with_params(
+        OPEN_METEO_URL,
+        [
+            ("latitude", lat.to_string()),
+            ("longitude", lon.to_string()),
+            ("current", "temperature_2m,weather_code,is_day".to_string()),
+        ],
+    )
+    .expect("The Open-Meteo URL is valid");
+    let response = HTTP_CLIENT.get(url).send().await?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(MvgError::RateLimited);
+    }
+    let body = response.error_for_status()?.bytes().await?;
+    parse_weather(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORECAST: &str = include_str!("../tests/fixtures/open_meteo.json");
+
+    #[test]
+    fn parses_the_current_weather() {
+        assert_eq!(
+            parse_weather(FORECAST.as_bytes()).unwrap(),
+            WeatherData {
+                temperature_c: 12.4,
+                condition: "Rain".to_string(),
+                icon_url: "https://openweathermap.org/img/wn/10d@2x.png".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn night_uses_the_night_icon() {
+        let night = FORECAST.replace("\"is_day\": 1", "\"is_day\": 0");
+        let weather = parse_weather(night.as_bytes()).unwrap();
+        assert_eq!(
+            weather.icon_url,
+            "https://openweathermap.org/img/wn/10n@2x.png"
+        );
+    }
+
+    #[test]
+    fn missing_current_weather_is_a_parse_error() {
+        let body = br#"{"latitude": 48.14, "longitude": 11.58}"#;
+        assert!(matches!(parse_weather(body), Err(MvgError::Parse(_))));
+    }
+
+    #[test]
+    fn weather_codes_follow_the_wmo_table() {
+        assert_eq!(describe_weather_code(0).0, "Clear");
+        assert_eq!(describe_weather_code(2).0, "Partly cloudy");
+        assert_eq!(describe_weather_code(48).0, "Fog");
+        assert_eq!(describe_weather_code(55).0, "Drizzle");
+        assert_eq!(describe_weather_code(81).0, "Rain");
+        assert_eq!(describe_weather_code(86).0, "Snow");
+        assert_eq!(describe_weather_code(96).0, "Thunderstorm");
+        assert_eq!(describe_weather_code(200).0, "Unknown");
+    }
+}